@@ -0,0 +1,36 @@
+#[cfg(test)]
+fn played_effects(ammunition: usize) -> Vec<(crate::audio::Effects, u64)> {
+  use specs::{Builder, prelude::RunNow, world::WorldExt};
+  use crate::audio::{AudioSystem, Effects, RecordingBackend};
+  use crate::character::{CharacterDrawable, controls::CharacterInputState};
+  use crate::graphics::GameTime;
+
+  let mut w = specs::World::new();
+  w.register::<CharacterInputState>();
+  w.register::<CharacterDrawable>();
+  w.insert(GameTime(500));
+
+  let mut ci = CharacterInputState::new();
+  ci.is_shooting = true;
+  let mut cd = CharacterDrawable::new(&crate::game::config::GameConfig::default());
+  cd.stats.ammunition = ammunition;
+  w.create_entity().with(ci).with(cd).build();
+
+  let (mut audio_system, audio_control) = AudioSystem::new(RecordingBackend::default());
+  audio_control.send(Effects::PistolFire).unwrap();
+  audio_system.run_now(&w);
+
+  audio_system.backend.played
+}
+
+#[test]
+fn firing_with_ammo_plays_pistol_test() {
+  use crate::audio::Effects;
+
+  assert_eq!(played_effects(10), vec![(Effects::PistolFire, 500)]);
+}
+
+#[test]
+fn firing_with_empty_magazine_is_silent_test() {
+  assert!(played_effects(0).is_empty(), "Empty magazine should not play a shot");
+}
@@ -1,51 +1,117 @@
-use std::{fs::File, io::BufReader};
+use std::{fs::File, io::{Cursor, Read}};
 
 use crossbeam_channel as channel;
 use rodio::Sink;
-use specs::prelude::ReadStorage;
+use specs::prelude::{Read as ReadResource, ReadStorage};
 
 use crate::character::{CharacterDrawable, controls::CharacterInputState};
 use crate::game::constants::PISTOL_AUDIO_PATH;
+use crate::game::error::GameError;
+use crate::graphics::GameTime;
 
-#[derive(Clone, Copy, PartialEq)]
+mod audio_test;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Effects {
   PistolFire,
   None,
 }
 
-pub struct AudioSystem {
-  effects: Effects,
+pub trait AudioBackend: Send {
+  fn play(&mut self, effect: Effects, game_time: u64);
+}
+
+impl AudioBackend for Box<dyn AudioBackend> {
+  fn play(&mut self, effect: Effects, game_time: u64) {
+    (**self).play(effect, game_time)
+  }
+}
+
+pub struct RodioBackend {
   sink: Sink,
+  pistol_data: Vec<u8>,
+}
+
+fn read_audio_file(filename: &str) -> Result<Vec<u8>, GameError> {
+  let mut file = File::open(filename).map_err(|e| GameError::io(filename, e))?;
+  let mut buf = Vec::new();
+  file.read_to_end(&mut buf).map_err(|e| GameError::io(filename, e))?;
+  rodio::Decoder::new(Cursor::new(buf.clone())).map_err(|e| GameError::parse(filename, e.to_string()))?;
+  Ok(buf)
+}
+
+impl RodioBackend {
+  pub fn new() -> Result<RodioBackend, GameError> {
+    let endpoint = rodio::default_output_device()
+      .ok_or_else(|| GameError::Device { context: "No audio output device found".to_string() })?;
+    let pistol_data = read_audio_file(PISTOL_AUDIO_PATH)?;
+
+    Ok(RodioBackend {
+      sink: Sink::new(&endpoint),
+      pistol_data,
+    })
+  }
+}
+
+impl AudioBackend for RodioBackend {
+  fn play(&mut self, effect: Effects, _game_time: u64) {
+    if effect == Effects::PistolFire && self.sink.empty() {
+      if let Ok(pistol) = rodio::Decoder::new(Cursor::new(self.pistol_data.clone())) {
+        self.sink.append(pistol);
+      }
+    }
+  }
+}
+
+/// Silent backend that keeps a log of played effects and their game time.
+#[derive(Default)]
+pub struct RecordingBackend {
+  pub played: Vec<(Effects, u64)>,
+}
+
+impl AudioBackend for RecordingBackend {
+  fn play(&mut self, effect: Effects, game_time: u64) {
+    self.played.push((effect, game_time));
+  }
+}
+
+/// Falls back to the silent backend on machines without an output device.
+pub fn default_backend() -> Result<Box<dyn AudioBackend>, GameError> {
+  match RodioBackend::new() {
+    Ok(backend) => Ok(Box::new(backend)),
+    Err(GameError::Device { context }) => {
+      println!("{}, audio disabled", context);
+      Ok(Box::new(RecordingBackend::default()))
+    }
+    Err(e) => Err(e),
+  }
+}
+
+pub struct AudioSystem<B: AudioBackend> {
+  effects: Effects,
+  pub backend: B,
   queue: channel::Receiver<Effects>,
 }
 
-impl AudioSystem {
-  pub fn new() -> (AudioSystem, channel::Sender<Effects>) {
+impl<B: AudioBackend> AudioSystem<B> {
+  pub fn new(backend: B) -> (AudioSystem<B>, channel::Sender<Effects>) {
     #[allow(deprecated)]
       let (tx, rx) = channel::unbounded();
-    let endpoint = rodio::default_output_device().unwrap();
 
     (AudioSystem {
       effects: Effects::None,
-      sink: Sink::new(&endpoint),
+      backend,
       queue: rx,
     }, tx)
   }
-
-  fn play_effect(&mut self) {
-    let file = File::open(PISTOL_AUDIO_PATH).unwrap();
-    let pistol_data = rodio::Decoder::new(BufReader::new(file)).unwrap();
-    if self.sink.empty() {
-      self.sink.append(pistol_data);
-    }
-  }
 }
 
-impl<'a> specs::prelude::System<'a> for AudioSystem {
+impl<'a, B: AudioBackend> specs::prelude::System<'a> for AudioSystem<B> {
   type SystemData = (ReadStorage<'a, CharacterInputState>,
-                     ReadStorage<'a, CharacterDrawable>);
+                     ReadStorage<'a, CharacterDrawable>,
+                     ReadResource<'a, GameTime>);
 
-  fn run(&mut self, (character_input, character_drawable): Self::SystemData) {
+  fn run(&mut self, (character_input, character_drawable, game_time): Self::SystemData) {
     use specs::join::Join;
 
     while let Ok(effect) = self.queue.try_recv() {
@@ -57,7 +123,7 @@ impl<'a> specs::prelude::System<'a> for AudioSystem {
 
     for (ci, cd) in (&character_input, &character_drawable).join() {
       if let Effects::PistolFire = self.effects {
-        if ci.is_shooting && cd.stats.ammunition > 0 { self.play_effect() }
+        if ci.is_shooting && cd.stats.ammunition > 0 { self.backend.play(Effects::PistolFire, game_time.0) }
       }
     }
   }
@@ -1,29 +1,15 @@
+use specs::{Builder, prelude::{Entities, LazyUpdate}};
 
-use crate::bullet::{BulletDrawable, collision::Collision};
+use crate::bullet::Bullet;
+use crate::game::components::WorldPosition;
 use crate::graphics::direction_movement;
 use crate::shaders::Position;
 
-pub struct Bullets {
-  pub bullets: Vec<BulletDrawable>,
-}
-
-impl Bullets {
-  pub fn new() -> Bullets {
-    Bullets {
-      bullets: Vec::new()
-    }
-  }
-
-  pub fn add_bullet(&mut self, position: Position, direction: f32) {
-    let movement_direction = direction_movement(direction);
-    self.bullets.push(BulletDrawable::new(position, movement_direction, direction));
-  }
-
-  pub fn remove_old_bullets(&mut self) {
-    self.bullets.retain(|ref mut b| b.status == Collision::Flying);
-  }
-}
-
-impl specs::prelude::Component for Bullets {
-  type Storage = specs::storage::VecStorage<Bullets>;
+pub fn add_bullet(entities: &Entities, lazy_update: &LazyUpdate, position: Position, direction: f32, speed: f32) {
+  let movement_direction = direction_movement(direction);
+  lazy_update.create_entity(entities)
+    .with(Bullet::new(direction))
+    .with(WorldPosition::new(position))
+    .with(Bullet::velocity(movement_direction, speed))
+    .build();
 }
@@ -1,6 +1,10 @@
-use specs::prelude::WriteStorage;
+use specs::prelude::{Entities, ReadExpect, ReadStorage, WriteExpect, WriteStorage};
 
-use crate::bullet::bullets::Bullets;
+use crate::bullet::Bullet;
+use crate::game::components::{Animation, Collider, Health, WorldPosition};
+use crate::game::GameRng;
+use crate::graphics::{can_move_to_tile, is_within_map, orientation::Stance};
+use crate::terrain::collision_grid::CollisionGrid;
 
 pub struct CollisionSystem;
 
@@ -11,14 +15,53 @@ pub enum Collision {
   OutOfBounds,
 }
 
+fn handle_bullet_hit(health: &mut Health, animation: &mut Animation, rng: &mut GameRng) {
+  health.0 -= 0.5;
+  if !health.is_alive() {
+    animation.stance =
+      if rng.get_random_bool() {
+        Stance::NormalDeath
+      } else {
+        Stance::CriticalDeath
+      };
+  }
+}
+
 impl<'a> specs::prelude::System<'a> for CollisionSystem {
-  type SystemData = WriteStorage<'a, Bullets>;
+  type SystemData = (Entities<'a>,
+                     WriteStorage<'a, Bullet>,
+                     ReadStorage<'a, WorldPosition>,
+                     ReadStorage<'a, Collider>,
+                     WriteStorage<'a, Health>,
+                     WriteStorage<'a, Animation>,
+                     WriteExpect<'a, GameRng>,
+                     ReadExpect<'a, CollisionGrid>);
 
-  fn run(&mut self, mut bullets: Self::SystemData) {
+  fn run(&mut self, (entities, mut bullets, positions, colliders, mut healths, mut animations, mut rng, grid): Self::SystemData) {
     use specs::join::Join;
 
-    for bs in (&mut bullets).join() {
-      Bullets::remove_old_bullets(bs);
+    for (b, p) in (&mut bullets, &positions).join() {
+      b.status = if !is_within_map(p.current, &grid) {
+        Collision::OutOfBounds
+      } else if !can_move_to_tile(p.current, &grid) {
+        Collision::Hit
+      } else {
+        Collision::Flying
+      }
+    }
+
+    for (c, p, h, a) in (&colliders, &positions, &mut healths, &mut animations).join() {
+      for (_, bp) in (&bullets, &positions).join() {
+        if c.hits(p.current, bp.current) && !a.is_dead() {
+          handle_bullet_hit(h, a, &mut rng);
+        }
+      }
+    }
+
+    for (e, b) in (&entities, &bullets).join() {
+      if b.status != Collision::Flying {
+        entities.delete(e).expect("Bullet removal failed");
+      }
     }
   }
 }
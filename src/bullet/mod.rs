@@ -1,18 +1,12 @@
-use std::f32;
 use std::f32::consts::PI;
 
 use cgmath::Point2;
-use specs::prelude::{Read, ReadStorage, WriteStorage};
 
-use crate::bullet::bullets::Bullets;
 use crate::bullet::collision::Collision;
-use crate::character::controls::CharacterInputState;
-use crate::game::constants::{ASPECT_RATIO, BULLET_SPEED, VIEW_DISTANCE};
+use crate::game::components::Velocity;
 use crate::gfx_app::{ColorFormat, DepthFormat};
-use crate::graphics::{camera::CameraInputState, can_move, dimensions::{Dimensions, get_projection, get_view_matrix}};
-use crate::graphics::can_move_to_tile;
 use crate::graphics::mesh::PlainMesh;
-use crate::shaders::{bullet_pipeline, Position, Projection, Rotation};
+use crate::shaders::{bullet_pipeline, Position, Projection, Rotation, ViewTranslation};
 
 pub mod bullets;
 pub mod collision;
@@ -22,63 +16,28 @@ const SHADER_FRAG: &[u8] = include_bytes!("../shaders/bullet.f.glsl");
 
 const SCALING_FACTOR: f32 = 5.0 / 3.0;
 
-#[derive(PartialEq)]
-pub struct BulletDrawable {
-  projection: Projection,
-  pub position: Position,
+pub struct Bullet {
   pub rotation: Rotation,
-  previous_position: Position,
-  offset_delta: Position,
-  pub movement_direction: Point2<f32>,
   pub status: collision::Collision,
 }
 
-impl BulletDrawable {
-  pub fn new(position: Position, movement_direction: Point2<f32>, direction: f32) -> BulletDrawable {
-    let view = get_view_matrix(VIEW_DISTANCE);
-    let projection = get_projection(view, ASPECT_RATIO);
-    let rotation = Rotation::new(direction * PI / 180.0);
-    BulletDrawable {
-      projection,
-      position,
-      rotation,
-      previous_position: Position::origin(),
-      offset_delta: Position::origin(),
-      movement_direction,
+impl Bullet {
+  pub fn new(direction: f32) -> Bullet {
+    Bullet {
+      rotation: Rotation::new(direction * PI / 180.0),
       status: Collision::Flying,
     }
   }
 
-  pub fn update(&mut self, world_to_clip: &Projection, ci: &CharacterInputState) {
-    self.projection = *world_to_clip;
-
-    self.offset_delta =
-      if (ci.movement.x() - self.previous_position.x()).abs() > f32::EPSILON ||
-        (ci.movement.y() - self.previous_position.y()).abs() > f32::EPSILON {
-        ci.movement - self.previous_position
-      } else {
-        self.offset_delta
-      };
-
-    self.previous_position = Position::new(
-      ci.movement.x() - (self.movement_direction.x * BULLET_SPEED / SCALING_FACTOR),
-      ci.movement.y() + (self.movement_direction.y * BULLET_SPEED));
-
-    self.position = self.position + self.offset_delta +
-      Position::new(self.movement_direction.x * BULLET_SPEED / SCALING_FACTOR, -self.movement_direction.y * BULLET_SPEED);
-
-    let tile_pos = ci.movement - self.position;
-
-    self.status = if !can_move(self.position) {
-      Collision::OutOfBounds
-    } else if !can_move_to_tile(tile_pos) {
-      Collision::Hit
-    } else {
-      Collision::Flying
-    }
+  pub fn velocity(movement_direction: Point2<f32>, speed: f32) -> Velocity {
+    Velocity(Position::new(movement_direction.x * speed / SCALING_FACTOR, -movement_direction.y * speed))
   }
 }
 
+impl specs::prelude::Component for Bullet {
+  type Storage = specs::storage::VecStorage<Bullet>;
+}
+
 pub struct BulletDrawSystem<R: gfx::Resources> {
   bundle: gfx::pso::bundle::Bundle<R, bullet_pipeline::Data<R>>,
 }
@@ -99,6 +58,7 @@ impl<R: gfx::Resources> BulletDrawSystem<R> {
       vbuf: mesh.vertex_buffer,
       projection_cb: factory.create_constant_buffer(1),
       position_cb: factory.create_constant_buffer(1),
+      view_cb: factory.create_constant_buffer(1),
       rotation_cb: factory.create_constant_buffer(1),
       out_color: rtv,
       out_depth: dsv,
@@ -109,34 +69,21 @@ impl<R: gfx::Resources> BulletDrawSystem<R> {
     }
   }
 
+  pub fn set_pipeline(&mut self, pso: gfx::PipelineState<R, bullet_pipeline::Meta>) {
+    self.bundle.pso = pso;
+  }
+
   pub fn draw<C>(&mut self,
-                 drawable: &BulletDrawable,
+                 position: &Position,
+                 bullet: &Bullet,
+                 projection: &Projection,
+                 view: &ViewTranslation,
                  encoder: &mut gfx::Encoder<R, C>)
     where C: gfx::CommandBuffer<R> {
-    encoder.update_constant_buffer(&self.bundle.data.projection_cb, &drawable.projection);
-    encoder.update_constant_buffer(&self.bundle.data.position_cb, &drawable.position);
-    encoder.update_constant_buffer(&self.bundle.data.rotation_cb, &drawable.rotation);
+    encoder.update_constant_buffer(&self.bundle.data.projection_cb, projection);
+    encoder.update_constant_buffer(&self.bundle.data.position_cb, position);
+    encoder.update_constant_buffer(&self.bundle.data.view_cb, view);
+    encoder.update_constant_buffer(&self.bundle.data.rotation_cb, &bullet.rotation);
     self.bundle.encode(encoder);
   }
 }
-
-pub struct PreDrawSystem;
-
-impl<'a> specs::prelude::System<'a> for PreDrawSystem {
-  type SystemData = (ReadStorage<'a, CameraInputState>,
-                     WriteStorage<'a, Bullets>,
-                     ReadStorage<'a, CharacterInputState>,
-                     Read<'a, Dimensions>);
-
-  fn run(&mut self, (camera_input, mut bullets, character_input, dim): Self::SystemData) {
-    use specs::join::Join;
-
-    for (camera, bs, ci) in (&camera_input, &mut bullets, &character_input).join() {
-      let world_to_clip = dim.world_to_projection(camera);
-
-      for b in &mut bs.bullets {
-        b.update(&world_to_clip, ci);
-      }
-    }
-  }
-}
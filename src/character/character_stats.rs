@@ -1,14 +1,34 @@
+use crate::game::constants::INVULNERABILITY_TIME;
+
 #[derive(Clone, Default)]
 pub struct CharacterStats {
   pub ammunition: usize,
   pub magazines: usize,
+  pub health: usize,
+  pub invulnerability: f64,
 }
 
 impl CharacterStats {
-  pub fn new() -> CharacterStats {
+  pub fn new(magazine_size: usize, health: usize) -> CharacterStats {
     CharacterStats {
-      ammunition: 10,
+      ammunition: magazine_size,
       magazines: 1,
+      health,
+      invulnerability: 0.0,
+    }
+  }
+
+  /// Takes `damage` unless still invulnerable from the previous hit. Returns whether the hit landed.
+  pub fn take_hit(&mut self, damage: usize) -> bool {
+    if self.invulnerability > 0.0 || self.health == 0 {
+      return false;
     }
+    self.health = self.health.saturating_sub(damage);
+    self.invulnerability = INVULNERABILITY_TIME;
+    true
+  }
+
+  pub fn update_invulnerability(&mut self, delta: f64) {
+    self.invulnerability = (self.invulnerability - delta).max(0.0);
   }
 }
@@ -0,0 +1,37 @@
+#[test]
+fn invulnerability_blocks_repeated_hits_test() {
+  use crate::character::character_stats::CharacterStats;
+  use crate::game::constants::{FIXED_TIMESTEP, INVULNERABILITY_TIME, PLAYER_HEALTH};
+
+  let mut stats = CharacterStats::new(10, PLAYER_HEALTH);
+  assert!(stats.take_hit(1));
+  assert!(!stats.take_hit(1), "Second hit should be absorbed while invulnerable");
+  assert_eq!(stats.health, PLAYER_HEALTH - 1);
+
+  let ticks = (INVULNERABILITY_TIME / FIXED_TIMESTEP) as u64 + 1;
+  for _ in 0..ticks {
+    stats.update_invulnerability(FIXED_TIMESTEP);
+  }
+  assert!(stats.take_hit(PLAYER_HEALTH));
+  assert_eq!(stats.health, 0, "Health should not underflow");
+}
+
+#[test]
+fn knockback_pushes_away_from_zombie_test() {
+  use crate::character::knockback;
+  use crate::game::constants::KNOCKBACK_DISTANCE;
+  use crate::graphics::coords_to_tile;
+  use crate::shaders::Position;
+  use crate::terrain::collision_grid::CollisionGrid;
+
+  let mut grid = CollisionGrid::new(128, 128);
+  let pushed = knockback(Position::origin(), Position::new(-10.0, 0.0), &grid);
+  assert_eq!(pushed.x(), KNOCKBACK_DISTANCE);
+  assert_eq!(pushed.y(), 0.0);
+
+  grid.set_blocked(coords_to_tile(pushed), true);
+  assert!(knockback(Position::origin(), Position::new(-10.0, 0.0), &grid) == Position::origin());
+
+  let pushed = knockback(Position::origin(), Position::new(0.0, 10.0), &grid);
+  assert!(pushed.y() < 0.0, "Zombie above should push the character down");
+}
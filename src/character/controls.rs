@@ -1,13 +1,14 @@
 use crossbeam_channel as channel;
-use specs::prelude::{Read, WriteStorage};
+use specs::prelude::{Read, ReadExpect, WriteStorage};
 
 use crate::character::CharacterDrawable;
-use crate::game::constants::{CHARACTER_X_SPEED, CHARACTER_Y_SPEED};
-use crate::graphics::{camera::CameraInputState, can_move_to_tile, DeltaTime, orientation::{Orientation, Stance}};
+use crate::game::config::GameConfig;
+use crate::graphics::{can_move_to_tile, DeltaTime, orientation::{Orientation, Stance}};
 use crate::shaders::Position;
+use crate::terrain::collision_grid::CollisionGrid;
 
 pub struct CharacterInputState {
-  pub movement: Position,
+  pub position: Position,
   pub orientation: Orientation,
   pub is_colliding: bool,
   pub is_shooting: bool,
@@ -16,53 +17,49 @@ pub struct CharacterInputState {
 impl CharacterInputState {
   pub fn new() -> CharacterInputState {
     CharacterInputState {
-      movement: Position::origin(),
+      position: Position::origin(),
       orientation: Orientation::Normal,
       is_colliding: false,
       is_shooting: false,
     }
   }
 
-  pub fn update(&mut self, camera: &mut CameraInputState, css: &CharacterControlSystem) {
+  pub fn update(&mut self, css: &CharacterControlSystem, grid: &CollisionGrid) {
     if css.y_move.is_none() && css.x_move.is_none() {
       self.orientation = Orientation::Normal;
     } else if css.x_move.is_none() {                  // Horizontal/vertical movement
       if let Some(y) = css.y_move {
-        let vertical_movement = self.movement + Position::new(0.0, y);
-        if !self.is_colliding || can_move_to_tile(vertical_movement) {
-          self.movement = vertical_movement;
-          camera.movement = camera.movement - Position::new(0.0, y);
+        let vertical_movement = self.position + Position::new(0.0, y);
+        if !self.is_colliding || can_move_to_tile(vertical_movement, grid) {
+          self.position = vertical_movement;
           self.orientation = match y {
-            y if y < 0.0 => Orientation::Up,
-            y if y > 0.0 => Orientation::Down,
+            y if y > 0.0 => Orientation::Up,
+            y if y < 0.0 => Orientation::Down,
             _ => Orientation::Normal,
           };
         }
       }
     } else if let Some(x) = css.x_move {        // Diagonal movement
-      let horizontal_move = self.movement + Position::new(x, 0.0);
+      let horizontal_move = self.position + Position::new(x, 0.0);
       if let Some(y) = css.y_move {
         let horizontal_movement = Position::new(x / 1.5, 0.0);
         let vertical_movement = Position::new(0.0, y / 1.666);
-        if !self.is_colliding || can_move_to_tile(self.movement + horizontal_movement + vertical_movement) {
-          self.movement = self.movement + horizontal_movement + vertical_movement;
-          camera.movement = camera.movement + horizontal_movement - vertical_movement;
+        if !self.is_colliding || can_move_to_tile(self.position + horizontal_movement + vertical_movement, grid) {
+          self.position = self.position + horizontal_movement + vertical_movement;
 
           self.orientation = match (x, y) {
-            (x, y) if x > 0.0 && y > 0.0 => Orientation::DownLeft,
-            (x, y) if x > 0.0 && y < 0.0 => Orientation::UpLeft,
-            (x, y) if x < 0.0 && y > 0.0 => Orientation::DownRight,
-            (x, y) if x < 0.0 && y < 0.0 => Orientation::UpRight,
+            (x, y) if x < 0.0 && y < 0.0 => Orientation::DownLeft,
+            (x, y) if x < 0.0 && y > 0.0 => Orientation::UpLeft,
+            (x, y) if x > 0.0 && y < 0.0 => Orientation::DownRight,
+            (x, y) if x > 0.0 && y > 0.0 => Orientation::UpRight,
             _ => Orientation::Normal,
           };
         }
-      } else if css.y_move.is_none() && !self.is_colliding || can_move_to_tile(horizontal_move) {
-        let horizontal_movement = Position::new(x, 0.0);
-        self.movement = self.movement + horizontal_movement;
-        camera.movement = camera.movement + horizontal_movement;
+      } else if css.y_move.is_none() && !self.is_colliding || can_move_to_tile(horizontal_move, grid) {
+        self.position = horizontal_move;
         self.orientation = match x {
-          x if x < 0.0 => Orientation::Right,
-          x if x > 0.0 => Orientation::Left,
+          x if x > 0.0 => Orientation::Right,
+          x if x < 0.0 => Orientation::Left,
           _ => Orientation::Normal,
         };
       }
@@ -81,6 +78,7 @@ impl specs::prelude::Component for CharacterInputState {
   type Storage = specs::storage::VecStorage<CharacterInputState>;
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum CharacterControl {
   Left,
   Right,
@@ -120,10 +118,11 @@ impl CharacterControlSystem {
 impl<'a> specs::prelude::System<'a> for CharacterControlSystem {
   type SystemData = (WriteStorage<'a, CharacterInputState>,
                      WriteStorage<'a, CharacterDrawable>,
-                     WriteStorage<'a, CameraInputState>,
-                     Read<'a, DeltaTime>);
+                     Read<'a, DeltaTime>,
+                     Read<'a, GameConfig>,
+                     ReadExpect<'a, CollisionGrid>);
 
-  fn run(&mut self, (mut character_input, mut character, mut camera_input, d): Self::SystemData) {
+  fn run(&mut self, (mut character_input, mut character, d, config, grid): Self::SystemData) {
     use specs::join::Join;
 
     let delta = d.0;
@@ -134,11 +133,11 @@ impl<'a> specs::prelude::System<'a> for CharacterControlSystem {
       self.cool_down = (self.cool_down - delta).max(0.0);
       while let Ok(control) = self.queue.try_recv() {
         match control {
-          CharacterControl::Up => self.y_move = Some(-CHARACTER_Y_SPEED),
-          CharacterControl::Down => self.y_move = Some(CHARACTER_Y_SPEED),
+          CharacterControl::Up => self.y_move = Some(config.character_y_speed),
+          CharacterControl::Down => self.y_move = Some(-config.character_y_speed),
           CharacterControl::YMoveStop => self.y_move = None,
-          CharacterControl::Right => self.x_move = Some(-CHARACTER_X_SPEED),
-          CharacterControl::Left => self.x_move = Some(CHARACTER_X_SPEED),
+          CharacterControl::Right => self.x_move = Some(config.character_x_speed),
+          CharacterControl::Left => self.x_move = Some(-config.character_x_speed),
           CharacterControl::XMoveStop => self.x_move = None,
           CharacterControl::CtrlPressed => self.is_ctrl_pressed = true,
           CharacterControl::CtrlReleased => self.is_ctrl_pressed = false,
@@ -147,12 +146,12 @@ impl<'a> specs::prelude::System<'a> for CharacterControlSystem {
         }
       }
 
-      for (ci, c, camera) in (&mut character_input, &mut character, &mut camera_input).join() {
+      for (ci, c) in (&mut character_input, &mut character).join() {
         if c.stance != Stance::NormalDeath {
-          ci.update(camera, self);
+          ci.update(self, &grid);
         }
-        if self.is_reloading && c.stats.magazines > 0 && c.stats.ammunition < 10 {
-          c.stats.ammunition = 10;
+        if self.is_reloading && c.stats.magazines > 0 && c.stats.ammunition < config.magazine_size {
+          c.stats.ammunition = config.magazine_size;
           c.stats.magazines -= 1;
         }
       }
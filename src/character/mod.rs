@@ -1,21 +1,26 @@
 use cgmath::Point2;
-use specs::prelude::{Read, ReadStorage, WriteStorage};
+use specs::prelude::{Entities, Read, ReadExpect, ReadStorage, WriteStorage};
 
 use crate::character::{character_stats::CharacterStats, controls::CharacterInputState};
 use crate::critter::{CharacterSprite, CritterData};
 use crate::data;
-use crate::game::constants::{AMMO_POSITIONS, ASPECT_RATIO, CHARACTER_SHEET_TOTAL_WIDTH, RUN_SPRITE_OFFSET, SPRITE_OFFSET, VIEW_DISTANCE, SMALL_HILLS};
+use crate::game::components::{Animation, WorldPosition};
+use crate::game::constants::{CHARACTER_SHEET_TOTAL_WIDTH, DEATH_ANIMATION_TIME, KNOCKBACK_DISTANCE, RUN_SPRITE_OFFSET, SPRITE_OFFSET};
+use crate::game::config::GameConfig;
+use crate::game::error::GameError;
+use crate::game::mouse_controls::MouseInputState;
 use crate::gfx_app::{ColorFormat, DepthFormat};
-use crate::gfx_app::mouse_controls::MouseInputState;
-use crate::graphics::{camera::CameraInputState, dimensions::{Dimensions, get_projection, get_view_matrix}, get_orientation_from_center, orientation::{Orientation, Stance}, overlaps, texture::load_texture, check_terrain_elevation};
+use crate::graphics::{camera::CameraInputState, can_move_to_tile, DeltaTime, dimensions::{Dimensions, get_projection, get_view_matrix}, get_orientation_from_center, interpolate, orientation::{Orientation, Stance}, overlaps, texture::load_texture, check_terrain_elevation};
 use crate::graphics::mesh::{RectangularTexturedMesh, Geometry};
 use crate::graphics::texture::Texture;
-use crate::shaders::{CharacterSheet, critter_pipeline, Position, Projection};
-use crate::terrain_object::{terrain_objects::TerrainObjects, TerrainObjectDrawable, TerrainTexture};
-use crate::zombie::{ZombieDrawable, zombies::Zombies};
+use crate::shaders::{CharacterSheet, critter_pipeline, Position, Projection, Tint, ViewTranslation};
+use crate::terrain::{collision_grid::CollisionGrid, map_objects::MapObjects};
+use crate::terrain_object::{TerrainObject, TerrainTexture};
+use crate::zombie::ZombieAi;
 
 pub mod controls;
 mod character_stats;
+mod character_test;
 
 const SHADER_VERT: &[u8] = include_bytes!("../shaders/character.v.glsl");
 const SHADER_FRAG: &[u8] = include_bytes!("../shaders/character.f.glsl");
@@ -25,52 +30,46 @@ pub struct CharacterDrawable {
   pub stats: CharacterStats,
   projection: Projection,
   pub position: Position,
+  last_tick_position: Position,
   orientation: Orientation,
   pub stance: Stance,
   direction: Orientation,
+  death_time: f64,
 }
 
 impl CharacterDrawable {
-  pub fn new() -> CharacterDrawable {
-    let view = get_view_matrix(VIEW_DISTANCE);
-    let projection = get_projection(view, ASPECT_RATIO);
-    let stats = CharacterStats::new();
+  pub fn new(config: &GameConfig) -> CharacterDrawable {
+    let view = get_view_matrix(config.view_distance);
+    let projection = get_projection(view, config.aspect_ratio());
+    let stats = CharacterStats::new(config.magazine_size, config.player_health);
     CharacterDrawable {
       stats,
       projection,
       position: Position::origin(),
+      last_tick_position: Position::origin(),
       orientation: Orientation::Right,
       stance: Stance::Walking,
       direction: Orientation::Right,
+      death_time: 0.0,
     }
   }
 
   pub fn update(&mut self, world_to_clip: &Projection, ci: &CharacterInputState, mouse_input: &MouseInputState,
-                dimensions: &Dimensions, objs: &mut Vec<TerrainObjectDrawable>, zombies: &[ZombieDrawable]) {
+                dimensions: &Dimensions, hills: &[Position], delta: f64) {
     self.projection = *world_to_clip;
+    self.last_tick_position = self.position;
 
-    self.position.position[1] = check_terrain_elevation(ci.movement - self.position, &SMALL_HILLS);
+    self.position = ci.position + Position::new(0.0, check_terrain_elevation(ci.position, hills));
 
-    fn zombie_not_dead(z: &ZombieDrawable) -> bool {
-      z.stance != Stance::NormalDeath &&
-        z.stance != Stance::CriticalDeath
+    self.stats.update_invulnerability(delta);
+    if self.stance == Stance::NormalDeath {
+      self.death_time += delta;
+      return;
     }
-
-    for idx in 0..AMMO_POSITIONS.len() {
-      self.ammo_pick_up(ci.movement, objs, idx);
-    }
-
-    if !cfg!(feature = "godmode") &&
-      zombies.iter()
-        .any(|z|
-          zombie_not_dead(z) &&
-            overlaps(ci.movement,
-                     ci.movement - z.position,
-                     15.0,
-                     30.0)) {
+    if self.stats.health == 0 {
       self.stance = Stance::NormalDeath;
-      println!("Player died");
-      std::process::exit(0);
+      self.death_time = 0.0;
+      return;
     }
 
     if ci.is_shooting && mouse_input.left_click_point.is_some() && !ci.is_colliding {
@@ -84,17 +83,45 @@ impl CharacterDrawable {
     }
   }
 
-  fn ammo_pick_up(&mut self, movement: Position, objs: &mut Vec<TerrainObjectDrawable>, idx: usize) {
-    if objs.len() > idx && objs[idx].object_type == TerrainTexture::Ammo && overlaps(movement, movement - objs[idx].position, 20.0, 20.0) {
+  /// True once the death animation has played out.
+  pub fn is_dead(&self) -> bool {
+    self.stance == Stance::NormalDeath && self.death_time >= DEATH_ANIMATION_TIME
+  }
+
+  /// The character flickers while invulnerable after a hit.
+  fn is_hidden(&self) -> bool {
+    self.stance != Stance::NormalDeath && (self.stats.invulnerability * 10.0) as u32 % 2 == 1
+  }
+
+  fn ammo_pick_up(&mut self, character_position: Position, object: &TerrainObject, object_position: Position) -> bool {
+    let is_picked_up = object.object_type == TerrainTexture::Ammo && overlaps(character_position, object_position, 20.0, 20.0);
+    if is_picked_up {
       self.stats.magazines = 2;
-      objs.remove(idx);
     }
+    is_picked_up
+  }
+}
+
+/// Pushes the character directly away from the zombie that hit it, unless that would end up inside an obstacle.
+pub fn knockback(position: Position, zombie_position: Position, grid: &CollisionGrid) -> Position {
+  let away = position - zombie_position;
+  let length = (away.x() * away.x() + away.y() * away.y()).sqrt();
+  let direction = if length > 0.0 {
+    Position::new(away.x() / length, away.y() / length)
+  } else {
+    Position::new(1.0, 0.0)
+  };
+  let target = position + Position::new(direction.x() * KNOCKBACK_DISTANCE, direction.y() * KNOCKBACK_DISTANCE);
+  if can_move_to_tile(target, grid) {
+    target
+  } else {
+    position
   }
 }
 
 impl Default for CharacterDrawable {
   fn default() -> Self {
-    CharacterDrawable::new()
+    CharacterDrawable::new(&GameConfig::default())
   }
 }
 
@@ -110,7 +137,7 @@ pub struct CharacterDrawSystem<R: gfx::Resources> {
 impl<R: gfx::Resources> CharacterDrawSystem<R> {
   pub fn new<F>(factory: &mut F,
                 rtv: gfx::handle::RenderTargetView<R, ColorFormat>,
-                dsv: gfx::handle::DepthStencilView<R, DepthFormat>) -> CharacterDrawSystem<R>
+                dsv: gfx::handle::DepthStencilView<R, DepthFormat>) -> Result<CharacterDrawSystem<R>, GameError>
     where F: gfx::Factory<R> {
     use gfx::traits::FactoryExt;
 
@@ -127,18 +154,28 @@ impl<R: gfx::Resources> CharacterDrawSystem<R> {
       vbuf: rect_mesh.mesh.vertex_buffer,
       projection_cb: factory.create_constant_buffer(1),
       position_cb: factory.create_constant_buffer(1),
+      view_cb: factory.create_constant_buffer(1),
       character_sprite_cb: factory.create_constant_buffer(1),
+      tint_cb: factory.create_constant_buffer(1),
       charactersheet: (rect_mesh.mesh.texture.raw, factory.create_sampler_linear()),
       out_color: rtv,
       out_depth: dsv,
     };
 
-    let data = data::load_character();
+    let data = data::load_character()?;
 
-    CharacterDrawSystem {
+    Ok(CharacterDrawSystem {
       bundle: gfx::Bundle::new(rect_mesh.mesh.slice, pso, pipeline_data),
       data,
-    }
+    })
+  }
+
+  pub fn set_pipeline(&mut self, pso: gfx::PipelineState<R, critter_pipeline::Meta>) {
+    self.bundle.pso = pso;
+  }
+
+  pub fn set_sprite_data(&mut self, data: Vec<CritterData>) {
+    self.data = data;
   }
 
   fn get_next_sprite(&self, character_idx: usize, character_fire_idx: usize, drawable: &mut CharacterDrawable) -> CharacterSheet {
@@ -164,10 +201,17 @@ impl<R: gfx::Resources> CharacterDrawSystem<R> {
   pub fn draw<C>(&mut self,
                  mut drawable: &mut CharacterDrawable,
                  character: &CharacterSprite,
+                 view: &ViewTranslation,
+                 alpha: f32,
                  encoder: &mut gfx::Encoder<R, C>)
     where C: gfx::CommandBuffer<R> {
+    if drawable.is_hidden() {
+      return;
+    }
     encoder.update_constant_buffer(&self.bundle.data.projection_cb, &drawable.projection);
-    encoder.update_constant_buffer(&self.bundle.data.position_cb, &drawable.position);
+    encoder.update_constant_buffer(&self.bundle.data.position_cb, &interpolate(drawable.last_tick_position, drawable.position, alpha));
+    encoder.update_constant_buffer(&self.bundle.data.view_cb, view);
+    encoder.update_constant_buffer(&self.bundle.data.tint_cb, &Tint { color: [1.0, 1.0, 1.0, 1.0] });
     encoder.update_constant_buffer(&self.bundle.data.character_sprite_cb,
                                    &self.get_next_sprite(character.character_idx,
                                                          character.character_fire_idx,
@@ -179,21 +223,44 @@ impl<R: gfx::Resources> CharacterDrawSystem<R> {
 pub struct PreDrawSystem;
 
 impl<'a> specs::prelude::System<'a> for PreDrawSystem {
-  type SystemData = (WriteStorage<'a, CharacterDrawable>,
+  type SystemData = (Entities<'a>,
+                     WriteStorage<'a, CharacterDrawable>,
                      ReadStorage<'a, CameraInputState>,
-                     ReadStorage<'a, CharacterInputState>,
+                     WriteStorage<'a, CharacterInputState>,
                      ReadStorage<'a, MouseInputState>,
-                     WriteStorage<'a, TerrainObjects>,
-                     ReadStorage<'a, Zombies>,
-                     Read<'a, Dimensions>);
+                     ReadStorage<'a, TerrainObject>,
+                     ReadStorage<'a, ZombieAi>,
+                     ReadStorage<'a, Animation>,
+                     ReadStorage<'a, WorldPosition>,
+                     Read<'a, Dimensions>,
+                     Read<'a, DeltaTime>,
+                     Read<'a, GameConfig>,
+                     ReadExpect<'a, CollisionGrid>,
+                     ReadExpect<'a, MapObjects>);
 
-  fn run(&mut self, (mut character, camera_input, character_input, mouse_input, mut terrain_objects, zombies, dim): Self::SystemData) {
+  fn run(&mut self, (entities, mut character, camera_input, mut character_input, mouse_input, terrain_objects, zombies, animations, positions, dim, dt, config, grid, map_objects): Self::SystemData) {
     use specs::join::Join;
 
-    for (c, camera, ci, mi, to, zs) in
-        (&mut character, &camera_input, &character_input, &mouse_input, &mut terrain_objects, &zombies).join() {
+    for (c, camera, ci, mi) in (&mut character, &camera_input, &mut character_input, &mouse_input).join() {
       let world_to_clip = dim.world_to_projection(camera);
-      c.update(&world_to_clip, ci, mi, &dim, &mut to.objects, &zs.zombies);
+
+      for (e, o, p) in (&entities, &terrain_objects, &positions).join() {
+        if c.ammo_pick_up(ci.position, o, p.current) {
+          entities.delete(e).expect("Ammo pick up failed");
+        }
+      }
+
+      let hit_by = (&zombies, &animations, &positions).join()
+        .find(|(_, a, p)| !a.is_dead() && overlaps(ci.position, p.current, 15.0, 30.0))
+        .map(|(z, _, p)| (p.current, config.zombie_archetype(z.archetype).damage));
+
+      if let Some((zombie_position, damage)) = hit_by {
+        if !cfg!(feature = "godmode") && c.stats.take_hit(damage) {
+          ci.position = knockback(ci.position, zombie_position, &grid);
+        }
+      }
+
+      c.update(&world_to_clip, ci, mi, &dim, &map_objects.hills, dt.0);
     }
   }
 }
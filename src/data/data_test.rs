@@ -0,0 +1,26 @@
+#[test]
+fn missing_sprite_file_test() {
+  use crate::data::read_sprite_file;
+  use crate::game::error::GameError;
+
+  match read_sprite_file("assets/does_not_exist.json") {
+    Err(GameError::Io { path, .. }) => assert_eq!(path, "assets/does_not_exist.json"),
+    _ => panic!("Missing sprite file should be an Io error"),
+  }
+}
+
+#[test]
+fn missing_frame_key_test() {
+  use crate::data::get_frame_data;
+  use crate::game::error::GameError;
+
+  let sprite = json::parse(r#"{ "frames": { "run_0_0": { "frame": { "x": 1, "y": 2, "w": 3 } } } }"#).unwrap();
+
+  match get_frame_data(&sprite, "run_0_0", "assets/character.json") {
+    Err(GameError::MissingData { path, context }) => {
+      assert_eq!(path, "assets/character.json");
+      assert_eq!(context, "frames.run_0_0.frame.h");
+    }
+    _ => panic!("Frame without height should be a MissingData error"),
+  }
+}
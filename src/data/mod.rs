@@ -4,114 +4,111 @@ use json::JsonValue;
 use tiled::Map;
 
 use crate::critter::CritterData;
-use crate::game::constants::{CHARACTER_BUF_LENGTH, CHARACTER_JSON_PATH, ZOMBIE_JSON_PATH};
+use crate::game::constants::{CHARACTER_BUF_LENGTH, CHARACTER_JSON_PATH, TERRAIN_TILESET_PATH, ZOMBIE_JSON_PATH};
+use crate::game::error::GameError;
+use crate::terrain::generator;
+use crate::zombie::archetypes::{parse_archetypes, ZombieArchetype};
 
-pub fn load_map_file(filename: &str) -> Map {
-  let file = match File::open(&Path::new(&filename)) {
-    Ok(f) => f,
-    Err(e) => panic!("File {} not found: {}", filename, e),
-  };
+mod data_test;
+
+pub fn load_map_file(filename: &str) -> Result<Map, GameError> {
+  let file = File::open(filename).map_err(|e| GameError::io(filename, e))?;
   let reader = BufReader::new(file);
-  match tiled::parse(reader) {
-    Ok(m) => m,
-    Err(e) => panic!("Map parse error {:?}", e)
-  }
+  // With the path Tiled can also load external tilesets next to the map.
+  tiled::parse_with_path(reader, Path::new(filename)).map_err(|e| GameError::parse(filename, format!("{:?}", e)))
 }
 
-pub fn get_map_tile(map: &Map, layer_index: usize, x: usize, y: usize) -> u32 {
-  let layer = match map.layers.get(layer_index) {
-    None => panic!("Layer_index value out of index {:?}", map.layers),
-    Some(l) => l
-  };
-  let y_index = match layer.tiles.iter().rev().nth(y) {
-    None => panic!("X value out of index {:?}", map.layers[0]),
-    Some(x) => x
-  };
-  match y_index.get(x) {
-    None => panic!("Y value out of index {:?}", layer.tiles[y]),
-    Some(val) => *val
+/// Reads a campaign map, generating it when the name is a generated map name.
+pub fn load_map(name: &str) -> Result<Map, GameError> {
+  match generator::parse_map_name(name) {
+    Some(generated) => {
+      let (seed, width, height) = generated?;
+      let tmx = generator::generate(seed, width, height).to_tmx(TERRAIN_TILESET_PATH);
+      tiled::parse(tmx.as_bytes()).map_err(|e| GameError::parse(name, format!("{:?}", e)))
+    }
+    None => load_map_file(name),
   }
 }
 
-fn read_sprite_file(filename: &str) -> String {
-  let path = Path::new(&filename);
-  let mut file = match File::open(&path) {
-    Ok(f) => f,
-    Err(e) => panic!("File {} not found: {}", filename, e),
-  };
+pub fn get_map_tile(map: &Map, layer_index: usize, x: usize, y: usize) -> Option<u32> {
+  map.layers.get(layer_index)
+    .and_then(|layer| layer.tiles.iter().rev().nth(y))
+    .and_then(|row| row.get(x))
+    .cloned()
+}
+
+pub(crate) fn read_sprite_file(filename: &str) -> Result<String, GameError> {
+  let mut file = File::open(filename).map_err(|e| GameError::io(filename, e))?;
   let mut buf = String::new();
-  match file.read_to_string(&mut buf) {
-    Ok(_) => buf,
-    Err(e) => panic!("read file {} error {}", filename, e),
-  }
+  file.read_to_string(&mut buf).map_err(|e| GameError::io(filename, e))?;
+  Ok(buf)
+}
+
+pub(crate) fn get_frame_data(character: &JsonValue, key: &str, filename: &str) -> Result<CritterData, GameError> {
+  let frame = &character["frames"][key]["frame"];
+  let value = |field: &str| frame[field].as_f32()
+    .ok_or_else(|| GameError::missing_data(filename, format!("frames.{}.frame.{}", key, field)));
+  Ok(CritterData::new([value("x")?, value("y")?, value("w")?, value("h")?]))
 }
 
-fn get_frame_data(character: &JsonValue, key: &str) -> CritterData {
-  CritterData::new([
-    character["frames"][key]["frame"]["x"].as_f32().unwrap(),
-    character["frames"][key]["frame"]["y"].as_f32().unwrap(),
-    character["frames"][key]["frame"]["w"].as_f32().unwrap(),
-    character["frames"][key]["frame"]["h"].as_f32().unwrap(),
-  ])
+fn parse_sprite_file(filename: &str) -> Result<JsonValue, GameError> {
+  let sprite_json = read_sprite_file(filename)?;
+  json::parse(&sprite_json).map_err(|e| GameError::parse(filename, e.to_string()))
 }
 
-pub fn load_character() -> Vec<CritterData> {
+pub fn load_character() -> Result<Vec<CritterData>, GameError> {
   let mut sprites = Vec::with_capacity(CHARACTER_BUF_LENGTH + 64);
-  let character_json = read_sprite_file(CHARACTER_JSON_PATH);
-  let character = match json::parse(&character_json) {
-    Ok(res) => res,
-    Err(e) => panic!("Character {} parse error {:?}", CHARACTER_JSON_PATH, e),
-  };
+  let character = parse_sprite_file(CHARACTER_JSON_PATH)?;
 
   for x in 0..16 {
     for y in 0..14 {
       let key = &format!("run_{}_{}", x, y);
-      sprites.push(get_frame_data(&character, key));
+      sprites.push(get_frame_data(&character, key, CHARACTER_JSON_PATH)?);
     }
   }
 
   for x in 0..15 {
     for y in 0..4 {
       let key = &format!("fire_{}_{}", x, y);
-      sprites.push(get_frame_data(&character, key));
+      sprites.push(get_frame_data(&character, key, CHARACTER_JSON_PATH)?);
     }
   }
 
-  sprites
+  Ok(sprites)
+}
+
+pub fn load_zombie_archetypes(filename: &str) -> Result<Vec<ZombieArchetype>, GameError> {
+  parse_archetypes(&parse_sprite_file(filename)?, filename)
 }
 
-pub fn load_zombie() -> Vec<CritterData> {
+pub fn load_zombie() -> Result<Vec<CritterData>, GameError> {
   let mut sprites = Vec::with_capacity(256);
-  let zombie_json = read_sprite_file(ZOMBIE_JSON_PATH);
-  let zombie = match json::parse(&zombie_json) {
-    Ok(res) => res,
-    Err(e) => panic!("Zombie {} parse error {:?}", ZOMBIE_JSON_PATH, e),
-  };
+  let zombie = parse_sprite_file(ZOMBIE_JSON_PATH)?;
 
   for x in 0..7 {
     for y in 0..7 {
       let key = &format!("critical_{}_{}", x, y);
-      sprites.push(get_frame_data(&zombie, key));
+      sprites.push(get_frame_data(&zombie, key, ZOMBIE_JSON_PATH)?);
     }
   }
   for x in 0..7 {
     for y in 0..5 {
       let key = &format!("normal_{}_{}", x, y);
-      sprites.push(get_frame_data(&zombie, key));
+      sprites.push(get_frame_data(&zombie, key, ZOMBIE_JSON_PATH)?);
     }
   }
   for x in 0..7 {
     for y in 0..4 {
       let key = &format!("still_{}_{}", x, y);
-      sprites.push(get_frame_data(&zombie, key));
+      sprites.push(get_frame_data(&zombie, key, ZOMBIE_JSON_PATH)?);
     }
   }
 
   for x in 0..7 {
     for y in 0..7 {
       let key = &format!("walk_{}_{}", x, y);
-      sprites.push(get_frame_data(&zombie, key));
+      sprites.push(get_frame_data(&zombie, key, ZOMBIE_JSON_PATH)?);
     }
   }
-  sprites
+  Ok(sprites)
 }
@@ -0,0 +1,75 @@
+#[test]
+fn editor_paints_and_places_objects_test() {
+  use specs::{join::Join, world::WorldExt};
+  use crate::editor::{EditorBrush, EditorControl, EditorTool, MapEditor};
+  use crate::game::campaign::Campaign;
+  use crate::game::config::GameConfig;
+  use crate::game::constants::{RESOLUTION_X, RESOLUTION_Y};
+  use crate::game::simulation::setup_world;
+  use crate::graphics::{coords_to_tile, dimensions::Dimensions};
+  use crate::terrain::{map_objects::MapObjects, tile_map::Terrain};
+  use crate::terrain_object::{TerrainObject, TerrainTexture};
+
+  let mut w = specs::World::new();
+  setup_world(&mut w, Dimensions::new(RESOLUTION_X as f32, RESOLUTION_Y as f32, 1.0, true), 3, &GameConfig::default(), &Campaign::default()).unwrap();
+  let houses = |w: &specs::World| w.read_storage::<TerrainObject>().join().filter(|o| o.object_type == TerrainTexture::House).count();
+  let house_count = houses(&w);
+  let (mut editor, editor_control) = MapEditor::new();
+  editor.open(&w).unwrap();
+
+  // The camera starts on the player spawn, so the middle of the window is the spawn tile
+  let spawn = coords_to_tile(w.read_resource::<MapObjects>().player_spawn);
+  let idx = spawn.y as usize * w.read_resource::<Terrain>().width + spawn.x as usize;
+  let painted = w.read_resource::<Terrain>().layers[0].tiles[idx];
+  editor.brush = u32::from(painted);
+  w.write_resource::<Terrain>().is_dirty = false;
+  for control in &[EditorControl::Cursor(f64::from(RESOLUTION_X) / 2.0, f64::from(RESOLUTION_Y) / 2.0), EditorControl::NextTile, EditorControl::Apply(true)] {
+    editor_control.send(*control).unwrap();
+  }
+  editor.update(&mut w, 0.0);
+  assert_eq!(*w.read_resource::<EditorBrush>(), EditorBrush(editor.brush));
+  let terrain = w.read_resource::<Terrain>();
+  assert_ne!(terrain.layers[0].tiles[idx], painted);
+  assert_eq!(u32::from(terrain.layers[0].tiles[idx]), editor.brush);
+  assert!(terrain.is_dirty);
+  drop(terrain);
+
+  for control in &[EditorControl::Apply(false), EditorControl::NextTool, EditorControl::Cursor(f64::from(RESOLUTION_X) * 0.75, f64::from(RESOLUTION_Y) / 2.0),
+    EditorControl::Apply(true)] {
+    editor_control.send(*control).unwrap();
+  }
+  editor.update(&mut w, 0.0);
+  assert_eq!(editor.tool, EditorTool::House);
+  assert_eq!(houses(&w), house_count + 1);
+
+  // Removing the only player spawn would leave the map unplayable
+  for control in &[EditorControl::Apply(false), EditorControl::Cursor(f64::from(RESOLUTION_X) / 2.0, f64::from(RESOLUTION_Y) / 2.0), EditorControl::Erase(true)] {
+    editor_control.send(*control).unwrap();
+  }
+  editor.update(&mut w, 0.0);
+  assert_eq!(coords_to_tile(w.read_resource::<MapObjects>().player_spawn), spawn);
+  assert_eq!(houses(&w), house_count + 1);
+}
+
+#[test]
+fn editor_needs_an_opened_map_matching_the_terrain_test() {
+  use specs::world::WorldExt;
+  use crate::editor::MapEditor;
+  use crate::game::campaign::Campaign;
+  use crate::game::config::GameConfig;
+  use crate::game::constants::{RESOLUTION_X, RESOLUTION_Y};
+  use crate::game::simulation::setup_world;
+  use crate::graphics::dimensions::Dimensions;
+  use crate::terrain::tile_map::{Terrain, TileLayer};
+
+  let mut w = specs::World::new();
+  setup_world(&mut w, Dimensions::new(RESOLUTION_X as f32, RESOLUTION_Y as f32, 1.0, true), 3, &GameConfig::default(), &Campaign::default()).unwrap();
+  let (mut editor, _editor_control) = MapEditor::new();
+  assert!(editor.save().is_err(), "Nothing to save before the editor is opened");
+
+  let extra_layer = TileLayer { tiles: vec![0; w.read_resource::<Terrain>().layers[0].tiles.len()], opacity: 1.0 };
+  w.write_resource::<Terrain>().layers.push(extra_layer);
+  assert!(editor.open(&w).is_err(), "Map file layers no longer match the terrain");
+  w.write_resource::<Terrain>().layers.pop();
+  assert!(editor.open(&w).is_ok());
+}
@@ -0,0 +1,304 @@
+use cgmath::Point2;
+use crossbeam_channel as channel;
+use specs::{shred::World, world::WorldExt};
+
+use crate::data;
+use crate::game::campaign::Campaign;
+use crate::game::config::GameConfig;
+use crate::game::constants::{EDITOR_PAN_SPEED, EDITOR_ZOOM_SPEED};
+use crate::game::error::GameError;
+use crate::game::simulation::replace_map_objects;
+use crate::graphics::{camera::CameraInputState, coords_to_tile, dimensions::Dimensions, screen_to_world};
+use crate::shaders::Position;
+use crate::terrain::generator;
+use crate::terrain::map_document::{MapDocument, Placeable};
+use crate::terrain::map_objects::MapObjects;
+use crate::terrain::tile_map::Terrain;
+
+mod editor_test;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EditorTool {
+  Paint,
+  House,
+  Tree,
+  Hill,
+  Ammo,
+  PlayerSpawn,
+  ZombieSpawn,
+}
+
+pub const EDITOR_TOOLS: [EditorTool; 7] = [EditorTool::Paint, EditorTool::House, EditorTool::Tree, EditorTool::Hill,
+  EditorTool::Ammo, EditorTool::PlayerSpawn, EditorTool::ZombieSpawn];
+
+impl EditorTool {
+  pub fn next(self) -> EditorTool {
+    let idx = EDITOR_TOOLS.iter().position(|tool| *tool == self).unwrap_or(0);
+    EDITOR_TOOLS[(idx + 1) % EDITOR_TOOLS.len()]
+  }
+
+  fn placeable(self) -> Option<Placeable> {
+    match self {
+      EditorTool::Paint => None,
+      EditorTool::House => Some(Placeable::House),
+      EditorTool::Tree => Some(Placeable::Tree),
+      EditorTool::Hill => Some(Placeable::Hill),
+      EditorTool::Ammo => Some(Placeable::Ammo),
+      EditorTool::PlayerSpawn => Some(Placeable::PlayerSpawn),
+      EditorTool::ZombieSpawn => Some(Placeable::ZombieSpawn),
+    }
+  }
+
+  /// Shown over the map while the tool is selected, `{erase}` and `{save}` name the bound inputs.
+  pub fn label(self) -> &'static str {
+    match self {
+      EditorTool::Paint => "Editor: paint tiles - {erase} picks, {save} saves",
+      EditorTool::House => "Editor: place houses - {erase} removes, {save} saves",
+      EditorTool::Tree => "Editor: place trees - {erase} removes, {save} saves",
+      EditorTool::Hill => "Editor: place hills - {erase} removes, {save} saves",
+      EditorTool::Ammo => "Editor: place ammo - {erase} removes, {save} saves",
+      EditorTool::PlayerSpawn => "Editor: move player spawn - {save} saves",
+      EditorTool::ZombieSpawn => "Editor: place zombie spawns - {erase} removes, {save} saves",
+    }
+  }
+}
+
+/// Global tile id the paint tool puts down, shown under the tool label.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EditorBrush(pub u32);
+
+pub fn brush_text(brush: EditorBrush) -> String {
+  format!("Brush tile {}", brush.0)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EditorControl {
+  Up,
+  Down,
+  Left,
+  Right,
+  XMoveStop,
+  YMoveStop,
+  ZoomIn,
+  ZoomOut,
+  ZoomStop,
+  Cursor(f64, f64),
+  /// Paints or places with the current tool while held.
+  Apply(bool),
+  /// Picks the tile under the cursor as the brush, or removes objects while held.
+  Erase(bool),
+  NextTool,
+  NextTile,
+  PreviousTile,
+}
+
+/// The map being edited and where it is saved.
+struct EditedMap {
+  name: String,
+  save_path: String,
+  document: MapDocument,
+  /// Document layer that is painted, the bottom visible one.
+  layer: usize,
+  /// Every global tile id of the map's tilesets, in order.
+  palette: Vec<u32>,
+}
+
+/// Paints tiles and places objects on the current map while the game is in `GameState::Editing`.
+/// Tile changes show up through `Terrain::is_dirty`, object changes rebuild the map objects of the world.
+pub struct MapEditor {
+  queue: channel::Receiver<EditorControl>,
+  map: Option<EditedMap>,
+  pub tool: EditorTool,
+  pub brush: u32,
+  cursor: (f64, f64),
+  applying: bool,
+  erasing: bool,
+  pan: (f32, f32),
+  zoom: f32,
+  last_tile: Option<Point2<i32>>,
+  tiles_changed: bool,
+}
+
+impl MapEditor {
+  pub fn new() -> (MapEditor, channel::Sender<EditorControl>) {
+    let (tx, rx) = channel::unbounded();
+    (MapEditor {
+      queue: rx,
+      map: None,
+      tool: EditorTool::Paint,
+      brush: 0,
+      cursor: (0.0, 0.0),
+      applying: false,
+      erasing: false,
+      pan: (0.0, 0.0),
+      zoom: 0.0,
+      last_tile: None,
+      tiles_changed: false,
+    }, tx)
+  }
+
+  /// Starts editing the current campaign map. Unsaved edits are kept when reopening the same map.
+  /// The map file has to have the tile layers the game is showing.
+  pub fn open(&mut self, world: &World) -> Result<(), GameError> {
+    let name = world.read_resource::<Campaign>().current_map().to_string();
+    if self.map.as_ref().map(|map| map.name.as_str()) == Some(name.as_str()) {
+      return Ok(());
+    }
+    let document = MapDocument::from_map(&data::load_map(&name)?, &name)?;
+    let layer = document.layers.iter().position(|layer| layer.visible)
+      .ok_or_else(|| GameError::missing_data(&name, "a visible tile layer".to_string()))?;
+    let terrain = world.read_resource::<Terrain>();
+    let visible_layers = document.layers.iter().filter(|layer| layer.visible).count();
+    if (document.width, document.height, visible_layers) != (terrain.width, terrain.height, terrain.layers.len()) {
+      return Err(GameError::parse(&name, "map file has changed since the game loaded it, restart to edit it".to_string()));
+    }
+    drop(terrain);
+    let palette = document.tilesets.iter()
+      .flat_map(|tileset| tileset.first_gid..tileset.first_gid + tileset.tile_count())
+      .collect::<Vec<_>>();
+    let save_path = match generator::parse_map_name(&name) {
+      Some(_) => format!("{}.tmx", name.replace(':', "_")),
+      None => name.clone(),
+    };
+    self.brush = match document.tile(layer, document.width / 2, document.height / 2) {
+      0 => palette.first().cloned().unwrap_or(0),
+      gid => gid,
+    };
+    self.map = Some(EditedMap { name, save_path, document, layer, palette });
+    Ok(())
+  }
+
+  /// Name of the map open in the editor, kept after the editor is closed.
+  pub fn map_name(&self) -> Option<&str> {
+    self.map.as_ref().map(|map| map.name.as_str())
+  }
+
+  /// Writes the edited map, returns the path it was written to.
+  pub fn save(&self) -> Result<String, GameError> {
+    let map = self.map.as_ref()
+      .ok_or_else(|| GameError::missing_data("Map editor", "an opened map".to_string()))?;
+    map.document.save(&map.save_path)?;
+    Ok(map.save_path.clone())
+  }
+
+  /// Handles queued controls, moves the camera and edits the tile under the cursor.
+  pub fn update(&mut self, world: &mut World, frame_time: f64) {
+    while let Ok(control) = self.queue.try_recv() {
+      match control {
+        EditorControl::Up => self.pan.1 = 1.0,
+        EditorControl::Down => self.pan.1 = -1.0,
+        EditorControl::Left => self.pan.0 = -1.0,
+        EditorControl::Right => self.pan.0 = 1.0,
+        EditorControl::XMoveStop => self.pan.0 = 0.0,
+        EditorControl::YMoveStop => self.pan.1 = 0.0,
+        EditorControl::ZoomIn => self.zoom = -1.0,
+        EditorControl::ZoomOut => self.zoom = 1.0,
+        EditorControl::ZoomStop => self.zoom = 0.0,
+        EditorControl::Cursor(x, y) => self.cursor = (x, y),
+        EditorControl::Apply(pressed) => {
+          self.applying = pressed;
+          self.last_tile = None;
+          if !pressed && self.tiles_changed {
+            self.refresh(world, None);
+          }
+        }
+        EditorControl::Erase(pressed) => {
+          self.erasing = pressed;
+          self.last_tile = None;
+        }
+        EditorControl::NextTool => self.tool = self.tool.next(),
+        EditorControl::NextTile => self.step_brush(1),
+        EditorControl::PreviousTile => self.step_brush(-1),
+      }
+    }
+
+    let tile = self.move_camera(world, frame_time as f32);
+    if (self.applying || self.erasing) && self.last_tile != Some(tile) {
+      self.last_tile = Some(tile);
+      self.edit(world, tile);
+    }
+    world.insert(EditorBrush(self.brush));
+  }
+
+  fn step_brush(&mut self, step: i32) {
+    let map = match self.map.as_ref() {
+      Some(map) if !map.palette.is_empty() => map,
+      _ => return,
+    };
+    let idx = map.palette.iter().position(|gid| *gid == self.brush).unwrap_or(0) as i32;
+    let len = map.palette.len() as i32;
+    self.brush = map.palette[((idx + step) % len + len) as usize % len as usize];
+  }
+
+  /// Pans and zooms the camera, returns the game tile under the cursor.
+  fn move_camera(&mut self, world: &mut World, frame_time: f32) -> Point2<i32> {
+    use specs::join::Join;
+
+    let config = world.read_resource::<GameConfig>();
+    let dim = world.read_resource::<Dimensions>();
+    let mut cameras = world.write_storage::<CameraInputState>();
+    let camera = (&mut cameras).join().next().expect("Camera entity missing");
+    let pan = Position::new(self.pan.0, self.pan.1);
+    let speed = EDITOR_PAN_SPEED * frame_time * camera.distance / config.view_distance;
+    camera.jump_to(camera.position + Position::new(pan.x() * speed, pan.y() * speed));
+    camera.distance = (camera.distance + self.zoom * EDITOR_ZOOM_SPEED * frame_time).max(config.zoom_min).min(config.zoom_max);
+    coords_to_tile(screen_to_world(self.cursor, camera, &dim))
+  }
+
+  fn edit(&mut self, world: &mut World, tile: Point2<i32>) {
+    let map = match self.map.as_mut() {
+      Some(map) => map,
+      None => return,
+    };
+    let (width, height) = (map.document.width as i32, map.document.height as i32);
+    if tile.x < 0 || tile.y < 0 || tile.x >= width || tile.y >= height {
+      return;
+    }
+    let (col, row) = (tile.x as usize, (height - 1 - tile.y) as usize);
+
+    match (self.tool.placeable(), self.erasing) {
+      (None, true) => self.brush = map.document.tile(map.layer, col, row),
+      (None, false) => {
+        if map.document.tile(map.layer, col, row) == self.brush {
+          return;
+        }
+        map.document.set_tile(map.layer, col, row, self.brush);
+        let terrain_layer = map.document.layers[..map.layer].iter().filter(|layer| layer.visible).count();
+        let mut terrain = world.write_resource::<Terrain>();
+        let idx = tile.y as usize * terrain.width + tile.x as usize;
+        terrain.layers[terrain_layer].tiles[idx] = self.brush as u16;
+        terrain.is_dirty = true;
+        self.tiles_changed = true;
+      }
+      (Some(placeable), erasing) => {
+        let previous = map.document.clone();
+        let changed = if erasing {
+          map.document.remove_at(col, row) > 0
+        } else {
+          map.document.remove_at(col, row);
+          map.document.place(placeable, col, row);
+          true
+        };
+        if changed {
+          self.refresh(world, Some(previous));
+        }
+      }
+    }
+  }
+
+  /// Applies the document to the world. If it is no longer a playable map the edit is undone.
+  fn refresh(&mut self, world: &mut World, previous: Option<MapDocument>) {
+    let map = self.map.as_mut().expect("Map editor has not been opened");
+    self.tiles_changed = false;
+    let parsed = map.document.to_map(&map.name)
+      .and_then(|tiled_map| MapObjects::from_map(&tiled_map, &map.name).map(|objects| (tiled_map, objects)));
+    match (parsed, previous) {
+      (Ok((tiled_map, objects)), _) => replace_map_objects(world, &tiled_map, objects),
+      (Err(e), Some(previous)) => {
+        eprintln!("Edit undone: {}", e);
+        map.document = previous;
+      }
+      (Err(e), None) => eprintln!("Edited map is not playable: {}", e),
+    }
+  }
+}
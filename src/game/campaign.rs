@@ -0,0 +1,75 @@
+use std::fs;
+
+use json::JsonValue;
+
+use crate::game::constants::MAP_FILE_PATH;
+use crate::game::error::GameError;
+
+/// Ordered list of maps played one after another, reaching a map's exit zone loads the next one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Campaign {
+  maps: Vec<String>,
+  current: usize,
+}
+
+impl Campaign {
+  /// A campaign of a single map, used for `--map`.
+  pub fn single(map: &str) -> Campaign {
+    Campaign { maps: vec![map.to_string()], current: 0 }
+  }
+
+  /// Reads a JSON object with a `maps` array of TMX paths.
+  pub fn load(path: &str) -> Result<Campaign, GameError> {
+    let content = fs::read_to_string(path).map_err(|e| GameError::io(path, e))?;
+    let value = json::parse(&content).map_err(|e| GameError::parse(path, e.to_string()))?;
+    Campaign::from_json(&value, path)
+  }
+
+  /// Builds a campaign from a `maps` array, `path` names where it came from in errors.
+  pub fn from_json(value: &JsonValue, path: &str) -> Result<Campaign, GameError> {
+    let maps = value["maps"].members()
+      .map(|m| m.as_str().map(str::to_string).ok_or_else(|| GameError::parse(path, format!("map {} is not a path", m))))
+      .collect::<Result<Vec<String>, GameError>>()?;
+    if maps.is_empty() {
+      return Err(GameError::missing_data(path, "a non-empty maps list".to_string()));
+    }
+    Ok(Campaign { maps, current: 0 })
+  }
+
+  /// Every map of the campaign in the format `from_json` reads, starting from the first one.
+  pub fn to_json(&self) -> JsonValue {
+    json::object! { "maps" => self.maps.clone() }
+  }
+
+  pub fn current_map(&self) -> &str {
+    &self.maps[self.current]
+  }
+
+  /// Moves on to the next map, returns false if the current map was the last one.
+  pub fn advance(&mut self) -> bool {
+    if self.current + 1 < self.maps.len() {
+      self.current += 1;
+      true
+    } else {
+      false
+    }
+  }
+
+  pub fn restart(&mut self) {
+    self.current = 0;
+  }
+
+  /// Jumps to `map`, which is played on its own if it is not part of the campaign.
+  pub fn select(&mut self, map: &str) {
+    match self.maps.iter().position(|m| m == map) {
+      Some(idx) => self.current = idx,
+      None => *self = Campaign::single(map),
+    }
+  }
+}
+
+impl Default for Campaign {
+  fn default() -> Campaign {
+    Campaign::single(MAP_FILE_PATH)
+  }
+}
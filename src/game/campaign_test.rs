@@ -0,0 +1,65 @@
+#[test]
+fn campaign_advances_through_maps_test() {
+  use crate::game::campaign::Campaign;
+  use crate::game::constants::CAMPAIGN_FILE_PATH;
+
+  let mut campaign = Campaign::load(CAMPAIGN_FILE_PATH).unwrap();
+  assert_eq!(campaign.current_map(), "assets/maps/tilemap.tmx");
+  assert!(campaign.advance());
+  assert_eq!(campaign.current_map(), "assets/maps/outskirts.tmx");
+  assert!(!campaign.advance());
+  assert_eq!(campaign.current_map(), "assets/maps/outskirts.tmx");
+  campaign.restart();
+  assert_eq!(campaign.current_map(), "assets/maps/tilemap.tmx");
+}
+
+#[test]
+fn selecting_unknown_map_plays_it_alone_test() {
+  use crate::game::campaign::Campaign;
+  use crate::game::constants::CAMPAIGN_FILE_PATH;
+
+  let mut campaign = Campaign::load(CAMPAIGN_FILE_PATH).unwrap();
+  campaign.select("assets/maps/outskirts.tmx");
+  assert_eq!(campaign.current_map(), "assets/maps/outskirts.tmx");
+  campaign.select("custom.tmx");
+  assert_eq!(campaign, Campaign::single("custom.tmx"));
+  assert!(!campaign.advance());
+}
+
+#[test]
+fn reaching_exit_loads_next_map_and_keeps_player_stats_test() {
+  use cgmath::Point2;
+  use specs::{join::Join, world::WorldExt};
+  use crate::character::{CharacterDrawable, controls::CharacterInputState};
+  use crate::game::campaign::Campaign;
+  use crate::game::config::GameConfig;
+  use crate::game::constants::{CAMPAIGN_FILE_PATH, RESOLUTION_X, RESOLUTION_Y};
+  use crate::game::simulation::{advance_level, LevelProgress, reached_exit, setup_world, update_level};
+  use crate::graphics::{dimensions::Dimensions, tile_to_coords};
+  use crate::terrain::map_objects::MapObjects;
+
+  let campaign = Campaign::load(CAMPAIGN_FILE_PATH).unwrap();
+  let mut w = specs::World::new();
+  setup_world(&mut w, Dimensions::new(RESOLUTION_X as f32, RESOLUTION_Y as f32, 1.0, true), 2, &GameConfig::default(), &campaign).unwrap();
+  assert!(!reached_exit(&w));
+  assert_eq!(update_level(&mut w, 0).unwrap(), LevelProgress::Playing);
+
+  let exit = w.read_resource::<MapObjects>().exit_tiles[0];
+  for (ci, cd) in (&mut w.write_storage::<CharacterInputState>(), &mut w.write_storage::<CharacterDrawable>()).join() {
+    ci.position = tile_to_coords(Point2::new(exit[0] as f32 + 0.5, exit[1] as f32 + 0.5));
+    cd.stats.health = 1;
+    cd.stats.magazines = 0;
+  }
+  assert!(reached_exit(&w));
+
+  assert_eq!(update_level(&mut w, 1).unwrap(), LevelProgress::Entered);
+  assert_eq!(w.read_resource::<Campaign>().current_map(), "assets/maps/outskirts.tmx");
+  let spawn = w.read_resource::<MapObjects>().player_spawn;
+  for (ci, cd) in (&w.read_storage::<CharacterInputState>(), &w.read_storage::<CharacterDrawable>()).join() {
+    assert_eq!(ci.position, spawn);
+    assert_eq!(cd.stats.health, 1);
+    assert_eq!(cd.stats.magazines, 0);
+  }
+  assert!(!reached_exit(&w));
+  assert!(!advance_level(&mut w).unwrap());
+}
@@ -0,0 +1,130 @@
+use specs::prelude::{ReadStorage, WriteStorage};
+
+use crate::graphics::{interpolate, orientation::{Orientation, Stance}, overlaps};
+use crate::shaders::Position;
+
+#[derive(Clone, Copy)]
+pub struct WorldPosition {
+  pub current: Position,
+  pub last_tick: Position,
+}
+
+impl WorldPosition {
+  pub fn new(position: Position) -> WorldPosition {
+    WorldPosition {
+      current: position,
+      last_tick: position,
+    }
+  }
+
+  pub fn move_by(&mut self, delta: Position) {
+    self.last_tick = self.current;
+    self.current = self.current + delta;
+  }
+
+  pub fn interpolated(&self, alpha: f32) -> Position {
+    interpolate(self.last_tick, self.current, alpha)
+  }
+}
+
+impl specs::prelude::Component for WorldPosition {
+  type Storage = specs::storage::VecStorage<WorldPosition>;
+}
+
+/// World space distance travelled per tick.
+pub struct Velocity(pub Position);
+
+impl specs::prelude::Component for Velocity {
+  type Storage = specs::storage::VecStorage<Velocity>;
+}
+
+pub struct Health(pub f32);
+
+impl Health {
+  pub fn is_alive(&self) -> bool {
+    self.0 > 0.0
+  }
+}
+
+impl specs::prelude::Component for Health {
+  type Storage = specs::storage::VecStorage<Health>;
+}
+
+pub struct Collider {
+  pub width: f32,
+  pub height: f32,
+}
+
+impl Collider {
+  pub fn new(width: f32, height: f32) -> Collider {
+    Collider {
+      width,
+      height,
+    }
+  }
+
+  pub fn hits(&self, own_position: Position, other: Position) -> bool {
+    overlaps(own_position, other, self.width, self.height)
+  }
+}
+
+impl specs::prelude::Component for Collider {
+  type Storage = specs::storage::VecStorage<Collider>;
+}
+
+pub struct Animation {
+  pub orientation: Orientation,
+  pub direction: Orientation,
+  pub stance: Stance,
+  pub frame_idx: usize,
+  pub death_frame_idx: usize,
+}
+
+impl Animation {
+  pub fn new(orientation: Orientation, stance: Stance) -> Animation {
+    Animation {
+      orientation,
+      direction: orientation,
+      stance,
+      frame_idx: 0,
+      death_frame_idx: 0,
+    }
+  }
+
+  pub fn is_dead(&self) -> bool {
+    self.stance == Stance::NormalDeath || self.stance == Stance::CriticalDeath
+  }
+
+  pub fn update_alive_idx(&mut self, max_idx: usize) {
+    if self.frame_idx < max_idx {
+      self.frame_idx += 1;
+    } else {
+      self.frame_idx = 0;
+    }
+  }
+
+  pub fn update_death_idx(&mut self, max_idx: usize) {
+    if self.death_frame_idx < max_idx {
+      self.death_frame_idx += 1;
+    }
+  }
+}
+
+impl specs::prelude::Component for Animation {
+  type Storage = specs::storage::VecStorage<Animation>;
+}
+
+pub struct MovementSystem;
+
+impl<'a> specs::prelude::System<'a> for MovementSystem {
+  type SystemData = (WriteStorage<'a, WorldPosition>,
+                     ReadStorage<'a, Velocity>);
+
+  fn run(&mut self, (mut positions, velocities): Self::SystemData) {
+    use specs::join::Join;
+
+    for (p, v) in (&mut positions, &velocities).join() {
+      p.move_by(v.0);
+    }
+  }
+}
@@ -0,0 +1,165 @@
+use std::{fs, io, str::FromStr};
+
+use json::JsonValue;
+
+use crate::game::constants::{AGGRO_RADIUS, BULLET_SPEED, CHARACTER_X_SPEED, CHARACTER_Y_SPEED, MAGAZINE_SIZE,
+                             PLAYER_HEALTH, RESOLUTION_X, RESOLUTION_Y, VIEW_DISTANCE, ZOOM_MAX, ZOOM_MIN};
+use crate::game::error::GameError;
+use crate::zombie::archetypes::ZombieArchetype;
+
+pub const CLI_SOURCE: &str = "command line";
+
+/// Gameplay and display values that can be tuned without recompiling.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameConfig {
+  pub resolution_x: u32,
+  pub resolution_y: u32,
+  pub view_distance: f32,
+  pub bullet_speed: f32,
+  pub character_x_speed: f32,
+  pub character_y_speed: f32,
+  pub zoom_min: f32,
+  pub zoom_max: f32,
+  pub aggro_radius: f32,
+  pub magazine_size: usize,
+  pub player_health: usize,
+  /// Loaded from the archetype data file rather than the settings file.
+  pub zombie_archetypes: Vec<ZombieArchetype>,
+}
+
+impl Default for GameConfig {
+  fn default() -> GameConfig {
+    GameConfig {
+      resolution_x: RESOLUTION_X,
+      resolution_y: RESOLUTION_Y,
+      view_distance: VIEW_DISTANCE,
+      bullet_speed: BULLET_SPEED,
+      character_x_speed: CHARACTER_X_SPEED,
+      character_y_speed: CHARACTER_Y_SPEED,
+      zoom_min: ZOOM_MIN,
+      zoom_max: ZOOM_MAX,
+      aggro_radius: AGGRO_RADIUS,
+      magazine_size: MAGAZINE_SIZE,
+      player_health: PLAYER_HEALTH,
+      zombie_archetypes: vec![ZombieArchetype::default()],
+    }
+  }
+}
+
+fn parse_value<T: FromStr>(key: &str, value: &str, source: &str) -> Result<T, GameError> {
+  value.trim().parse::<T>()
+    .map_err(|_| GameError::parse(source, format!("invalid value \"{}\" for {}", value, key)))
+}
+
+impl GameConfig {
+  /// Reads a JSON object of settings on top of the defaults. A missing file is only an error when `required`.
+  pub fn load(path: &str, required: bool) -> Result<GameConfig, GameError> {
+    let mut config = GameConfig::default();
+    let content = match fs::read_to_string(path) {
+      Ok(content) => content,
+      Err(ref e) if e.kind() == io::ErrorKind::NotFound && !required => return Ok(config),
+      Err(e) => return Err(GameError::io(path, e)),
+    };
+    let value = json::parse(&content).map_err(|e| GameError::parse(path, e.to_string()))?;
+    config.set_all(&value, path)?;
+    Ok(config)
+  }
+
+  /// Sets every key of a JSON object of settings.
+  pub fn set_all(&mut self, value: &JsonValue, source: &str) -> Result<(), GameError> {
+    if !value.is_object() {
+      return Err(GameError::parse(source, "expected a JSON object of settings".to_string()));
+    }
+    for (key, setting) in value.entries() {
+      self.set(key, &setting.dump(), source)?;
+    }
+    Ok(())
+  }
+
+  /// Every setting as written in the config file, the archetypes are left out.
+  pub fn settings_json(&self) -> JsonValue {
+    json::object! {
+      "resolution_x" => self.resolution_x,
+      "resolution_y" => self.resolution_y,
+      "view_distance" => self.view_distance,
+      "bullet_speed" => self.bullet_speed,
+      "character_x_speed" => self.character_x_speed,
+      "character_y_speed" => self.character_y_speed,
+      "zoom_min" => self.zoom_min,
+      "zoom_max" => self.zoom_max,
+      "aggro_radius" => self.aggro_radius,
+      "magazine_size" => self.magazine_size,
+      "player_health" => self.player_health
+    }
+  }
+
+  pub fn aspect_ratio(&self) -> f32 {
+    self.resolution_x as f32 / self.resolution_y as f32
+  }
+
+  pub fn set(&mut self, key: &str, value: &str, source: &str) -> Result<(), GameError> {
+    match key {
+      "resolution_x" => self.resolution_x = parse_value(key, value, source)?,
+      "resolution_y" => self.resolution_y = parse_value(key, value, source)?,
+      "view_distance" => self.view_distance = parse_value(key, value, source)?,
+      "bullet_speed" => self.bullet_speed = parse_value(key, value, source)?,
+      "character_x_speed" => self.character_x_speed = parse_value(key, value, source)?,
+      "character_y_speed" => self.character_y_speed = parse_value(key, value, source)?,
+      "zoom_min" => self.zoom_min = parse_value(key, value, source)?,
+      "zoom_max" => self.zoom_max = parse_value(key, value, source)?,
+      "aggro_radius" => self.aggro_radius = parse_value(key, value, source)?,
+      "magazine_size" => self.magazine_size = parse_value(key, value, source)?,
+      "player_health" => self.player_health = parse_value(key, value, source)?,
+      _ => return Err(GameError::parse(source, format!("unknown setting \"{}\"", key))),
+    }
+    Ok(())
+  }
+
+  /// Applies a `key=value` override given on the command line.
+  pub fn set_override(&mut self, setting: &str) -> Result<(), GameError> {
+    let mut parts = setting.splitn(2, '=');
+    match (parts.next(), parts.next()) {
+      (Some(key), Some(value)) => self.set(key.trim(), value, CLI_SOURCE),
+      _ => Err(GameError::parse(CLI_SOURCE, format!("expected key=value, got \"{}\"", setting))),
+    }
+  }
+
+  /// Archetype of a spawned zombie. Unknown indices fall back to the first archetype.
+  pub fn zombie_archetype(&self, idx: usize) -> &ZombieArchetype {
+    self.zombie_archetypes.get(idx).unwrap_or(&self.zombie_archetypes[0])
+  }
+
+  pub fn validate(&self, source: &str) -> Result<(), GameError> {
+    let invalid = |reason: String| Err(GameError::parse(source, reason));
+    if self.resolution_x == 0 || self.resolution_y == 0 {
+      return invalid(format!("resolution must be non-zero, got {}x{}", self.resolution_x, self.resolution_y));
+    }
+    let positive = [
+      ("view_distance", self.view_distance),
+      ("bullet_speed", self.bullet_speed),
+      ("character_x_speed", self.character_x_speed),
+      ("character_y_speed", self.character_y_speed),
+      ("zoom_min", self.zoom_min),
+      ("aggro_radius", self.aggro_radius),
+    ];
+    for (key, value) in positive.iter() {
+      if *value <= 0.0 || !value.is_finite() {
+        return invalid(format!("{} must be a positive number, got {}", key, value));
+      }
+    }
+    if self.zoom_min >= self.zoom_max || !self.zoom_max.is_finite() {
+      return invalid(format!("zoom_min ({}) must be less than zoom_max ({})", self.zoom_min, self.zoom_max));
+    }
+    if self.view_distance < self.zoom_min || self.view_distance > self.zoom_max {
+      return invalid(format!("view_distance ({}) must be between zoom_min ({}) and zoom_max ({})",
+                             self.view_distance, self.zoom_min, self.zoom_max));
+    }
+    if self.magazine_size == 0 {
+      return invalid("magazine_size must be at least 1".to_string());
+    }
+    if self.player_health == 0 {
+      return invalid("player_health must be at least 1".to_string());
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,76 @@
+#[test]
+fn cli_override_test() {
+  use crate::game::config::GameConfig;
+
+  let mut config = GameConfig::default();
+  config.set_override("bullet_speed=45.5").unwrap();
+  config.set_override("magazine_size=6").unwrap();
+  assert_eq!(config.bullet_speed, 45.5);
+  assert_eq!(config.magazine_size, 6);
+  assert!(config.validate("test").is_ok());
+}
+
+#[test]
+fn invalid_config_values_test() {
+  use crate::game::config::GameConfig;
+
+  let mut config = GameConfig::default();
+  assert!(config.set_override("bullet_speed=fast").is_err());
+  assert!(config.set_override("walk_speed=3").is_err());
+  assert!(config.set_override("bullet_speed").is_err());
+
+  config.set_override("character_x_speed=-3").unwrap();
+  assert!(config.validate("test").is_err());
+
+  let mut config = GameConfig::default();
+  config.set_override("zoom_min=700").unwrap();
+  let error = config.validate("test").unwrap_err().to_string();
+  assert!(error.contains("zoom_min"), "Unexpected error: {}", error);
+
+  let mut config = GameConfig::default();
+  config.set_override("player_health=0").unwrap();
+  assert!(config.validate("test").is_err());
+}
+
+#[test]
+fn reload_fills_configured_magazine_test() {
+  use specs::world::WorldExt;
+  use crate::character::{CharacterDrawable, controls::CharacterControl};
+  use crate::game::config::GameConfig;
+  use crate::game::campaign::Campaign;
+  use crate::game::simulation::{step, test_simulation};
+
+  let config = GameConfig { magazine_size: 6, player_health: 8, ..GameConfig::default() };
+  let (mut w, mut dispatcher, controls) = test_simulation(1, &config, &Campaign::default());
+  {
+    let mut character_drawable = w.write_storage::<CharacterDrawable>();
+    for cd in specs::join::Join::join(&mut character_drawable) {
+      assert_eq!(cd.stats.ammunition, 6);
+      assert_eq!(cd.stats.health, 8);
+      cd.stats.ammunition = 0;
+    }
+  }
+
+  controls.character_control.send(CharacterControl::ReloadPressed).unwrap();
+  for tick in 0..5 {
+    step(&mut dispatcher, &mut w, tick);
+  }
+
+  let character_drawable = w.read_storage::<CharacterDrawable>();
+  let cd = specs::join::Join::join(&character_drawable).next().unwrap();
+  assert_eq!(cd.stats.ammunition, 6);
+}
+
+#[test]
+fn settings_json_round_trip_test() {
+  use crate::game::config::GameConfig;
+
+  let mut config = GameConfig::default();
+  config.set_override("resolution_x=1280").unwrap();
+  config.set_override("resolution_y=1024").unwrap();
+  config.set_override("bullet_speed=45.5").unwrap();
+  let mut loaded = GameConfig::default();
+  loaded.set_all(&config.settings_json(), "test").unwrap();
+  assert_eq!(loaded, config);
+  assert_eq!(loaded.aspect_ratio(), 1.25);
+}
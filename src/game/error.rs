@@ -0,0 +1,56 @@
+use std::{error::Error, fmt, io};
+
+#[derive(Debug)]
+pub enum GameError {
+  Io { path: String, source: io::Error },
+  Write { path: String, source: io::Error },
+  Parse { path: String, reason: String },
+  MissingData { path: String, context: String },
+  Device { context: String },
+}
+
+impl GameError {
+  pub fn io(path: &str, source: io::Error) -> GameError {
+    GameError::Io { path: path.to_string(), source }
+  }
+
+  pub fn write(path: &str, source: io::Error) -> GameError {
+    GameError::Write { path: path.to_string(), source }
+  }
+
+  pub fn parse(path: &str, reason: String) -> GameError {
+    GameError::Parse { path: path.to_string(), reason }
+  }
+
+  pub fn missing_data(path: &str, context: String) -> GameError {
+    GameError::MissingData { path: path.to_string(), context }
+  }
+}
+
+impl fmt::Display for GameError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      GameError::Io { path, source } if path.starts_with("assets/") =>
+        write!(f, "Could not read {}: {}. Check that the game is started from the directory containing assets/", path, source),
+      GameError::Io { path, source } =>
+        write!(f, "Could not read {}: {}", path, source),
+      GameError::Write { path, source } =>
+        write!(f, "Could not write {}: {}", path, source),
+      GameError::Parse { path, reason } =>
+        write!(f, "Could not parse {}: {}", path, reason),
+      GameError::MissingData { path, context } =>
+        write!(f, "{} is missing {}", path, context),
+      GameError::Device { context } =>
+        write!(f, "{}", context),
+    }
+  }
+}
+
+impl Error for GameError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match self {
+      GameError::Io { source, .. } | GameError::Write { source, .. } => Some(source),
+      _ => None,
+    }
+  }
+}
@@ -1,22 +1,51 @@
 use num::Integer;
-use rand::distributions::uniform::SampleUniform;
-use rand::Rng;
+use rand::{distributions::uniform::SampleUniform, Rng, rngs::StdRng, SeedableRng};
 
+pub mod campaign;
+mod campaign_test;
+pub mod components;
+pub mod config;
+mod config_test;
 pub mod constants;
+pub mod error;
+pub mod mouse_controls;
+pub mod replay;
+mod replay_test;
+pub mod save;
+mod save_test;
+pub mod simulation;
+mod simulation_test;
+pub mod state;
+mod state_test;
 
-pub fn get_random_bool() -> bool {
-  let mut rnd = rand::thread_rng();
-  rnd.gen()
+pub struct GameRng {
+  pub seed: u64,
+  rng: StdRng,
 }
 
-pub fn get_rand_from_range<T>(min: T, max: T) -> T
-  where T: Integer + SampleUniform {
-  let mut rnd = rand::thread_rng();
-  rnd.gen_range(min, max)
+impl GameRng {
+  pub fn new(seed: u64) -> GameRng {
+    GameRng {
+      seed,
+      rng: StdRng::seed_from_u64(seed),
+    }
+  }
+
+  pub fn get_random_bool(&mut self) -> bool {
+    self.rng.gen()
+  }
+
+  pub fn get_rand_from_range<T>(&mut self, min: T, max: T) -> T
+    where T: Integer + SampleUniform {
+    self.rng.gen_range(min, max)
+  }
+
+  /// Seed for a new game drawn from this one, so restarts differ but stay reproducible.
+  pub fn next_seed(&mut self) -> u64 {
+    self.rng.gen()
+  }
 }
 
-#[allow(dead_code)]
-pub fn get_weighted_random(weight: f32) -> bool {
-  let mut rnd = rand::thread_rng();
-  rnd.gen::<f32>() < weight
+pub fn new_seed() -> u64 {
+  rand::random()
 }
@@ -1,12 +1,13 @@
 use cgmath::Point2;
 use crossbeam_channel as channel;
-use specs::prelude::{Read, ReadStorage, WriteStorage};
+use specs::prelude::{Entities, LazyUpdate, Read, ReadExpect, ReadStorage, WriteStorage};
 
-use crate::bullet::bullets::Bullets;
+use crate::bullet::bullets;
 use crate::character::{CharacterDrawable, controls::CharacterInputState};
-use crate::game::constants::SMALL_HILLS;
-use crate::graphics::{camera::CameraInputState, check_terrain_elevation, dimensions::Dimensions, direction};
+use crate::game::config::GameConfig;
+use crate::graphics::{check_terrain_elevation, dimensions::Dimensions, direction};
 use crate::shaders::Position;
+use crate::terrain::map_objects::MapObjects;
 
 type MouseEvent = channel::Sender<(MouseControl, Option<(f64, f64)>)>;
 
@@ -37,6 +38,7 @@ impl specs::prelude::Component for MouseInputState {
   type Storage = specs::storage::VecStorage<MouseInputState>;
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum MouseControl {
   LeftClick,
 }
@@ -55,20 +57,22 @@ impl MouseControlSystem {
 }
 
 impl<'a> specs::prelude::System<'a> for MouseControlSystem {
-  type SystemData = (WriteStorage<'a, MouseInputState>,
+  type SystemData = (Entities<'a>,
+                     WriteStorage<'a, MouseInputState>,
                      WriteStorage<'a, CharacterDrawable>,
-                     ReadStorage<'a, CameraInputState>,
                      ReadStorage<'a, CharacterInputState>,
-                     WriteStorage<'a, Bullets>,
-                     Read<'a, Dimensions>);
+                     Read<'a, LazyUpdate>,
+                     Read<'a, Dimensions>,
+                     Read<'a, GameConfig>,
+                     ReadExpect<'a, MapObjects>);
 
-  fn run(&mut self, (mut mouse_input, mut character_drawable, camera, character_input, mut bullets, dim): Self::SystemData) {
+  fn run(&mut self, (entities, mut mouse_input, mut character_drawable, character_input, lazy_update, dim, config, map_objects): Self::SystemData) {
     use specs::join::Join;
 
     while let Ok((control_value, value)) = self.queue.try_recv() {
       match control_value {
         MouseControl::LeftClick => {
-          for (mut mi, cd, bs, ca, ci) in (&mut mouse_input, &mut character_drawable, &mut bullets, &camera, &character_input).join() {
+          for (mi, cd, ci) in (&mut mouse_input, &mut character_drawable, &character_input).join() {
             if let Some(val) = value {
               if ci.is_shooting && cd.stats.ammunition > 0 {
                 cd.stats.ammunition -= 1;
@@ -76,8 +80,8 @@ impl<'a> specs::prelude::System<'a> for MouseControlSystem {
                 let end_point = Point2::new(val.0 as f32 * dim.hidpi_factor, val.1 as f32 * dim.hidpi_factor);
                 mi.left_click_point = Some(end_point);
                 let dir = direction(start_point, end_point);
-                let elevated_pos_y = check_terrain_elevation(ci.movement, &SMALL_HILLS);
-                Bullets::add_bullet(bs, Position::new(-ca.movement.x(), ca.movement.y() + elevated_pos_y), dir);
+                let elevated_pos_y = check_terrain_elevation(ci.position, &map_objects.hills);
+                bullets::add_bullet(&entities, &lazy_update, ci.position + Position::new(0.0, elevated_pos_y), dir, config.bullet_speed);
               }
             } else {
               mi.left_click_point = None;
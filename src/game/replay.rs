@@ -0,0 +1,238 @@
+use std::{collections::VecDeque, fs::{self, File}, io::Write};
+
+use crossbeam_channel as channel;
+use json::JsonValue;
+
+use crate::audio::Effects;
+use crate::character::controls::CharacterControl;
+use crate::game::campaign::Campaign;
+use crate::game::config::GameConfig;
+use crate::game::error::GameError;
+use crate::game::mouse_controls::MouseControl;
+use crate::graphics::camera::CameraControl;
+
+/// 2: gameplay settings in the header.
+/// 3: the campaign maps in the header.
+const REPLAY_VERSION: u32 = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InputEvent {
+  Character(CharacterControl),
+  Camera(CameraControl),
+  Mouse(MouseControl, Option<(f64, f64)>),
+  Audio(Effects),
+}
+
+/// Anything a recorded message can be sent to, with or without a window.
+pub trait InputSink {
+  fn send(&mut self, event: InputEvent);
+}
+
+fn character_control_name(control: CharacterControl) -> &'static str {
+  match control {
+    CharacterControl::Left => "Left",
+    CharacterControl::Right => "Right",
+    CharacterControl::Up => "Up",
+    CharacterControl::Down => "Down",
+    CharacterControl::XMoveStop => "XMoveStop",
+    CharacterControl::YMoveStop => "YMoveStop",
+    CharacterControl::CtrlPressed => "CtrlPressed",
+    CharacterControl::CtrlReleased => "CtrlReleased",
+    CharacterControl::ReloadPressed => "ReloadPressed",
+    CharacterControl::ReloadReleased => "ReloadReleased",
+  }
+}
+
+fn character_control_from_name(name: &str) -> Option<CharacterControl> {
+  match name {
+    "Left" => Some(CharacterControl::Left),
+    "Right" => Some(CharacterControl::Right),
+    "Up" => Some(CharacterControl::Up),
+    "Down" => Some(CharacterControl::Down),
+    "XMoveStop" => Some(CharacterControl::XMoveStop),
+    "YMoveStop" => Some(CharacterControl::YMoveStop),
+    "CtrlPressed" => Some(CharacterControl::CtrlPressed),
+    "CtrlReleased" => Some(CharacterControl::CtrlReleased),
+    "ReloadPressed" => Some(CharacterControl::ReloadPressed),
+    "ReloadReleased" => Some(CharacterControl::ReloadReleased),
+    _ => None,
+  }
+}
+
+fn camera_control_name(control: CameraControl) -> &'static str {
+  match control {
+    CameraControl::ZoomOut => "ZoomOut",
+    CameraControl::ZoomIn => "ZoomIn",
+    CameraControl::ZoomStop => "ZoomStop",
+    CameraControl::Left => "Left",
+    CameraControl::Right => "Right",
+    CameraControl::Up => "Up",
+    CameraControl::Down => "Down",
+    CameraControl::XMoveStop => "XMoveStop",
+    CameraControl::YMoveStop => "YMoveStop",
+  }
+}
+
+fn camera_control_from_name(name: &str) -> Option<CameraControl> {
+  match name {
+    "ZoomOut" => Some(CameraControl::ZoomOut),
+    "ZoomIn" => Some(CameraControl::ZoomIn),
+    "ZoomStop" => Some(CameraControl::ZoomStop),
+    "Left" => Some(CameraControl::Left),
+    "Right" => Some(CameraControl::Right),
+    "Up" => Some(CameraControl::Up),
+    "Down" => Some(CameraControl::Down),
+    "XMoveStop" => Some(CameraControl::XMoveStop),
+    "YMoveStop" => Some(CameraControl::YMoveStop),
+    _ => None,
+  }
+}
+
+fn effect_from_name(name: &str) -> Option<Effects> {
+  match name {
+    "PistolFire" => Some(Effects::PistolFire),
+    "None" => Some(Effects::None),
+    _ => None,
+  }
+}
+
+impl InputEvent {
+  pub fn to_json(self, tick: u64) -> JsonValue {
+    match self {
+      InputEvent::Character(c) => json::object! { "tick" => tick, "type" => "character", "control" => character_control_name(c) },
+      InputEvent::Camera(c) => json::object! { "tick" => tick, "type" => "camera", "control" => camera_control_name(c) },
+      InputEvent::Mouse(MouseControl::LeftClick, Some((x, y))) =>
+        json::object! { "tick" => tick, "type" => "mouse", "control" => "LeftClick", "x" => x, "y" => y },
+      InputEvent::Mouse(MouseControl::LeftClick, None) => json::object! { "tick" => tick, "type" => "mouse", "control" => "LeftClick" },
+      InputEvent::Audio(e) => json::object! { "tick" => tick, "type" => "audio", "control" => format!("{:?}", e) },
+    }
+  }
+
+  pub fn from_json(value: &JsonValue) -> Option<(u64, InputEvent)> {
+    let tick = value["tick"].as_u64()?;
+    let control = value["control"].as_str()?;
+    let event = match value["type"].as_str()? {
+      "character" => InputEvent::Character(character_control_from_name(control)?),
+      "camera" => InputEvent::Camera(camera_control_from_name(control)?),
+      "mouse" if control == "LeftClick" => {
+        let position = match (value["x"].as_f64(), value["y"].as_f64()) {
+          (Some(x), Some(y)) => Some((x, y)),
+          _ => None,
+        };
+        InputEvent::Mouse(MouseControl::LeftClick, position)
+      }
+      "audio" => InputEvent::Audio(effect_from_name(control)?),
+      _ => return None,
+    };
+    Some((tick, event))
+  }
+}
+
+/// Writes every control message to a JSON lines file, one line per message.
+/// Lines are written unbuffered so the file stays usable if the game exits abruptly.
+pub struct InputRecorder {
+  path: String,
+  file: File,
+  queue: channel::Receiver<InputEvent>,
+}
+
+impl InputRecorder {
+  /// The header holds the seed, the settings and the maps the session is played with.
+  pub fn new(path: &str, seed: u64, config: &GameConfig, campaign: &Campaign) -> Result<(InputRecorder, channel::Sender<InputEvent>), GameError> {
+    let (tx, rx) = channel::unbounded();
+    let mut file = File::create(path).map_err(|e| GameError::write(path, e))?;
+    let header = json::object! {
+      "version" => REPLAY_VERSION,
+      "seed" => seed,
+      "config" => config.settings_json(),
+      "campaign" => campaign.to_json()
+    };
+    writeln!(file, "{}", header.dump()).map_err(|e| GameError::write(path, e))?;
+
+    Ok((InputRecorder {
+      path: path.to_string(),
+      file,
+      queue: rx,
+    }, tx))
+  }
+
+  /// Stamps pending messages with the tick that will consume them.
+  pub fn record_pending(&mut self, tick: u64) -> Result<(), GameError> {
+    while let Ok(event) = self.queue.try_recv() {
+      writeln!(self.file, "{}", event.to_json(tick).dump()).map_err(|e| GameError::write(&self.path, e))?;
+    }
+    Ok(())
+  }
+
+  /// Marks the end of the recorded run, with the seed the next one starts from.
+  pub fn record_restart(&mut self, tick: u64, seed: u64) -> Result<(), GameError> {
+    self.record_pending(tick)?;
+    let restart = json::object! { "tick" => tick, "type" => "restart", "seed" => seed };
+    writeln!(self.file, "{}", restart.dump()).map_err(|e| GameError::io(&self.path, e))
+  }
+}
+
+/// Plays back the first run of a recording, up to the game over or completed campaign that ended it.
+pub struct Replay {
+  pub seed: u64,
+  /// Settings of the recorded session on top of the defaults, zombie archetypes come from the data file.
+  pub config: GameConfig,
+  /// Maps of the recorded session, replacing `--map` and `--generate`.
+  pub campaign: Campaign,
+  events: VecDeque<(u64, InputEvent)>,
+}
+
+impl Replay {
+  pub fn load(path: &str) -> Result<Replay, GameError> {
+    let content = fs::read_to_string(path).map_err(|e| GameError::io(path, e))?;
+    let mut lines = content.lines().filter(|l| !l.trim().is_empty());
+
+    let header = lines.next()
+      .ok_or_else(|| GameError::missing_data(path, "replay header".to_string()))
+      .and_then(|l| json::parse(l).map_err(|e| GameError::parse(path, e.to_string())))?;
+    match header["version"].as_u32() {
+      Some(REPLAY_VERSION) => (),
+      Some(v) => return Err(GameError::parse(path, format!("unsupported replay version {}", v))),
+      None => return Err(GameError::missing_data(path, "version".to_string())),
+    }
+    let seed = header["seed"].as_u64()
+      .ok_or_else(|| GameError::missing_data(path, "seed".to_string()))?;
+    let mut config = GameConfig::default();
+    config.set_all(&header["config"], path)?;
+    config.validate(path)?;
+    let campaign = Campaign::from_json(&header["campaign"], path)?;
+
+    let mut events = VecDeque::new();
+    for (idx, line) in lines.enumerate() {
+      let value = json::parse(line).map_err(|e| GameError::parse(path, e.to_string()))?;
+      if value["type"] == "restart" {
+        break;
+      }
+      let event = InputEvent::from_json(&value)
+        .ok_or_else(|| GameError::parse(path, format!("invalid event on line {}", idx + 2)))?;
+      events.push_back(event);
+    }
+
+    Ok(Replay {
+      seed,
+      config,
+      campaign,
+      events,
+    })
+  }
+
+  pub fn is_finished(&self) -> bool {
+    self.events.is_empty()
+  }
+
+  /// Sends every message recorded for `tick` or earlier.
+  pub fn feed(&mut self, tick: u64, sink: &mut impl InputSink) {
+    while let Some(&(event_tick, event)) = self.events.front() {
+      if event_tick > tick {
+        break;
+      }
+      sink.send(event);
+      self.events.pop_front();
+    }
+  }
+}
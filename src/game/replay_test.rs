@@ -0,0 +1,76 @@
+#[test]
+fn input_event_json_round_trip_test() {
+  use crate::audio::Effects;
+  use crate::character::controls::CharacterControl;
+  use crate::game::mouse_controls::MouseControl;
+  use crate::game::replay::InputEvent;
+  use crate::graphics::camera::CameraControl;
+
+  let events = [
+    InputEvent::Character(CharacterControl::ReloadPressed),
+    InputEvent::Camera(CameraControl::ZoomIn),
+    InputEvent::Mouse(MouseControl::LeftClick, Some((120.5, 80.0))),
+    InputEvent::Mouse(MouseControl::LeftClick, None),
+    InputEvent::Audio(Effects::PistolFire),
+  ];
+  for (tick, event) in events.iter().enumerate() {
+    let parsed = InputEvent::from_json(&event.to_json(tick as u64));
+    assert_eq!(parsed, Some((tick as u64, *event)));
+  }
+}
+
+#[cfg(test)]
+fn character_position_after(ticks: u64, seed: u64, config: &crate::game::config::GameConfig, campaign: &crate::game::campaign::Campaign,
+                            mut feed: impl FnMut(u64, &mut crate::game::simulation::SimulationControls)) -> crate::shaders::Position {
+  use specs::world::WorldExt;
+  use crate::character::controls::CharacterInputState;
+  use crate::game::simulation::{step, test_simulation};
+
+  let (mut w, mut dispatcher, mut controls) = test_simulation(seed, config, campaign);
+  for tick in 0..ticks {
+    feed(tick, &mut controls);
+    step(&mut dispatcher, &mut w, tick);
+  }
+
+  let character_input = w.read_storage::<CharacterInputState>();
+  specs::join::Join::join(&character_input).next().unwrap().position
+}
+
+#[test]
+fn recorded_input_replays_identically_test() {
+  use crate::character::controls::CharacterControl;
+  use crate::game::campaign::Campaign;
+  use crate::game::config::GameConfig;
+  use crate::game::replay::{InputEvent, InputRecorder, InputSink, Replay};
+
+  let path = std::env::temp_dir().join(format!("hinterland_replay_test_{}.jsonl", std::process::id()));
+  let path = path.to_str().unwrap();
+  let config = GameConfig { character_x_speed: 5.0, ..GameConfig::default() };
+  let campaign = Campaign::single("assets/maps/outskirts.tmx");
+  let (mut recorder, record_control) = InputRecorder::new(path, 7, &config, &campaign).unwrap();
+
+  let live = character_position_after(40, 7, &config, &campaign, |tick, controls| {
+    let control = match tick {
+      3 => Some(CharacterControl::Left),
+      12 => Some(CharacterControl::Up),
+      20 => Some(CharacterControl::XMoveStop),
+      _ => None,
+    };
+    if let Some(control) = control {
+      record_control.send(InputEvent::Character(control)).unwrap();
+      controls.send(InputEvent::Character(control));
+    }
+    recorder.record_pending(tick).unwrap();
+  });
+
+  let mut replay = Replay::load(path).unwrap();
+  assert_eq!(replay.seed, 7);
+  assert_eq!(replay.config.character_x_speed, 5.0);
+  assert_eq!(replay.campaign, campaign);
+  let (config, campaign) = (replay.config.clone(), replay.campaign.clone());
+  let replayed = character_position_after(40, replay.seed, &config, &campaign, |tick, controls| replay.feed(tick, controls));
+  let _ = std::fs::remove_file(path);
+
+  assert!(live != crate::shaders::Position::origin(), "Recorded input should move the character");
+  assert!(live == replayed, "Replay should reproduce the recorded session");
+}
@@ -0,0 +1,348 @@
+use std::fs;
+
+use json::JsonValue;
+use specs::{Builder, shred::World, world::WorldExt};
+
+use crate::bullet::{Bullet, collision::Collision};
+use crate::character::{CharacterDrawable, controls::CharacterInputState};
+use crate::game::campaign::Campaign;
+use crate::game::components::{Animation, Health, Velocity, WorldPosition};
+use crate::game::config::GameConfig;
+use crate::game::error::GameError;
+use crate::game::simulation::load_map;
+use crate::graphics::camera::CameraInputState;
+use crate::graphics::orientation::{Orientation, Stance};
+use crate::shaders::{Position, Rotation};
+use crate::terrain_object::{TerrainObject, TerrainTexture};
+use crate::zombie::{waves::ZombieWaves, zombies::create_zombie, ZombieAi};
+
+/// Bumped whenever a field is added to or changes meaning in the saved state, older saves are rejected.
+/// 2: player health, 3: zombie waves with the max health and speed of each zombie, 4: zombie archetype, 5: current map.
+const SAVE_VERSION: u32 = 5;
+
+struct SavedZombie {
+  /// Index into the zombie archetypes of the game config.
+  archetype: usize,
+  position: Position,
+  health: f32,
+  max_health: f32,
+  speed: f32,
+  stance: Stance,
+  orientation: Orientation,
+  direction: Orientation,
+  death_frame_idx: usize,
+}
+
+struct SavedBullet {
+  position: Position,
+  velocity: Position,
+  rotation: f32,
+}
+
+/// Snapshot of everything that changes while playing. Terrain, houses and trees are static and come from setup_world.
+struct SavedState {
+  tick: u64,
+  map: String,
+  camera_distance: f32,
+  character_position: Position,
+  ammunition: usize,
+  magazines: usize,
+  health: usize,
+  wave: usize,
+  wave_remaining: usize,
+  zombies: Vec<SavedZombie>,
+  bullets: Vec<SavedBullet>,
+  pickups: Vec<Position>,
+}
+
+fn position_json(position: Position) -> JsonValue {
+  json::object! { "x" => position.x(), "y" => position.y() }
+}
+
+fn read_f32(value: &JsonValue, field: &str, path: &str, context: &str) -> Result<f32, GameError> {
+  value[field].as_f32()
+    .ok_or_else(|| GameError::missing_data(path, format!("{}.{}", context, field)))
+}
+
+fn read_usize(value: &JsonValue, field: &str, path: &str, context: &str) -> Result<usize, GameError> {
+  value[field].as_usize()
+    .ok_or_else(|| GameError::missing_data(path, format!("{}.{}", context, field)))
+}
+
+fn read_array<'v>(value: &'v JsonValue, field: &str, path: &str) -> Result<&'v JsonValue, GameError> {
+  if value[field].is_array() {
+    Ok(&value[field])
+  } else {
+    Err(GameError::missing_data(path, field.to_string()))
+  }
+}
+
+fn read_position(value: &JsonValue, path: &str, context: &str) -> Result<Position, GameError> {
+  Ok(Position::new(read_f32(value, "x", path, context)?, read_f32(value, "y", path, context)?))
+}
+
+fn read_orientation(value: &JsonValue, field: &str, path: &str, context: &str) -> Result<Orientation, GameError> {
+  value[field].as_str().and_then(|s| s.parse().ok())
+    .ok_or_else(|| GameError::missing_data(path, format!("{}.{}", context, field)))
+}
+
+impl SavedState {
+  fn from_world(world: &World, tick: u64) -> SavedState {
+    use specs::join::Join;
+
+    let character_input = world.read_storage::<CharacterInputState>();
+    let character_drawable = world.read_storage::<CharacterDrawable>();
+    let camera_input = world.read_storage::<CameraInputState>();
+    let positions = world.read_storage::<WorldPosition>();
+    let velocities = world.read_storage::<Velocity>();
+    let zombies = world.read_storage::<ZombieAi>();
+    let healths = world.read_storage::<Health>();
+    let animations = world.read_storage::<Animation>();
+    let bullets = world.read_storage::<Bullet>();
+    let terrain_objects = world.read_storage::<TerrainObject>();
+    let waves = world.read_resource::<ZombieWaves>();
+    let campaign = world.read_resource::<Campaign>();
+
+    let (ci, cd, camera) = (&character_input, &character_drawable, &camera_input).join().next()
+      .expect("Player entity missing");
+
+    SavedState {
+      tick,
+      map: campaign.current_map().to_string(),
+      camera_distance: camera.distance,
+      character_position: ci.position,
+      ammunition: cd.stats.ammunition,
+      magazines: cd.stats.magazines,
+      health: cd.stats.health,
+      wave: waves.wave,
+      wave_remaining: waves.remaining,
+      zombies: (&zombies, &positions, &healths, &animations).join()
+        .map(|(z, p, h, a)| SavedZombie {
+          archetype: z.archetype,
+          position: p.current,
+          health: h.0,
+          max_health: z.max_health,
+          speed: z.speed,
+          stance: a.stance.clone(),
+          orientation: a.orientation,
+          direction: a.direction,
+          death_frame_idx: a.death_frame_idx,
+        })
+        .collect(),
+      bullets: (&bullets, &positions, &velocities).join()
+        .map(|(b, p, v)| SavedBullet { position: p.current, velocity: v.0, rotation: b.rotation.rotation })
+        .collect(),
+      pickups: (&terrain_objects, &positions).join()
+        .filter(|(o, _)| o.object_type == TerrainTexture::Ammo)
+        .map(|(_, p)| p.current)
+        .collect(),
+    }
+  }
+
+  fn to_json(&self, archetypes: &[String]) -> JsonValue {
+    let mut zombies = JsonValue::new_array();
+    for z in &self.zombies {
+      let mut zombie = position_json(z.position);
+      zombie["archetype"] = archetypes[z.archetype].as_str().into();
+      zombie["health"] = z.health.into();
+      zombie["max_health"] = z.max_health.into();
+      zombie["speed"] = z.speed.into();
+      zombie["stance"] = z.stance.to_string().into();
+      zombie["orientation"] = z.orientation.to_string().into();
+      zombie["direction"] = z.direction.to_string().into();
+      zombie["death_frame_idx"] = z.death_frame_idx.into();
+      zombies.push(zombie).expect("Zombie save error");
+    }
+
+    let mut bullets = JsonValue::new_array();
+    for b in &self.bullets {
+      let mut bullet = position_json(b.position);
+      bullet["dx"] = b.velocity.x().into();
+      bullet["dy"] = b.velocity.y().into();
+      bullet["rotation"] = b.rotation.into();
+      bullets.push(bullet).expect("Bullet save error");
+    }
+
+    let mut pickups = JsonValue::new_array();
+    for p in &self.pickups {
+      pickups.push(position_json(*p)).expect("Pickup save error");
+    }
+
+    let mut character = position_json(self.character_position);
+    character["ammunition"] = self.ammunition.into();
+    character["magazines"] = self.magazines.into();
+    character["health"] = self.health.into();
+
+    json::object! {
+      "version" => SAVE_VERSION,
+      "tick" => self.tick,
+      "map" => self.map.clone(),
+      "camera" => json::object! { "distance" => self.camera_distance },
+      "wave" => json::object! { "number" => self.wave, "remaining" => self.wave_remaining },
+      "character" => character,
+      "zombies" => zombies,
+      "bullets" => bullets,
+      "pickups" => pickups
+    }
+  }
+
+  /// `archetypes` are the names of the zombie archetypes the saved ones are looked up from.
+  fn from_json(value: &JsonValue, path: &str, archetypes: &[String]) -> Result<SavedState, GameError> {
+    match value["version"].as_u32() {
+      Some(SAVE_VERSION) => (),
+      Some(v) if v < SAVE_VERSION => return Err(GameError::parse(path, format!("save version {} is older than {} and can't be loaded", v, SAVE_VERSION))),
+      Some(v) => return Err(GameError::parse(path, format!("unsupported save version {}", v))),
+      None => return Err(GameError::missing_data(path, "version".to_string())),
+    }
+
+    let mut zombies = Vec::new();
+    for (idx, z) in read_array(value, "zombies", path)?.members().enumerate() {
+      let context = format!("zombies[{}]", idx);
+      let archetype = z["archetype"].as_str()
+        .ok_or_else(|| GameError::missing_data(path, format!("{}.archetype", context)))?;
+      zombies.push(SavedZombie {
+        archetype: archetypes.iter().position(|name| name == archetype)
+          .ok_or_else(|| GameError::parse(path, format!("{} has unknown archetype \"{}\"", context, archetype)))?,
+        position: read_position(z, path, &context)?,
+        health: read_f32(z, "health", path, &context)?,
+        max_health: read_f32(z, "max_health", path, &context)?,
+        speed: read_f32(z, "speed", path, &context)?,
+        stance: z["stance"].as_str().and_then(|s| s.parse().ok())
+          .ok_or_else(|| GameError::missing_data(path, format!("{}.stance", context)))?,
+        orientation: read_orientation(z, "orientation", path, &context)?,
+        direction: read_orientation(z, "direction", path, &context)?,
+        death_frame_idx: read_usize(z, "death_frame_idx", path, &context)?,
+      });
+    }
+
+    let mut bullets = Vec::new();
+    for (idx, b) in read_array(value, "bullets", path)?.members().enumerate() {
+      let context = format!("bullets[{}]", idx);
+      bullets.push(SavedBullet {
+        position: read_position(b, path, &context)?,
+        velocity: Position::new(read_f32(b, "dx", path, &context)?, read_f32(b, "dy", path, &context)?),
+        rotation: read_f32(b, "rotation", path, &context)?,
+      });
+    }
+
+    let mut pickups = Vec::new();
+    for (idx, p) in read_array(value, "pickups", path)?.members().enumerate() {
+      pickups.push(read_position(p, path, &format!("pickups[{}]", idx))?);
+    }
+
+    let character = &value["character"];
+    Ok(SavedState {
+      tick: value["tick"].as_u64().ok_or_else(|| GameError::missing_data(path, "tick".to_string()))?,
+      map: value["map"].as_str().ok_or_else(|| GameError::missing_data(path, "map".to_string()))?.to_string(),
+      camera_distance: read_f32(&value["camera"], "distance", path, "camera")?,
+      character_position: read_position(character, path, "character")?,
+      ammunition: read_usize(character, "ammunition", path, "character")?,
+      magazines: read_usize(character, "magazines", path, "character")?,
+      health: read_usize(character, "health", path, "character")?,
+      wave: read_usize(&value["wave"], "number", path, "wave")?,
+      wave_remaining: read_usize(&value["wave"], "remaining", path, "wave")?,
+      zombies,
+      bullets,
+      pickups,
+    })
+  }
+
+  fn apply(self, world: &mut World) {
+    use specs::join::Join;
+
+    {
+      let entities = world.entities();
+      let zombies = world.read_storage::<ZombieAi>();
+      let bullets = world.read_storage::<Bullet>();
+      let terrain_objects = world.read_storage::<TerrainObject>();
+      for (e, _) in (&entities, &zombies).join() {
+        entities.delete(e).expect("Zombie delete error");
+      }
+      for (e, _) in (&entities, &bullets).join() {
+        entities.delete(e).expect("Bullet delete error");
+      }
+      for (e, o) in (&entities, &terrain_objects).join() {
+        if o.object_type == TerrainTexture::Ammo {
+          entities.delete(e).expect("Pickup delete error");
+        }
+      }
+    }
+    world.maintain();
+
+    {
+      let mut character_input = world.write_storage::<CharacterInputState>();
+      let mut character_drawable = world.write_storage::<CharacterDrawable>();
+      let mut camera_input = world.write_storage::<CameraInputState>();
+      for (ci, cd, camera) in (&mut character_input, &mut character_drawable, &mut camera_input).join() {
+        ci.position = self.character_position;
+        cd.stats.ammunition = self.ammunition;
+        cd.stats.magazines = self.magazines;
+        cd.stats.health = self.health;
+        cd.stats.invulnerability = 0.0;
+        cd.stance = Stance::Walking;
+        camera.distance = self.camera_distance;
+        camera.jump_to(self.character_position);
+      }
+    }
+
+    {
+      let mut waves = world.write_resource::<ZombieWaves>();
+      waves.wave = self.wave;
+      waves.remaining = self.wave_remaining;
+    }
+
+    for z in self.zombies {
+      let entity = create_zombie(world, z.position);
+      let mut zombies = world.write_storage::<ZombieAi>();
+      let mut healths = world.write_storage::<Health>();
+      let mut animations = world.write_storage::<Animation>();
+      if let (Some(zombie), Some(h), Some(a)) = (zombies.get_mut(entity), healths.get_mut(entity), animations.get_mut(entity)) {
+        zombie.archetype = z.archetype;
+        zombie.max_health = z.max_health;
+        zombie.speed = z.speed;
+        h.0 = z.health;
+        a.stance = z.stance;
+        a.orientation = z.orientation;
+        a.direction = z.direction;
+        a.death_frame_idx = z.death_frame_idx;
+      }
+    }
+
+    for b in self.bullets {
+      world.create_entity()
+        .with(Bullet { rotation: Rotation::new(b.rotation), status: Collision::Flying })
+        .with(WorldPosition::new(b.position))
+        .with(Velocity(b.velocity))
+        .build();
+    }
+
+    for p in self.pickups {
+      world.create_entity()
+        .with(TerrainObject::new(TerrainTexture::Ammo))
+        .with(WorldPosition::new(p))
+        .build();
+    }
+  }
+}
+
+fn archetype_names(world: &World) -> Vec<String> {
+  world.read_resource::<GameConfig>().zombie_archetypes.iter().map(|a| a.name.clone()).collect()
+}
+
+pub fn save_game(world: &World, tick: u64, path: &str) -> Result<(), GameError> {
+  let state = SavedState::from_world(world, tick);
+  fs::write(path, state.to_json(&archetype_names(world)).pretty(2)).map_err(|e| GameError::write(path, e))
+}
+
+/// Replaces the dynamic part of the world with the saved state and returns the tick to resume from.
+pub fn load_game(world: &mut World, path: &str) -> Result<u64, GameError> {
+  let content = fs::read_to_string(path).map_err(|e| GameError::io(path, e))?;
+  let value = json::parse(&content).map_err(|e| GameError::parse(path, e.to_string()))?;
+  let state = SavedState::from_json(&value, path, &archetype_names(world))?;
+  let tick = state.tick;
+  if world.read_resource::<Campaign>().current_map() != state.map {
+    load_map(world, &state.map)?;
+  }
+  state.apply(world);
+  Ok(tick)
+}
@@ -0,0 +1,125 @@
+#[test]
+fn save_and_load_round_trip_test() {
+  use specs::{Builder, world::WorldExt};
+  use crate::bullet::Bullet;
+  use crate::character::{CharacterDrawable, controls::CharacterInputState};
+  use crate::game::components::{Health, Velocity, WorldPosition};
+  use crate::game::constants::{RESOLUTION_X, RESOLUTION_Y};
+  use crate::game::save::{load_game, save_game};
+  use crate::game::config::GameConfig;
+  use crate::game::campaign::Campaign;
+  use crate::game::simulation::setup_world;
+  use crate::graphics::{camera::CameraInputState, dimensions::Dimensions};
+  use crate::shaders::Position;
+  use crate::terrain_object::{TerrainObject, TerrainTexture};
+  use crate::zombie::{waves::ZombieWaves, zombies::create_zombie, ZombieAi};
+
+  let dimensions = || Dimensions::new(RESOLUTION_X as f32, RESOLUTION_Y as f32, 1.0, true);
+  let mut w = specs::World::new();
+  setup_world(&mut w, dimensions(), 3, &GameConfig::default(), &Campaign::default()).unwrap();
+  create_zombie(&mut w, Position::new(300.0, 300.0));
+  create_zombie(&mut w, Position::new(-300.0, 300.0));
+  {
+    use specs::join::Join;
+    let mut waves = w.write_resource::<ZombieWaves>();
+    waves.wave = 3;
+    waves.remaining = 4;
+    let mut character_input = w.write_storage::<CharacterInputState>();
+    let mut character_drawable = w.write_storage::<CharacterDrawable>();
+    let mut camera_input = w.write_storage::<CameraInputState>();
+    for (ci, cd, camera) in (&mut character_input, &mut character_drawable, &mut camera_input).join() {
+      ci.position = Position::new(120.0, -40.0);
+      cd.stats.ammunition = 3;
+      cd.stats.health = 2;
+      camera.distance = 250.0;
+    }
+    let mut zombies = w.write_storage::<ZombieAi>();
+    let mut healths = w.write_storage::<Health>();
+    if let Some((z, h)) = (&mut zombies, &mut healths).join().next() {
+      z.max_health = 1.5;
+      h.0 = 0.5;
+    }
+  }
+  w.create_entity()
+    .with(Bullet::new(90.0))
+    .with(WorldPosition::new(Position::new(10.0, 20.0)))
+    .with(Velocity(Position::new(0.0, 30.0)))
+    .build();
+
+  let path = std::env::temp_dir().join(format!("hinterland_save_test_{}.json", std::process::id()));
+  let path = path.to_str().unwrap();
+  save_game(&w, 480, path).unwrap();
+
+  let mut loaded = specs::World::new();
+  setup_world(&mut loaded, dimensions(), 3, &GameConfig::default(), &Campaign::default()).unwrap();
+  let tick = load_game(&mut loaded, path).unwrap();
+  let _ = std::fs::remove_file(path);
+  assert_eq!(tick, 480);
+
+  use specs::join::Join;
+  let character_input = loaded.read_storage::<CharacterInputState>();
+  let character_drawable = loaded.read_storage::<CharacterDrawable>();
+  let camera_input = loaded.read_storage::<CameraInputState>();
+  let (ci, cd, camera) = (&character_input, &character_drawable, &camera_input).join().next().unwrap();
+  assert!(ci.position == Position::new(120.0, -40.0));
+  assert_eq!(cd.stats.ammunition, 3);
+  assert_eq!(cd.stats.health, 2);
+  assert_eq!(camera.distance, 250.0);
+
+  let zombies = loaded.read_storage::<ZombieAi>();
+  let healths = loaded.read_storage::<Health>();
+  let zombie_healths: Vec<f32> = (&zombies, &healths).join().map(|(_, h)| h.0).collect();
+  assert_eq!(zombie_healths.len(), (&w.read_storage::<ZombieAi>()).join().count());
+  assert_eq!(zombie_healths.iter().filter(|h| **h == 0.5).count(), 1);
+  assert_eq!(zombies.join().filter(|z| z.max_health == 1.5).count(), 1);
+  let waves = loaded.read_resource::<ZombieWaves>();
+  assert_eq!((waves.wave, waves.remaining), (3, 4));
+
+  let bullets = loaded.read_storage::<Bullet>();
+  let positions = loaded.read_storage::<WorldPosition>();
+  let bullet_positions: Vec<Position> = (&bullets, &positions).join().map(|(_, p)| p.current).collect();
+  assert!(bullet_positions == vec![Position::new(10.0, 20.0)]);
+
+  let terrain_objects = loaded.read_storage::<TerrainObject>();
+  let original_objects = w.read_storage::<TerrainObject>();
+  let ammo_count = |objects: &specs::ReadStorage<TerrainObject>| objects.join().filter(|o| o.object_type == TerrainTexture::Ammo).count();
+  assert_eq!(ammo_count(&terrain_objects), ammo_count(&original_objects));
+}
+
+#[test]
+fn outdated_or_incomplete_save_is_rejected_test() {
+  use specs::world::WorldExt;
+  use crate::game::campaign::Campaign;
+  use crate::game::config::GameConfig;
+  use crate::game::constants::{RESOLUTION_X, RESOLUTION_Y};
+  use crate::game::save::{load_game, save_game};
+  use crate::game::simulation::setup_world;
+  use crate::graphics::dimensions::Dimensions;
+
+  let mut w = specs::World::new();
+  setup_world(&mut w, Dimensions::new(RESOLUTION_X as f32, RESOLUTION_Y as f32, 1.0, true), 3, &GameConfig::default(), &Campaign::default()).unwrap();
+  crate::zombie::zombies::create_zombie(&mut w, crate::shaders::Position::new(300.0, 300.0));
+  let path = std::env::temp_dir().join(format!("hinterland_outdated_save_test_{}.json", std::process::id()));
+  let path = path.to_str().unwrap();
+  save_game(&w, 10, path).unwrap();
+  let saved = json::parse(&std::fs::read_to_string(path).unwrap()).unwrap();
+
+  let load_with = |w: &mut specs::World, change: &dyn Fn(&mut json::JsonValue)| {
+    let mut value = saved.clone();
+    change(&mut value);
+    std::fs::write(path, value.dump()).unwrap();
+    load_game(w, path).err().map(|e| e.to_string())
+  };
+  assert_eq!(load_with(&mut w, &|_| ()), None);
+  let error = load_with(&mut w, &|value| value["version"] = 0.into()).unwrap();
+  assert!(error.contains("older"), "Unexpected error: {}", error);
+  let error = load_with(&mut w, &|value| { value["zombies"][0].remove("speed"); }).unwrap();
+  assert!(error.contains("zombies[0].speed"), "Unexpected error: {}", error);
+  let error = load_with(&mut w, &|value| { value["zombies"][0]["archetype"] = "giant".into(); }).unwrap();
+  assert!(error.contains("giant"), "Unexpected error: {}", error);
+  let error = load_with(&mut w, &|value| { value.remove("bullets"); }).unwrap();
+  assert!(error.contains("bullets"), "Unexpected error: {}", error);
+  let error = load_with(&mut w, &|value| { value.remove("map"); }).unwrap();
+  assert!(error.contains("map"), "Unexpected error: {}", error);
+  let _ = std::fs::remove_file(path);
+}
@@ -0,0 +1,346 @@
+use crossbeam_channel as channel;
+use specs::{Builder, prelude::{Dispatcher, DispatcherBuilder}, shred::World, world::WorldExt};
+use tiled::Map;
+
+use crate::bullet::{Bullet, collision::CollisionSystem};
+use crate::character;
+use crate::character::controls::{CharacterControl, CharacterControlSystem};
+use crate::critter::CharacterSprite;
+use crate::data;
+use crate::game::components::{Animation, Collider, Health, MovementSystem, Velocity, WorldPosition};
+use crate::game::GameRng;
+use crate::game::campaign::Campaign;
+use crate::game::config::GameConfig;
+use crate::game::error::GameError;
+use crate::game::save::load_game;
+use crate::game::constants::FIXED_TIMESTEP;
+use crate::game::mouse_controls::{MouseControl, MouseControlSystem, MouseInputState};
+use crate::game::replay::{InputEvent, InputSink, Replay};
+use crate::graphics;
+use crate::graphics::{coords_to_tile, DeltaTime, dimensions::Dimensions, FrameDelta, GameTime, Interpolation};
+use crate::graphics::camera::{CameraControl, CameraControlSystem};
+use crate::hud;
+use crate::terrain;
+use crate::terrain::{collision_grid::CollisionGrid, map_objects::MapObjects, tile_map::Terrain};
+use crate::shaders::Position;
+use crate::terrain_object::{terrain_objects::create_terrain_objects, TerrainObject, TerrainTexture};
+use crate::terrain_shape::{terrain_shape_objects::create_small_hill, TerrainShape};
+use crate::zombie;
+use crate::zombie::{waves::{SpawnSystem, ZombieWaves}, ZombieAi};
+
+pub struct SimulationSystems {
+  pub camera_system: CameraControlSystem,
+  pub character_system: CharacterControlSystem,
+  pub mouse_system: MouseControlSystem,
+}
+
+impl SimulationSystems {
+  pub fn new() -> (SimulationSystems, SimulationControls) {
+    let (camera_system, camera_control) = CameraControlSystem::new();
+    let (character_system, character_control) = CharacterControlSystem::new();
+    let (mouse_system, mouse_control) = MouseControlSystem::new();
+    (SimulationSystems { camera_system, character_system, mouse_system },
+     SimulationControls { camera_control, character_control, mouse_control })
+  }
+}
+
+/// Senders for the simulation systems. Audio messages are dropped, there is nothing to play them.
+pub struct SimulationControls {
+  pub camera_control: channel::Sender<CameraControl>,
+  pub character_control: channel::Sender<CharacterControl>,
+  pub mouse_control: channel::Sender<(MouseControl, Option<(f64, f64)>)>,
+}
+
+impl InputSink for SimulationControls {
+  fn send(&mut self, event: InputEvent) {
+    match event {
+      InputEvent::Character(control) => self.character_control.send(control).expect("Character control update error"),
+      InputEvent::Camera(control) => self.camera_control.send(control).expect("Terrain control update error"),
+      InputEvent::Mouse(control, position) => self.mouse_control.send((control, position)).expect("Mouse control update error"),
+      InputEvent::Audio(_) => (),
+    }
+  }
+}
+
+/// Loads the current campaign map and inserts every component, resource and starting entity.
+pub fn setup_world(world: &mut World, dimensions: Dimensions, seed: u64, config: &GameConfig, campaign: &Campaign) -> Result<(), GameError> {
+  let map_path = campaign.current_map();
+  let map = data::load_map(map_path)?;
+  let objects = MapObjects::from_map(&map, map_path)?;
+
+  world.register::<terrain::TerrainDrawable>();
+  world.register::<graphics::camera::CameraInputState>();
+  world.register::<character::CharacterDrawable>();
+  world.register::<hud::hud_objects::HudObjects>();
+  world.register::<CharacterSprite>();
+  world.register::<character::controls::CharacterInputState>();
+  world.register::<MouseInputState>();
+  world.register::<WorldPosition>();
+  world.register::<Velocity>();
+  world.register::<Health>();
+  world.register::<Animation>();
+  world.register::<Collider>();
+  world.register::<ZombieAi>();
+  world.register::<Bullet>();
+  world.register::<TerrainObject>();
+  world.register::<TerrainShape>();
+
+  world.insert(dimensions);
+  world.insert(character::controls::CharacterInputState::new());
+  world.insert(MouseInputState::new());
+  world.insert(DeltaTime(0.0));
+  world.insert(GameTime(0));
+  world.insert(FrameDelta(0.0));
+  world.insert(Interpolation(0.0));
+  world.insert(GameRng::new(seed));
+  world.insert(config.clone());
+  world.insert(ZombieWaves::with_spawn_points(objects.zombie_spawns.clone()));
+  world.insert(CollisionGrid::from_map(&map, &objects.blocked_tiles));
+  world.insert(Terrain::from_map(&map, map_path)?);
+  world.insert(campaign.clone());
+  world.insert(terrain::path_finding::FlowField::default());
+
+  let mut character_input = character::controls::CharacterInputState::new();
+  character_input.position = objects.player_spawn;
+  let mut camera = graphics::camera::CameraInputState::new(config.view_distance);
+  camera.jump_to(objects.player_spawn);
+  world.create_entity()
+    .with(terrain::TerrainDrawable::new(config))
+    .with(character::CharacterDrawable::new(config))
+    .with(hud::hud_objects::HudObjects::new(config))
+    .with(CharacterSprite::new())
+    .with(camera)
+    .with(character_input)
+    .with(MouseInputState::new()).build();
+
+  create_object_entities(world, &objects);
+  world.insert(objects);
+  Ok(())
+}
+
+fn create_object_entities(world: &mut World, objects: &MapObjects) {
+  for hill in objects.hills.iter() {
+    create_small_hill(world, *hill);
+  }
+  create_terrain_objects(world, objects);
+}
+
+/// Swaps in edited map objects: recreates their entities, the collision grid and the zombie spawn points.
+pub fn replace_map_objects(world: &mut World, map: &Map, objects: MapObjects) {
+  use specs::join::Join;
+
+  let stale = {
+    let entities = world.entities();
+    let (terrain_objects, terrain_shapes) = (world.read_storage::<TerrainObject>(), world.read_storage::<TerrainShape>());
+    (&entities, &terrain_objects).join().map(|(e, _)| e)
+      .chain((&entities, &terrain_shapes).join().map(|(e, _)| e))
+      .collect::<Vec<_>>()
+  };
+  world.delete_entities(&stale).expect("Map object removal failed");
+  world.maintain();
+
+  world.insert(CollisionGrid::from_map(map, &objects.blocked_tiles));
+  world.insert(terrain::path_finding::FlowField::default());
+  world.write_resource::<ZombieWaves>().spawn_points = objects.zombie_spawns.clone();
+  create_object_entities(world, &objects);
+  world.insert(objects);
+}
+
+/// Ammo of the current map objects that has been picked up.
+fn collected_ammo(world: &World) -> Vec<Position> {
+  use specs::join::Join;
+
+  let (terrain_objects, positions) = (world.read_storage::<TerrainObject>(), world.read_storage::<WorldPosition>());
+  let remaining = (&terrain_objects, &positions).join()
+    .filter(|(o, _)| o.object_type == TerrainTexture::Ammo)
+    .map(|(_, p)| p.current)
+    .collect::<Vec<_>>();
+  world.read_resource::<MapObjects>().ammo.iter()
+    .filter(|p| !remaining.contains(p))
+    .cloned()
+    .collect()
+}
+
+/// Reads the current map again for hot reloading, the player and zombies stay where they are
+/// and ammo that was picked up stays gone.
+pub fn reload_map(world: &mut World) -> Result<(), GameError> {
+  let map_path = world.read_resource::<Campaign>().current_map().to_string();
+  let map = data::load_map(&map_path)?;
+  let mut objects = MapObjects::from_map(&map, &map_path)?;
+  let collected = collected_ammo(world);
+  objects.ammo.retain(|p| !collected.contains(p));
+  world.insert(Terrain::from_map(&map, &map_path)?);
+  replace_map_objects(world, &map, objects);
+  Ok(())
+}
+
+/// Registers every system that does not need a window, GL context or audio device.
+pub fn with_simulation_systems<'a, 'b>(builder: DispatcherBuilder<'a, 'b>,
+                                       systems: SimulationSystems) -> DispatcherBuilder<'a, 'b> {
+  builder
+    .with(terrain::PreDrawSystem, "draw-prep-terrain", &[])
+    .with(character::PreDrawSystem, "draw-prep-character", &[])
+    .with(SpawnSystem, "zombie-spawner", &[])
+    .with(zombie::AiSystem, "zombie-ai", &[])
+    .with(MovementSystem, "movement", &["zombie-ai"])
+    .with(hud::PreDrawSystem, "draw-prep-hud", &[])
+    .with(systems.character_system, "character-system", &[])
+    .with(systems.camera_system, "terrain-system", &["character-system"])
+    .with(systems.mouse_system, "mouse-system", &[])
+    .with(CollisionSystem, "collision-system", &["mouse-system", "movement"])
+}
+
+/// Advances the simulation by exactly one fixed timestep.
+pub fn step(dispatcher: &mut Dispatcher, world: &mut World, tick: u64) {
+  *world.write_resource::<DeltaTime>() = DeltaTime(FIXED_TIMESTEP);
+  *world.write_resource::<GameTime>() = GameTime((tick as f64 * FIXED_TIMESTEP) as u64);
+
+  dispatcher.dispatch(world);
+  world.maintain();
+}
+
+/// Drops every entity and resource and sets the world up again with the same dimensions and config.
+fn rebuild_world(world: &mut World, campaign: &Campaign, seed: u64) -> Result<(), GameError> {
+  let dimensions = (*world.read_resource::<Dimensions>()).clone();
+  let config = (*world.read_resource::<GameConfig>()).clone();
+  *world = WorldExt::new();
+  setup_world(world, dimensions, seed, &config, campaign)
+}
+
+fn current_seed(world: &World) -> u64 {
+  world.read_resource::<GameRng>().seed
+}
+
+/// Seed to start over with. A seed given with `--seed` is kept, otherwise a new one is drawn
+/// from the game random number generator so every retry plays differently.
+pub fn restart_seed(world: &World, fixed_seed: bool) -> u64 {
+  if fixed_seed {
+    current_seed(world)
+  } else {
+    world.write_resource::<GameRng>().next_seed()
+  }
+}
+
+/// Starts the current map over.
+pub fn restart_world(world: &mut World, seed: u64) -> Result<(), GameError> {
+  let campaign = (*world.read_resource::<Campaign>()).clone();
+  rebuild_world(world, &campaign, seed)
+}
+
+/// Starts over from the first map of the campaign.
+pub fn restart_campaign(world: &mut World, seed: u64) -> Result<(), GameError> {
+  let mut campaign = (*world.read_resource::<Campaign>()).clone();
+  campaign.restart();
+  rebuild_world(world, &campaign, seed)
+}
+
+/// Switches to `map`, used when loading a game saved on another map.
+pub fn load_map(world: &mut World, map: &str) -> Result<(), GameError> {
+  let mut campaign = (*world.read_resource::<Campaign>()).clone();
+  campaign.select(map);
+  let seed = current_seed(world);
+  rebuild_world(world, &campaign, seed)
+}
+
+/// Builds the next campaign map, carrying the player's health and ammunition over.
+/// Returns false when the campaign is already on its last map.
+pub fn advance_level(world: &mut World) -> Result<bool, GameError> {
+  use specs::join::Join;
+
+  let mut campaign = (*world.read_resource::<Campaign>()).clone();
+  if !campaign.advance() {
+    return Ok(false);
+  }
+  let stats = world.read_storage::<character::CharacterDrawable>().join().next().map(|c| c.stats.clone());
+  let seed = current_seed(world);
+  rebuild_world(world, &campaign, seed)?;
+  if let Some(stats) = stats {
+    for c in (&mut world.write_storage::<character::CharacterDrawable>()).join() {
+      c.stats = stats.clone();
+    }
+  }
+  Ok(true)
+}
+
+/// Where the player stands after a simulation step.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LevelProgress {
+  Playing,
+  PlayerDied,
+  /// The exit was reached and the next campaign map is loaded.
+  Entered,
+  /// The exit of the last campaign map was reached.
+  Completed,
+}
+
+/// Ends the run when the player died and moves on to the next campaign map when an exit was reached.
+pub fn update_level(world: &mut World, tick: u64) -> Result<LevelProgress, GameError> {
+  if is_player_dead(world) {
+    println!("Player died at tick {}", tick);
+    return Ok(LevelProgress::PlayerDied);
+  }
+  if !reached_exit(world) {
+    return Ok(LevelProgress::Playing);
+  }
+  if advance_level(world)? {
+    println!("Entered {} at tick {}", world.read_resource::<Campaign>().current_map(), tick);
+    Ok(LevelProgress::Entered)
+  } else {
+    println!("Campaign completed at tick {}", tick);
+    Ok(LevelProgress::Completed)
+  }
+}
+
+pub fn reached_exit(world: &World) -> bool {
+  use specs::join::Join;
+  let objects = world.read_resource::<MapObjects>();
+  world.read_storage::<character::controls::CharacterInputState>().join()
+    .any(|ci| {
+      let tile = coords_to_tile(ci.position);
+      objects.exit_tiles.contains(&[tile.x, tile.y])
+    })
+}
+
+pub fn is_player_dead(world: &World) -> bool {
+  use specs::join::Join;
+  world.read_storage::<character::CharacterDrawable>().join().any(|c| c.is_dead())
+}
+
+pub fn run_headless(ticks: u64, seed: u64, config: &GameConfig, campaign: &Campaign, mut replay: Option<Replay>,
+                    load_path: Option<String>) -> Result<(), GameError> {
+  let mut w = WorldExt::new();
+  let dimensions = Dimensions::new(config.resolution_x as f32, config.resolution_y as f32, 1.0, true);
+  setup_world(&mut w, dimensions, seed, config, campaign)?;
+  let start_tick = match load_path {
+    Some(path) => load_game(&mut w, &path)?,
+    None => 0,
+  };
+
+  let (systems, mut controls) = SimulationSystems::new();
+  let mut dispatcher = with_simulation_systems(DispatcherBuilder::new(), systems).build();
+
+  for tick in start_tick..start_tick + ticks {
+    if let Some(r) = replay.as_mut() {
+      r.feed(tick, &mut controls);
+    }
+    step(&mut dispatcher, &mut w, tick);
+    match update_level(&mut w, tick)? {
+      LevelProgress::PlayerDied | LevelProgress::Completed => return Ok(()),
+      LevelProgress::Playing | LevelProgress::Entered => (),
+    }
+  }
+
+  println!("Headless simulation finished after {} ticks", ticks);
+  Ok(())
+}
+
+/// A world and dispatcher with the simulation systems, for tests that drive the simulation directly.
+#[cfg(test)]
+pub fn test_simulation(seed: u64, config: &GameConfig, campaign: &Campaign) -> (World, Dispatcher<'static, 'static>, SimulationControls) {
+  let mut w = WorldExt::new();
+  setup_world(&mut w, Dimensions::new(config.resolution_x as f32, config.resolution_y as f32, 1.0, true), seed, config, campaign).unwrap();
+
+  let (systems, controls) = SimulationSystems::new();
+  let dispatcher = with_simulation_systems(DispatcherBuilder::new(), systems).build();
+  (w, dispatcher, controls)
+}
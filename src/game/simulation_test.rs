@@ -0,0 +1,144 @@
+#[test]
+fn headless_character_movement_test() {
+  use specs::world::WorldExt;
+  use crate::character::controls::{CharacterControl, CharacterInputState};
+  use crate::game::config::GameConfig;
+  use crate::game::campaign::Campaign;
+  use crate::game::simulation::{step, test_simulation};
+  use crate::graphics::camera::CameraInputState;
+
+  let (mut w, mut dispatcher, controls) = test_simulation(1, &GameConfig::default(), &Campaign::default());
+
+  controls.character_control.send(CharacterControl::Left).unwrap();
+  for tick in 0..10 {
+    step(&mut dispatcher, &mut w, tick);
+  }
+
+  let character_input = w.read_storage::<CharacterInputState>();
+  let camera_input = w.read_storage::<CameraInputState>();
+  let (ci, camera) = specs::join::Join::join((&character_input, &camera_input)).next().unwrap();
+  assert!(ci.position.x() < 0.0, "Character should have moved left");
+  assert_eq!(camera.position, ci.position, "Camera should follow the character");
+}
+
+#[cfg(test)]
+fn zombie_positions_after(ticks: u64, seed: u64) -> Vec<crate::shaders::Position> {
+  use specs::world::WorldExt;
+  use crate::game::campaign::Campaign;
+  use crate::game::components::WorldPosition;
+  use crate::game::config::GameConfig;
+  use crate::game::simulation::{step, test_simulation};
+  use crate::zombie::ZombieAi;
+
+  let (mut w, mut dispatcher, _controls) = test_simulation(seed, &GameConfig::default(), &Campaign::default());
+
+  for tick in 0..ticks {
+    step(&mut dispatcher, &mut w, tick);
+  }
+
+  let zombies = w.read_storage::<ZombieAi>();
+  let positions = w.read_storage::<WorldPosition>();
+  specs::join::Join::join((&zombies, &positions))
+    .map(|(_, p)| p.current)
+    .collect()
+}
+
+#[test]
+fn same_seed_same_simulation_test() {
+  assert!(zombie_positions_after(300, 42) == zombie_positions_after(300, 42), "Same seed should reproduce zombie movement");
+}
+
+#[test]
+fn bullet_hit_damages_zombie_test() {
+  use specs::{Builder, prelude::RunNow, world::WorldExt};
+  use crate::bullet::{Bullet, collision::CollisionSystem};
+  use crate::game::components::{Animation, Collider, Health, WorldPosition};
+  use crate::game::GameRng;
+  use crate::graphics::orientation::{Orientation, Stance};
+  use crate::shaders::Position;
+  use crate::terrain::collision_grid::CollisionGrid;
+
+  let mut w = specs::World::new();
+  w.register::<Bullet>();
+  w.register::<WorldPosition>();
+  w.register::<Collider>();
+  w.register::<Health>();
+  w.register::<Animation>();
+  w.insert(GameRng::new(1));
+  w.insert(CollisionGrid::new(128, 128));
+
+  let zombie = w.create_entity()
+    .with(WorldPosition::new(Position::origin()))
+    .with(Health(1.0))
+    .with(Animation::new(Orientation::Left, Stance::Still))
+    .with(Collider::new(15.0, 15.0))
+    .build();
+  w.create_entity()
+    .with(Bullet::new(0.0))
+    .with(WorldPosition::new(Position::new(5.0, 5.0)))
+    .build();
+
+  CollisionSystem.run_now(&w);
+  w.maintain();
+
+  assert_eq!(w.read_storage::<Health>().get(zombie).unwrap().0, 0.5, "Bullet should halve zombie health");
+}
+
+#[test]
+fn reload_map_keeps_picked_up_ammo_test() {
+  use specs::{join::Join, world::WorldExt};
+  use crate::character::controls::CharacterInputState;
+  use crate::game::constants::{RESOLUTION_X, RESOLUTION_Y};
+  use crate::game::config::GameConfig;
+  use crate::game::campaign::Campaign;
+  use crate::game::simulation::{reload_map, setup_world};
+  use crate::graphics::dimensions::Dimensions;
+  use crate::shaders::Position;
+  use crate::terrain::tile_map::Terrain;
+  use crate::terrain_object::{TerrainObject, TerrainTexture};
+
+  let mut w = specs::World::new();
+  setup_world(&mut w, Dimensions::new(RESOLUTION_X as f32, RESOLUTION_Y as f32, 1.0, true), 1, &GameConfig::default(), &Campaign::default()).unwrap();
+  let ammo = |w: &specs::World| w.read_storage::<TerrainObject>().join().filter(|o| o.object_type == TerrainTexture::Ammo).count();
+  let ammo_count = ammo(&w);
+  let picked_up = {
+    let (entities, objects) = (w.entities(), w.read_storage::<TerrainObject>());
+    (&entities, &objects).join().find(|(_, o)| o.object_type == TerrainTexture::Ammo).map(|(e, _)| e).unwrap()
+  };
+  w.delete_entity(picked_up).unwrap();
+  w.maintain();
+  for ci in (&mut w.write_storage::<CharacterInputState>()).join() {
+    ci.position = Position::new(64.0, -32.0);
+  }
+  w.write_resource::<Terrain>().is_dirty = false;
+
+  reload_map(&mut w).unwrap();
+  assert_eq!(ammo(&w), ammo_count - 1);
+  assert!(w.read_resource::<Terrain>().is_dirty);
+  assert!(w.read_storage::<CharacterInputState>().join().all(|ci| ci.position == Position::new(64.0, -32.0)));
+}
+
+#[test]
+fn restart_draws_new_seed_unless_fixed_test() {
+  use specs::world::WorldExt;
+  use crate::game::campaign::Campaign;
+  use crate::game::config::GameConfig;
+  use crate::game::constants::{RESOLUTION_X, RESOLUTION_Y};
+  use crate::game::GameRng;
+  use crate::game::simulation::{restart_seed, restart_world, setup_world};
+  use crate::graphics::dimensions::Dimensions;
+
+  let world_with_seed = |seed| {
+    let mut w = specs::World::new();
+    setup_world(&mut w, Dimensions::new(RESOLUTION_X as f32, RESOLUTION_Y as f32, 1.0, true), seed, &GameConfig::default(), &Campaign::default()).unwrap();
+    w
+  };
+  let mut w = world_with_seed(42);
+  assert_eq!(restart_seed(&w, true), 42);
+
+  let seed = restart_seed(&w, false);
+  assert_ne!(seed, 42, "A retry should not replay the same waves");
+  assert_eq!(seed, restart_seed(&world_with_seed(42), false), "The next seed should follow from the current one");
+  restart_world(&mut w, seed).unwrap();
+  assert_eq!(w.read_resource::<GameRng>().seed, seed);
+}
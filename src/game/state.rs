@@ -0,0 +1,73 @@
+use crate::game::constants::{COMPLETED_TEXT, GAME_OVER_TEXT, PAUSED_TEXT, TITLE_TEXT};
+use crate::editor::EditorTool;
+use crate::gfx_app::bindings::{Action, InputBindings};
+use crate::gfx_app::WindowStatus;
+
+/// Top level state around the simulation. The simulation is only stepped while `Playing`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameState {
+  Title,
+  Playing,
+  Paused,
+  GameOver,
+  /// The exit of the last campaign map was reached.
+  Completed,
+  /// The simulation is frozen while the map is edited with `tool`. Closing the editor goes back
+  /// to the pause screen if it was opened from there.
+  Editing { tool: EditorTool, paused: bool },
+}
+
+/// What the main loop has to do besides switching to the next state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Transition {
+  None,
+  Restart,
+  /// Start over from the first map of the campaign.
+  NewGame,
+  OpenEditor,
+  Quit,
+}
+
+impl GameState {
+  pub fn next(self, status: &WindowStatus) -> (GameState, Transition) {
+    match (self, status) {
+      (_, WindowStatus::Close) => (self, Transition::Quit),
+      (GameState::Title, WindowStatus::Confirm) => (GameState::Playing, Transition::None),
+      (GameState::Playing, WindowStatus::Pause) | (GameState::Playing, WindowStatus::FocusLost) => (GameState::Paused, Transition::None),
+      (GameState::Paused, WindowStatus::Pause) | (GameState::Paused, WindowStatus::Confirm) => (GameState::Playing, Transition::None),
+      (GameState::GameOver, WindowStatus::Confirm) => (GameState::Playing, Transition::Restart),
+      (GameState::Completed, WindowStatus::Confirm) => (GameState::Playing, Transition::NewGame),
+      (GameState::Playing, WindowStatus::ToggleEditor) | (GameState::Paused, WindowStatus::ToggleEditor) => (self, Transition::OpenEditor),
+      (GameState::Editing { paused: true, .. }, WindowStatus::ToggleEditor) => (GameState::Paused, Transition::None),
+      (GameState::Editing { paused: false, .. }, WindowStatus::ToggleEditor) => (GameState::Playing, Transition::None),
+      (GameState::Playing, WindowStatus::Quit) | (GameState::Editing { .. }, WindowStatus::Quit) => (self, Transition::None),
+      (_, WindowStatus::Quit) => (self, Transition::Quit),
+      _ => (self, Transition::None),
+    }
+  }
+
+  /// Editor state for `tool` that returns to this state when the editor is closed.
+  pub fn edit_with(self, tool: EditorTool) -> GameState {
+    GameState::Editing { tool, paused: self == GameState::Paused }
+  }
+
+  pub fn is_editing(self) -> bool {
+    matches!(self, GameState::Editing { .. })
+  }
+
+  /// Text shown over the frozen world or the editor, if any, naming the inputs bound to its actions.
+  pub fn overlay_text(self, bindings: &InputBindings) -> Option<String> {
+    let text = match self {
+      GameState::Title => TITLE_TEXT,
+      GameState::Playing => return None,
+      GameState::Paused => PAUSED_TEXT,
+      GameState::GameOver => GAME_OVER_TEXT,
+      GameState::Completed => COMPLETED_TEXT,
+      GameState::Editing { tool, .. } => tool.label(),
+    };
+    Some(text.replace("{confirm}", &bindings.input_names(Action::Confirm))
+      .replace("{quit}", &bindings.input_names(Action::Quit))
+      .replace("{erase}", &bindings.input_names(Action::Erase))
+      .replace("{save}", &bindings.input_names(Action::QuickSave)))
+  }
+}
@@ -0,0 +1,96 @@
+#[test]
+fn state_transitions_test() {
+  use crate::game::state::{GameState, Transition};
+  use crate::gfx_app::WindowStatus;
+
+  assert_eq!(GameState::Title.next(&WindowStatus::Confirm), (GameState::Playing, Transition::None));
+  assert_eq!(GameState::Playing.next(&WindowStatus::Pause), (GameState::Paused, Transition::None));
+  assert_eq!(GameState::Playing.next(&WindowStatus::FocusLost), (GameState::Paused, Transition::None));
+  assert_eq!(GameState::Paused.next(&WindowStatus::Pause), (GameState::Playing, Transition::None));
+  assert_eq!(GameState::Paused.next(&WindowStatus::FocusLost), (GameState::Paused, Transition::None));
+  assert_eq!(GameState::GameOver.next(&WindowStatus::Confirm), (GameState::Playing, Transition::Restart));
+  assert_eq!(GameState::Completed.next(&WindowStatus::Confirm), (GameState::Playing, Transition::NewGame));
+  assert_eq!(GameState::Completed.next(&WindowStatus::Pause), (GameState::Completed, Transition::None));
+  assert_eq!(GameState::Paused.next(&WindowStatus::Quit), (GameState::Paused, Transition::Quit));
+  assert_eq!(GameState::Playing.next(&WindowStatus::Close), (GameState::Playing, Transition::Quit));
+}
+
+#[test]
+fn quit_key_is_ignored_while_playing_test() {
+  use crate::game::state::{GameState, Transition};
+  use crate::gfx_app::bindings::InputBindings;
+  use crate::gfx_app::WindowStatus;
+
+  let bindings = InputBindings::default();
+  assert_eq!(GameState::Playing.next(&WindowStatus::Quit), (GameState::Playing, Transition::None));
+  assert_eq!(GameState::Playing.overlay_text(&bindings), None);
+  assert!(GameState::GameOver.overlay_text(&bindings).is_some());
+}
+
+#[test]
+fn overlay_text_names_bound_inputs_test() {
+  use crate::game::state::GameState;
+  use crate::gfx_app::bindings::InputBindings;
+
+  let text = GameState::Title.overlay_text(&InputBindings::default()).unwrap();
+  assert_eq!(text, "Hinterland - Return to start, Q to quit");
+
+  let bindings = InputBindings::parse(r#"{ "Confirm": ["Space", "MouseLeft"], "Quit": "F10" }"#, "test").unwrap();
+  let text = GameState::Paused.overlay_text(&bindings).unwrap();
+  assert_eq!(text, "Paused - MouseLeft/Space to resume, F10 to quit");
+}
+
+#[test]
+fn editor_transitions_test() {
+  use crate::editor::EditorTool;
+  use crate::game::state::{GameState, Transition};
+  use crate::gfx_app::{bindings::InputBindings, WindowStatus};
+
+  assert_eq!(GameState::Playing.next(&WindowStatus::ToggleEditor), (GameState::Playing, Transition::OpenEditor));
+  assert_eq!(GameState::Paused.next(&WindowStatus::ToggleEditor), (GameState::Paused, Transition::OpenEditor));
+  assert_eq!(GameState::Title.next(&WindowStatus::ToggleEditor), (GameState::Title, Transition::None));
+  let editing = GameState::Playing.edit_with(EditorTool::House);
+  assert_eq!(editing.next(&WindowStatus::ToggleEditor), (GameState::Playing, Transition::None));
+  assert_eq!(editing.next(&WindowStatus::Quit), (editing, Transition::None));
+  assert_eq!(editing.overlay_text(&InputBindings::default()).unwrap(), "Editor: place houses - MouseRight removes, F5 saves");
+  let editing_paused = GameState::Paused.edit_with(EditorTool::House);
+  assert!(editing_paused.is_editing());
+  assert_eq!(editing_paused.next(&WindowStatus::ToggleEditor), (GameState::Paused, Transition::None));
+}
+
+#[cfg(not(feature = "godmode"))]
+#[test]
+fn zombie_touch_ends_game_and_restart_rebuilds_world_test() {
+  use specs::{join::Join, world::WorldExt};
+  use crate::character::CharacterDrawable;
+  use crate::game::constants::{DEATH_ANIMATION_TIME, FIXED_TIMESTEP};
+  use crate::game::config::GameConfig;
+  use crate::game::campaign::Campaign;
+  use crate::game::simulation::{is_player_dead, restart_world, step, test_simulation};
+  use crate::graphics::orientation::Stance;
+  use crate::shaders::Position;
+  use crate::zombie::{zombies::create_zombie, ZombieAi};
+
+  let (mut w, mut dispatcher, _controls) = test_simulation(3, &GameConfig::default(), &Campaign::default());
+  let zombie_count = w.read_storage::<ZombieAi>().count();
+
+  for cd in (&mut w.write_storage::<CharacterDrawable>()).join() {
+    cd.stats.health = 1;
+  }
+  create_zombie(&mut w, Position::origin());
+  step(&mut dispatcher, &mut w, 0);
+  assert!(w.read_storage::<CharacterDrawable>().join().all(|cd| cd.stance == Stance::NormalDeath));
+  assert!(!is_player_dead(&w), "Game should not end before the death animation");
+
+  let death_ticks = (DEATH_ANIMATION_TIME / FIXED_TIMESTEP) as u64 + 1;
+  for tick in 1..=death_ticks {
+    step(&mut dispatcher, &mut w, tick);
+  }
+  assert!(is_player_dead(&w), "Losing the last hit point should kill the player");
+
+  restart_world(&mut w, 3).unwrap();
+  assert!(!is_player_dead(&w));
+  assert_eq!(w.read_storage::<ZombieAi>().count(), zombie_count);
+  step(&mut dispatcher, &mut w, 0);
+  assert!(!is_player_dead(&w), "Restarted world should be playable");
+}
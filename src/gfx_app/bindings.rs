@@ -0,0 +1,180 @@
+use std::{collections::HashMap, fs, io};
+
+use glutin::{MouseButton, VirtualKeyCode};
+use glutin::VirtualKeyCode::*;
+
+use crate::game::error::GameError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+  MoveUp,
+  MoveDown,
+  MoveLeft,
+  MoveRight,
+  Aim,
+  Fire,
+  Reload,
+  ZoomIn,
+  ZoomOut,
+  QuickSave,
+  QuickLoad,
+  ReloadBindings,
+  Pause,
+  Confirm,
+  Quit,
+  ToggleEditor,
+  /// Removes objects or picks the tile under the cursor in the map editor.
+  Erase,
+  NextTool,
+  NextTile,
+  PreviousTile,
+}
+
+const ACTIONS: [Action; 20] = [Action::MoveUp, Action::MoveDown, Action::MoveLeft, Action::MoveRight, Action::Aim,
+  Action::Fire, Action::Reload, Action::ZoomIn, Action::ZoomOut, Action::QuickSave, Action::QuickLoad,
+  Action::ReloadBindings, Action::Pause, Action::Confirm, Action::Quit, Action::ToggleEditor, Action::Erase,
+  Action::NextTool, Action::NextTile, Action::PreviousTile];
+
+const KEYS: [VirtualKeyCode; 95] = [Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, Key0,
+  A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+  Escape, F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+  Snapshot, Scroll, Pause, Insert, Home, Delete, End, PageDown, PageUp, Left, Up, Right, Down,
+  Back, Return, Space, Tab, LAlt, LControl, LShift, LWin, RAlt, RControl, RShift, RWin,
+  Minus, Equals, Comma, Period, Slash, Semicolon, Apostrophe, LBracket, RBracket, Backslash, Grave,
+  Numpad0, Numpad1, Numpad2, Numpad3, Numpad4, Numpad5, Numpad6, Numpad7, Numpad8, Numpad9];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Input {
+  Key(VirtualKeyCode),
+  Mouse(MouseButton),
+}
+
+impl Input {
+  /// Keys use their `VirtualKeyCode` names, mouse buttons are MouseLeft, MouseRight, MouseMiddle or MouseN.
+  pub fn from_name(name: &str) -> Option<Input> {
+    match name {
+      "MouseLeft" => Some(Input::Mouse(MouseButton::Left)),
+      "MouseRight" => Some(Input::Mouse(MouseButton::Right)),
+      "MouseMiddle" => Some(Input::Mouse(MouseButton::Middle)),
+      _ if name.starts_with("Mouse") => name["Mouse".len()..].parse().ok().map(|b| Input::Mouse(MouseButton::Other(b))),
+      _ => KEYS.iter().find(|k| format!("{:?}", k) == name).map(|k| Input::Key(*k)),
+    }
+  }
+
+  /// The name used for this input in the bindings file.
+  pub fn name(self) -> String {
+    match self {
+      Input::Key(key) => format!("{:?}", key),
+      Input::Mouse(MouseButton::Left) => "MouseLeft".to_string(),
+      Input::Mouse(MouseButton::Right) => "MouseRight".to_string(),
+      Input::Mouse(MouseButton::Middle) => "MouseMiddle".to_string(),
+      Input::Mouse(MouseButton::Other(b)) => format!("Mouse{}", b),
+    }
+  }
+}
+
+fn action_from_name(name: &str) -> Option<Action> {
+  ACTIONS.iter().find(|a| format!("{:?}", a) == name).copied()
+}
+
+#[derive(Clone)]
+pub struct InputBindings {
+  actions: HashMap<Input, Action>,
+}
+
+impl Default for InputBindings {
+  fn default() -> InputBindings {
+    let mut bindings = InputBindings { actions: HashMap::new() };
+    let defaults = [
+      (Input::Key(W), Action::MoveUp),
+      (Input::Key(S), Action::MoveDown),
+      (Input::Key(A), Action::MoveLeft),
+      (Input::Key(D), Action::MoveRight),
+      (Input::Key(LControl), Action::Aim),
+      (Input::Key(RControl), Action::Aim),
+      (Input::Mouse(MouseButton::Left), Action::Fire),
+      (Input::Key(R), Action::Reload),
+      (Input::Key(Z), Action::ZoomIn),
+      (Input::Key(X), Action::ZoomOut),
+      (Input::Key(F5), Action::QuickSave),
+      (Input::Key(F9), Action::QuickLoad),
+      (Input::Key(F8), Action::ReloadBindings),
+      (Input::Key(Escape), Action::Pause),
+      (Input::Key(Return), Action::Confirm),
+      (Input::Key(Q), Action::Quit),
+      (Input::Key(F2), Action::ToggleEditor),
+      (Input::Mouse(MouseButton::Right), Action::Erase),
+      (Input::Key(Tab), Action::NextTool),
+      (Input::Key(Period), Action::NextTile),
+      (Input::Key(Comma), Action::PreviousTile),
+    ];
+    for (input, action) in defaults.iter() {
+      bindings.bind(*action, *input);
+    }
+    bindings
+  }
+}
+
+impl InputBindings {
+  /// Reads bindings from `path`, falling back to the defaults if the file does not exist.
+  pub fn load(path: &str) -> Result<InputBindings, GameError> {
+    match fs::read_to_string(path) {
+      Ok(content) => InputBindings::parse(&content, path),
+      Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(InputBindings::default()),
+      Err(e) => Err(GameError::io(path, e)),
+    }
+  }
+
+  /// Parses a JSON object mapping each action name to a key or mouse button name, or a list of them.
+  /// Actions missing from the file keep no binding.
+  pub fn parse(content: &str, path: &str) -> Result<InputBindings, GameError> {
+    let value = json::parse(content).map_err(|e| GameError::parse(path, e.to_string()))?;
+    if !value.is_object() {
+      return Err(GameError::parse(path, "expected a JSON object of action bindings".to_string()));
+    }
+
+    let mut bindings = InputBindings { actions: HashMap::new() };
+    for (action_name, inputs) in value.entries() {
+      let action = action_from_name(action_name)
+        .ok_or_else(|| GameError::parse(path, format!("unknown action \"{}\"", action_name)))?;
+      let inputs = if inputs.is_string() {
+        vec![inputs]
+      } else if inputs.is_array() {
+        inputs.members().collect()
+      } else {
+        return Err(GameError::parse(path, format!("{} must be a key name or a list of them, got {}", action_name, inputs)));
+      };
+      for input_value in inputs {
+        let input_name = input_value.as_str().unwrap_or_default();
+        let input = Input::from_name(input_name)
+          .ok_or_else(|| GameError::parse(path, format!("unknown key or button \"{}\" for {}", input_value, action_name)))?;
+        if let Some(existing) = bindings.action(input) {
+          return Err(GameError::parse(path, format!("{} is bound to both {:?} and {:?}", input_name, existing, action)));
+        }
+        bindings.bind(action, input);
+      }
+    }
+    Ok(bindings)
+  }
+
+  pub fn bind(&mut self, action: Action, input: Input) {
+    self.actions.insert(input, action);
+  }
+
+  pub fn action(&self, input: Input) -> Option<Action> {
+    self.actions.get(&input).copied()
+  }
+
+  /// Names of every input bound to `action`, e.g. "Q/Escape", for showing to the player.
+  pub fn input_names(&self, action: Action) -> String {
+    let mut names = self.actions.iter()
+      .filter(|(_, a)| **a == action)
+      .map(|(input, _)| input.name())
+      .collect::<Vec<_>>();
+    if names.is_empty() {
+      return "(unbound)".to_string();
+    }
+    names.sort();
+    names.join("/")
+  }
+}
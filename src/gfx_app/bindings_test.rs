@@ -0,0 +1,84 @@
+#[test]
+fn bindings_file_matches_defaults_test() {
+  use glutin::{MouseButton, VirtualKeyCode};
+  use crate::gfx_app::bindings::{Action, Input, InputBindings};
+
+  let content = std::fs::read_to_string("keybindings.json").unwrap();
+  let bindings = InputBindings::parse(&content, "keybindings.json").unwrap();
+  let defaults = InputBindings::default();
+  for input in [Input::Key(VirtualKeyCode::W), Input::Key(VirtualKeyCode::RControl), Input::Mouse(MouseButton::Left),
+                Input::Key(VirtualKeyCode::Escape)].iter() {
+    assert_eq!(bindings.action(*input), defaults.action(*input));
+  }
+  assert_eq!(bindings.action(Input::Key(VirtualKeyCode::F8)), Some(Action::ReloadBindings));
+  assert_eq!(bindings.action(Input::Key(VirtualKeyCode::Escape)), Some(Action::Pause));
+}
+
+#[test]
+fn multiple_inputs_per_action_test() {
+  use glutin::{MouseButton, VirtualKeyCode};
+  use crate::gfx_app::bindings::{Action, Input, InputBindings};
+
+  let azerty = r#"{ "MoveUp": ["Z", "Up"], "MoveLeft": "Q", "Fire": ["MouseLeft", "Space"], "Aim": ["MouseRight"] }"#;
+  let bindings = InputBindings::parse(azerty, "azerty.json").unwrap();
+  assert_eq!(bindings.action(Input::Key(VirtualKeyCode::Z)), Some(Action::MoveUp));
+  assert_eq!(bindings.action(Input::Key(VirtualKeyCode::Up)), Some(Action::MoveUp));
+  assert_eq!(bindings.action(Input::Key(VirtualKeyCode::Q)), Some(Action::MoveLeft));
+  assert_eq!(bindings.action(Input::Key(VirtualKeyCode::Space)), Some(Action::Fire));
+  assert_eq!(bindings.action(Input::Mouse(MouseButton::Right)), Some(Action::Aim));
+  assert_eq!(bindings.action(Input::Key(VirtualKeyCode::W)), None);
+}
+
+#[test]
+fn invalid_bindings_test() {
+  use crate::gfx_app::bindings::InputBindings;
+
+  assert!(InputBindings::parse(r#"{ "Jump": ["Space"] }"#, "test").is_err());
+  assert!(InputBindings::parse(r#"{ "Fire": ["Banana"] }"#, "test").is_err());
+  let error = InputBindings::parse(r#"{ "MoveUp": 87 }"#, "test").err().unwrap().to_string();
+  assert!(error.contains("MoveUp"), "Unexpected error: {}", error);
+  let error = InputBindings::parse(r#"{ "Fire": ["Space"], "Reload": ["Space"] }"#, "test").err().unwrap().to_string();
+  assert!(error.contains("Space"), "Unexpected error: {}", error);
+}
+
+#[test]
+fn bound_input_sends_control_test() {
+  use crossbeam_channel as channel;
+  use glutin::{ElementState, VirtualKeyCode};
+  use crate::character::controls::CharacterControl;
+  use crate::game::simulation::SimulationControls;
+  use crate::gfx_app::{process_input, WindowStatus};
+  use crate::gfx_app::bindings::{Input, InputBindings};
+  use crate::gfx_app::controls::TilemapControls;
+
+  let (audio_control, _audio_queue) = channel::unbounded();
+  let (camera_control, _camera_queue) = channel::unbounded();
+  let (character_control, character_queue) = channel::unbounded();
+  let (mouse_control, _mouse_queue) = channel::unbounded();
+  let mut controls = TilemapControls::new(audio_control, SimulationControls { camera_control, character_control, mouse_control });
+  let bindings = InputBindings::parse(r#"{ "MoveLeft": ["Q", "Left"], "Quit": ["F10"] }"#, "test").unwrap();
+
+  let status = process_input(Input::Key(VirtualKeyCode::Left), ElementState::Pressed, (0.0, 0.0), Some(&mut controls), &bindings);
+  assert!(status == WindowStatus::Open);
+  assert_eq!(character_queue.try_recv().ok(), Some(CharacterControl::Left));
+
+  // Releasing one of two held keys bound to the same action keeps moving.
+  process_input(Input::Key(VirtualKeyCode::Q), ElementState::Pressed, (0.0, 0.0), Some(&mut controls), &bindings);
+  assert_eq!(character_queue.try_recv().ok(), Some(CharacterControl::Left));
+  process_input(Input::Key(VirtualKeyCode::Q), ElementState::Released, (0.0, 0.0), Some(&mut controls), &bindings);
+  assert!(character_queue.try_recv().is_err());
+  process_input(Input::Key(VirtualKeyCode::Left), ElementState::Released, (0.0, 0.0), Some(&mut controls), &bindings);
+  assert_eq!(character_queue.try_recv().ok(), Some(CharacterControl::XMoveStop));
+
+  let status = process_input(Input::Key(VirtualKeyCode::Escape), ElementState::Pressed, (0.0, 0.0), Some(&mut controls), &bindings);
+  assert!(status == WindowStatus::Open, "Unbound Escape should not pause");
+  let status = process_input(Input::Key(VirtualKeyCode::F10), ElementState::Pressed, (0.0, 0.0), Some(&mut controls), &bindings);
+  assert!(status == WindowStatus::Quit);
+
+  // Without controls, as during a replay, gameplay input is dropped but quitting still works.
+  let status = process_input(Input::Key(VirtualKeyCode::Left), ElementState::Pressed, (0.0, 0.0), None, &bindings);
+  assert!(status == WindowStatus::Open);
+  assert!(character_queue.try_recv().is_err());
+  let status = process_input(Input::Key(VirtualKeyCode::F10), ElementState::Pressed, (0.0, 0.0), None, &bindings);
+  assert!(status == WindowStatus::Quit);
+}
@@ -1,8 +1,14 @@
+use std::collections::HashSet;
+
 use crossbeam_channel as channel;
 
 use crate::audio::Effects;
 use crate::character::controls::CharacterControl;
-use crate::gfx_app::mouse_controls::MouseControl;
+use crate::editor::EditorControl;
+use crate::game::mouse_controls::MouseControl;
+use crate::game::replay::{InputEvent, InputSink};
+use crate::game::simulation::SimulationControls;
+use crate::gfx_app::bindings::{Action, Input, InputBindings};
 use crate::graphics::camera::CameraControl;
 
 pub enum Control {
@@ -13,57 +19,121 @@ pub enum Control {
 
 pub struct TilemapControls {
   audio_control: channel::Sender<Effects>,
-  terrain_control: channel::Sender<CameraControl>,
-  character_control: channel::Sender<CharacterControl>,
-  mouse_control: channel::Sender<(MouseControl, Option<(f64, f64)>)>,
+  simulation: SimulationControls,
+  recorder: Option<channel::Sender<InputEvent>>,
+  editor_control: Option<channel::Sender<EditorControl>>,
+  editing: bool,
+  held: HashSet<Input>,
 }
 
 impl TilemapControls {
-  pub fn new(atc: channel::Sender<Effects>,
-             ttc: channel::Sender<CameraControl>,
-             ctc: channel::Sender<CharacterControl>,
-             mtc: channel::Sender<(MouseControl, Option<(f64, f64)>)>) -> TilemapControls {
+  pub fn new(atc: channel::Sender<Effects>, simulation: SimulationControls) -> TilemapControls {
     TilemapControls {
       audio_control: atc,
-      terrain_control: ttc,
-      character_control: ctc,
-      mouse_control: mtc,
+      simulation,
+      recorder: None,
+      editor_control: None,
+      editing: false,
+      held: HashSet::new(),
+    }
+  }
+
+  pub fn record_to(&mut self, recorder: channel::Sender<InputEvent>) {
+    self.recorder = Some(recorder);
+  }
+
+  pub fn edit_with(&mut self, editor_control: channel::Sender<EditorControl>) {
+    self.editor_control = Some(editor_control);
+  }
+
+  pub fn is_editing(&self) -> bool {
+    self.editing
+  }
+
+  /// Keeps track of held inputs. Returns true for the release of an input while another input
+  /// bound to the same action is still held, so the action keeps going.
+  pub fn is_still_held(&mut self, input: Input, pressed: bool, action: Action, bindings: &InputBindings) -> bool {
+    if pressed {
+      self.held.insert(input);
+      return false;
+    }
+    self.held.remove(&input);
+    self.held.iter().any(|held| bindings.action(*held) == Some(action))
+  }
+
+  /// Sends input to the map editor instead of the character. Held movement is stopped on both sides
+  /// so nothing keeps moving after the switch.
+  pub fn set_editing(&mut self, editing: bool) {
+    if self.editing == editing {
+      return;
+    }
+    if editing {
+      for control in [CharacterControl::XMoveStop, CharacterControl::YMoveStop, CharacterControl::CtrlReleased].iter() {
+        self.move_character(*control);
+      }
+    } else {
+      for control in [EditorControl::XMoveStop, EditorControl::YMoveStop, EditorControl::ZoomStop, EditorControl::Apply(false), EditorControl::Erase(false)].iter() {
+        self.edit(*control);
+      }
+    }
+    self.editing = editing;
+  }
+
+  /// Editor input is not part of the simulation, so it is never recorded.
+  pub fn edit(&mut self, control: EditorControl) {
+    if let Some(ref editor_control) = self.editor_control {
+      editor_control.send(control).expect("Editor control update error");
     }
   }
 
   pub fn zoom(&mut self, control: &Control) {
     match control {
-      Control::Plus => self.terrain_control.send(CameraControl::ZoomIn),
-      Control::Negative => self.terrain_control.send(CameraControl::ZoomOut),
-      Control::Released => self.terrain_control.send(CameraControl::ZoomStop),
-    }.expect("Terrain control update error");
+      Control::Plus => self.send(InputEvent::Camera(CameraControl::ZoomIn)),
+      Control::Negative => self.send(InputEvent::Camera(CameraControl::ZoomOut)),
+      Control::Released => self.send(InputEvent::Camera(CameraControl::ZoomStop)),
+    }
   }
 
   pub fn ctrl_pressed(&mut self, is_ctrl: bool) {
     if is_ctrl {
-      self.character_control.send(CharacterControl::CtrlPressed)
+      self.send(InputEvent::Character(CharacterControl::CtrlPressed))
     } else {
-      self.character_control.send(CharacterControl::CtrlReleased)
-    }.expect("Character Ctrl control update error");
+      self.send(InputEvent::Character(CharacterControl::CtrlReleased))
+    }
   }
 
   pub fn move_character(&mut self, character_control: CharacterControl) {
-    self.character_control.send(character_control).expect("Character move control update error");
+    self.send(InputEvent::Character(character_control))
   }
 
   pub fn reload_weapon(&mut self, is_reloading: bool) {
     if is_reloading {
-      self.character_control.send(CharacterControl::ReloadPressed)
+      self.send(InputEvent::Character(CharacterControl::ReloadPressed))
     } else {
-      self.character_control.send(CharacterControl::ReloadReleased)
-    }.expect("Character reload weapon control update error");
+      self.send(InputEvent::Character(CharacterControl::ReloadReleased))
+    }
   }
 
   pub fn mouse_left_click(&mut self, mouse_pos: Option<(f64, f64)>) {
-    self.mouse_control.send((MouseControl::LeftClick, mouse_pos)).expect("Mouse control shoot update error");
+    self.send(InputEvent::Mouse(MouseControl::LeftClick, mouse_pos));
     match mouse_pos {
-      Some(_) => self.audio_control.send(Effects::PistolFire),
-      _ => self.audio_control.send(Effects::None),
-    }.expect("Audio control update error");
+      Some(_) => self.send(InputEvent::Audio(Effects::PistolFire)),
+      _ => self.send(InputEvent::Audio(Effects::None)),
+    }
+  }
+}
+
+impl InputSink for TilemapControls {
+  fn send(&mut self, event: InputEvent) {
+    // Once the recorder is dropped the recording has stopped.
+    if let Some(ref recorder) = self.recorder {
+      if recorder.send(event).is_err() {
+        self.recorder = None;
+      }
+    }
+    match event {
+      InputEvent::Audio(effect) => self.audio_control.send(effect).expect("Audio control update error"),
+      event => self.simulation.send(event),
+    }
   }
 }
@@ -0,0 +1,251 @@
+use std::{collections::HashMap, fs, path::{Path, PathBuf}, time::SystemTime};
+
+use crossbeam_channel as channel;
+use gfx::PipelineState;
+use specs::{shred::World, world::WorldExt};
+
+use crate::critter::CritterData;
+use crate::data;
+use crate::game::campaign::Campaign;
+use crate::game::config::GameConfig;
+use crate::game::constants::{ASSETS_DIR, CHARACTER_JSON_PATH, HOT_RELOAD_INTERVAL, SHADERS_DIR, ZOMBIE_ARCHETYPES_PATH, ZOMBIE_JSON_PATH};
+use crate::game::error::GameError;
+use crate::game::simulation::reload_map;
+use crate::shaders::{bullet_pipeline, critter_pipeline, static_element_pipeline, text_pipeline, tilemap_pipeline};
+use crate::terrain::generator;
+use crate::terrain::tile_map::Terrain;
+use crate::zombie::{archetypes::ZombieArchetype, load_sprite_sheets};
+
+/// The GLSL programs in `src/shaders`, each used by one pipeline type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShaderProgram {
+  Bullet,
+  Critter,
+  StaticElement,
+  Terrain,
+  Text,
+}
+
+impl ShaderProgram {
+  fn from_file_name(file_name: &str) -> Option<ShaderProgram> {
+    match file_name.split('.').next() {
+      Some("bullet") => Some(ShaderProgram::Bullet),
+      Some("character") => Some(ShaderProgram::Critter),
+      Some("static_element") => Some(ShaderProgram::StaticElement),
+      Some("terrain") => Some(ShaderProgram::Terrain),
+      Some("text") => Some(ShaderProgram::Text),
+      _ => None,
+    }
+  }
+
+  fn file_stem(self) -> &'static str {
+    match self {
+      ShaderProgram::Bullet => "bullet",
+      ShaderProgram::Critter => "character",
+      ShaderProgram::StaticElement => "static_element",
+      ShaderProgram::Terrain => "terrain",
+      ShaderProgram::Text => "text",
+    }
+  }
+
+  /// Vertex and fragment shader sources.
+  fn read_sources(self) -> Result<(Vec<u8>, Vec<u8>), GameError> {
+    let read = |stage: &str| {
+      let path = format!("{}/{}.{}.glsl", SHADERS_DIR, self.file_stem(), stage);
+      fs::read(&path).map_err(|e| GameError::io(&path, e))
+    };
+    Ok((read("v")?, read("f")?))
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AssetChange {
+  Map,
+  CharacterSprites,
+  ZombieSprites,
+  ZombieArchetypes,
+  Shader(ShaderProgram),
+}
+
+/// What a changed file means for the running game, `map_files` being the current map and its tileset images.
+pub fn asset_change(path: &Path, map_files: &[PathBuf]) -> Option<AssetChange> {
+  let extension = path.extension().and_then(|e| e.to_str());
+  if same_file(path, Path::new(CHARACTER_JSON_PATH)) {
+    Some(AssetChange::CharacterSprites)
+  } else if same_file(path, Path::new(ZOMBIE_JSON_PATH)) {
+    Some(AssetChange::ZombieSprites)
+  } else if same_file(path, Path::new(ZOMBIE_ARCHETYPES_PATH)) {
+    Some(AssetChange::ZombieArchetypes)
+  } else if extension == Some("glsl") {
+    path.file_name().and_then(|name| name.to_str()).and_then(ShaderProgram::from_file_name).map(AssetChange::Shader)
+  } else if extension == Some("tsx") || map_files.iter().any(|file| same_file(path, file)) {
+    Some(AssetChange::Map)
+  } else {
+    None
+  }
+}
+
+/// Generated maps have no file to read again, and a map open in the editor under another name
+/// would no longer match the edited document after a reload.
+pub fn map_reloadable(current_map: &str, edited_map: Option<&str>) -> bool {
+  generator::parse_map_name(current_map).is_none() && (edited_map.is_none() || edited_map == Some(current_map))
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+  a == b || match (fs::canonicalize(a), fs::canonicalize(b)) {
+    (Ok(a), Ok(b)) => a == b,
+    _ => false,
+  }
+}
+
+/// Finds files added or modified under the watched directories by comparing modification times and sizes.
+pub struct AssetWatcher {
+  dirs: Vec<PathBuf>,
+  stamps: HashMap<PathBuf, (SystemTime, u64)>,
+}
+
+impl AssetWatcher {
+  pub fn new(dirs: &[&Path]) -> AssetWatcher {
+    let mut watcher = AssetWatcher {
+      dirs: dirs.iter().map(|dir| dir.to_path_buf()).collect(),
+      stamps: HashMap::new(),
+    };
+    watcher.changed_files();
+    watcher
+  }
+
+  /// Files changed since the previous call, sorted by path.
+  pub fn changed_files(&mut self) -> Vec<PathBuf> {
+    let mut files = vec![];
+    for dir in &self.dirs {
+      list_files(dir, &mut files);
+    }
+    let mut changed = files.into_iter()
+      .filter(|(path, stamp)| self.stamps.insert(path.clone(), *stamp) != Some(*stamp))
+      .map(|(path, _)| path)
+      .collect::<Vec<_>>();
+    changed.sort();
+    changed
+  }
+}
+
+fn list_files(dir: &Path, files: &mut Vec<(PathBuf, (SystemTime, u64))>) {
+  let entries = match fs::read_dir(dir) {
+    Ok(entries) => entries,
+    Err(_) => return,
+  };
+  for entry in entries.filter_map(Result::ok) {
+    let path = entry.path();
+    match entry.metadata() {
+      Ok(metadata) if metadata.is_dir() => list_files(&path, files),
+      Ok(metadata) => if let Ok(modified) = metadata.modified() {
+        files.push((path, (modified, metadata.len())));
+      },
+      Err(_) => (),
+    }
+  }
+}
+
+/// Reloaded assets for the `DrawSystem`, which owns the pipelines and sprite tables.
+pub enum Reload<R: gfx::Resources> {
+  CharacterSprites(Vec<CritterData>),
+  ZombieSprites(Vec<CritterData>),
+  /// Archetypes and the sprite sheet of each of them.
+  ZombieArchetypes(Vec<ZombieArchetype>, Vec<gfx::handle::ShaderResourceView<R, [f32; 4]>>),
+  TileSheets,
+  BulletPipeline(PipelineState<R, bullet_pipeline::Meta>),
+  CritterPipeline(PipelineState<R, critter_pipeline::Meta>),
+  StaticElementPipeline(PipelineState<R, static_element_pipeline::Meta>),
+  TilemapPipeline(PipelineState<R, tilemap_pipeline::Meta>),
+  TextPipeline(PipelineState<R, text_pipeline::Meta>),
+}
+
+/// Watches `assets/` and `src/shaders` and reloads changed files into the running game.
+/// Whatever fails to load or compile keeps its previous version.
+pub struct HotReload<R: gfx::Resources> {
+  watcher: AssetWatcher,
+  sender: channel::Sender<Reload<R>>,
+  since_check: f64,
+}
+
+impl<R: gfx::Resources> HotReload<R> {
+  pub fn new() -> (HotReload<R>, channel::Receiver<Reload<R>>) {
+    let (tx, rx) = channel::unbounded();
+    (HotReload {
+      watcher: AssetWatcher::new(&[Path::new(ASSETS_DIR), Path::new(SHADERS_DIR)]),
+      sender: tx,
+      since_check: 0.0,
+    }, rx)
+  }
+
+  /// `edited_map` is the map open in the editor, if any.
+  pub fn update<F>(&mut self, world: &mut World, factory: &mut F, frame_time: f64, edited_map: Option<&str>)
+    where F: gfx::Factory<R> {
+    self.since_check += frame_time;
+    if self.since_check < HOT_RELOAD_INTERVAL {
+      return;
+    }
+    self.since_check = 0.0;
+
+    let map_files = current_map_files(world);
+    let reload_map = map_reloadable(world.read_resource::<Campaign>().current_map(), edited_map);
+    let mut changes = vec![];
+    for change in self.watcher.changed_files().iter()
+      .filter_map(|path| asset_change(path, &map_files))
+      .filter(|change| *change != AssetChange::Map || reload_map) {
+      if !changes.contains(&change) {
+        changes.push(change);
+      }
+    }
+    for change in changes {
+      match self.reload(world, factory, change) {
+        Ok(()) => println!("Reloaded {:?}", change),
+        Err(e) => eprintln!("Reloading {:?} failed, keeping the previous version: {}", change, e),
+      }
+    }
+  }
+
+  fn reload<F>(&self, world: &mut World, factory: &mut F, change: AssetChange) -> Result<(), GameError>
+    where F: gfx::Factory<R> {
+    use gfx::traits::FactoryExt;
+
+    let reload = match change {
+      AssetChange::Map => {
+        reload_map(world)?;
+        Reload::TileSheets
+      }
+      AssetChange::CharacterSprites => Reload::CharacterSprites(data::load_character()?),
+      AssetChange::ZombieSprites => Reload::ZombieSprites(data::load_zombie()?),
+      AssetChange::ZombieArchetypes => {
+        let archetypes = data::load_zombie_archetypes(ZOMBIE_ARCHETYPES_PATH)?;
+        let sprite_sheets = load_sprite_sheets(factory, &archetypes)?;
+        world.write_resource::<GameConfig>().zombie_archetypes = archetypes.clone();
+        Reload::ZombieArchetypes(archetypes, sprite_sheets)
+      }
+      AssetChange::Shader(program) => {
+        let (vert, frag) = program.read_sources()?;
+        let compile_error = |e: gfx::PipelineStateError<String>| GameError::Device { context: e.to_string() };
+        match program {
+          ShaderProgram::Bullet => Reload::BulletPipeline(
+            factory.create_pipeline_simple(&vert, &frag, bullet_pipeline::new()).map_err(compile_error)?),
+          ShaderProgram::Critter => Reload::CritterPipeline(
+            factory.create_pipeline_simple(&vert, &frag, critter_pipeline::new()).map_err(compile_error)?),
+          ShaderProgram::StaticElement => Reload::StaticElementPipeline(
+            factory.create_pipeline_simple(&vert, &frag, static_element_pipeline::new()).map_err(compile_error)?),
+          ShaderProgram::Terrain => Reload::TilemapPipeline(
+            factory.create_pipeline_simple(&vert, &frag, tilemap_pipeline::new()).map_err(compile_error)?),
+          ShaderProgram::Text => Reload::TextPipeline(
+            factory.create_pipeline_simple(&vert, &frag, text_pipeline::new()).map_err(compile_error)?),
+        }
+      }
+    };
+    self.sender.send(reload).expect("Hot reload channel error");
+    Ok(())
+  }
+}
+
+fn current_map_files(world: &World) -> Vec<PathBuf> {
+  let mut files = vec![PathBuf::from(world.read_resource::<Campaign>().current_map())];
+  files.extend(world.read_resource::<Terrain>().tilesets.iter().map(|tileset| tileset.image_path.clone()));
+  files
+}
@@ -0,0 +1,47 @@
+#[test]
+fn asset_watcher_reports_changed_files_test() {
+  use std::fs;
+  use crate::gfx_app::hot_reload::AssetWatcher;
+
+  let dir = std::env::temp_dir().join(format!("hinterland_asset_watcher_test_{}", std::process::id()));
+  let _ = fs::remove_dir_all(&dir);
+  fs::create_dir_all(dir.join("maps")).unwrap();
+  fs::write(dir.join("character.json"), "{}").unwrap();
+
+  let mut watcher = AssetWatcher::new(&[dir.as_path()]);
+  assert!(watcher.changed_files().is_empty());
+  fs::write(dir.join("character.json"), "{\"frames\": {}}").unwrap();
+  fs::write(dir.join("maps").join("tilemap.tmx"), "<map/>").unwrap();
+  assert_eq!(watcher.changed_files(), vec![dir.join("character.json"), dir.join("maps").join("tilemap.tmx")]);
+  assert!(watcher.changed_files().is_empty());
+  let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn asset_change_test() {
+  use std::path::{Path, PathBuf};
+  use crate::game::constants::{CHARACTER_JSON_PATH, MAP_FILE_PATH, TERRAIN_TILESET_PATH, ZOMBIE_ARCHETYPES_PATH, ZOMBIE_JSON_PATH};
+  use crate::gfx_app::hot_reload::{asset_change, AssetChange, ShaderProgram};
+
+  let map_files = vec![PathBuf::from(MAP_FILE_PATH), PathBuf::from(TERRAIN_TILESET_PATH)];
+  assert_eq!(asset_change(Path::new(CHARACTER_JSON_PATH), &map_files), Some(AssetChange::CharacterSprites));
+  assert_eq!(asset_change(Path::new(ZOMBIE_JSON_PATH), &map_files), Some(AssetChange::ZombieSprites));
+  assert_eq!(asset_change(Path::new(ZOMBIE_ARCHETYPES_PATH), &map_files), Some(AssetChange::ZombieArchetypes));
+  assert_eq!(asset_change(Path::new("src/shaders/character.f.glsl"), &map_files), Some(AssetChange::Shader(ShaderProgram::Critter)));
+  assert_eq!(asset_change(Path::new("src/shaders/static_element.v.glsl"), &map_files), Some(AssetChange::Shader(ShaderProgram::StaticElement)));
+  assert_eq!(asset_change(Path::new(MAP_FILE_PATH), &map_files), Some(AssetChange::Map));
+  assert_eq!(asset_change(Path::new(TERRAIN_TILESET_PATH), &map_files), Some(AssetChange::Map));
+  assert_eq!(asset_change(Path::new("assets/maps/forest.tmx"), &map_files), None);
+  assert_eq!(asset_change(Path::new("assets/audio/pistol.ogg"), &map_files), None);
+}
+
+#[test]
+fn map_reloadable_test() {
+  use crate::game::constants::MAP_FILE_PATH;
+  use crate::gfx_app::hot_reload::map_reloadable;
+
+  assert!(map_reloadable(MAP_FILE_PATH, None));
+  assert!(map_reloadable(MAP_FILE_PATH, Some(MAP_FILE_PATH)));
+  assert!(!map_reloadable(MAP_FILE_PATH, Some("assets/maps/outskirts.tmx")), "The editor has another map open");
+  assert!(!map_reloadable("generated:42:128x128", None), "Generated maps have no file");
+}
@@ -1,30 +1,29 @@
 use std::time;
 
-use specs::{Builder, prelude::DispatcherBuilder, shred::World, world::WorldExt};
-
-use crate::{bullet, terrain_shape};
-use crate::audio::AudioSystem;
-use crate::bullet::bullets::Bullets;
-use crate::bullet::collision::CollisionSystem;
-use crate::character;
-use crate::character::controls::CharacterControlSystem;
-use crate::critter::CharacterSprite;
+use crossbeam_channel as channel;
+use specs::{prelude::DispatcherBuilder, shred::World, world::WorldExt};
+
+use crate::audio::{AudioSystem, default_backend};
+use crate::editor::MapEditor;
+use crate::game::constants::{FIXED_TIMESTEP, MAX_FRAME_TIME, QUICKSAVE_PATH};
+use crate::game::campaign::Campaign;
+use crate::game::config::GameConfig;
+use crate::game::error::GameError;
+use crate::game::replay::{InputEvent, InputRecorder, Replay};
+use crate::game::save::{load_game, save_game};
+use crate::game::simulation::{LevelProgress, restart_campaign, restart_seed, restart_world, setup_world, SimulationSystems, step, update_level, with_simulation_systems};
+use crate::game::state::{GameState, Transition};
 use crate::gfx_app::{Window, WindowStatus};
 use crate::gfx_app::controls::TilemapControls;
-use crate::gfx_app::mouse_controls::{MouseControlSystem, MouseInputState};
+use crate::gfx_app::hot_reload::HotReload;
 use crate::gfx_app::renderer::DeviceRenderer;
 use crate::gfx_app::system::DrawSystem;
-use crate::graphics;
-use crate::graphics::{DeltaTime, dimensions::Dimensions, GameTime};
-use crate::graphics::camera::CameraControlSystem;
-use crate::hud;
-use crate::terrain;
-use crate::terrain_object;
-use crate::zombie;
-use crate::zombie::zombies::Zombies;
-use crate::game::constants::SMALL_HILLS;
-
-pub fn run<W, D, F>(window: &mut W)
+use crate::graphics::{dimensions::Dimensions, FrameDelta, Interpolation};
+
+/// Starts in the map editor when `edit` is set. Restarts keep the seed only if it is `fixed_seed`.
+#[allow(clippy::too_many_arguments)]
+pub fn run<W, D, F>(window: &mut W, seed: u64, fixed_seed: bool, config: &GameConfig, campaign: &Campaign, record_path: Option<String>,
+                    replay: Option<Replay>, load_path: Option<String>, edit: bool) -> Result<(), GameError>
   where W: Window<D, F>,
         D: gfx::Device + 'static,
         F: gfx::Factory<D::Resources>,
@@ -36,107 +35,203 @@ pub fn run<W, D, F>(window: &mut W)
                                    viewport_size.1,
                                    window.get_hidpi_factor(),
                                    window.is_windowed());
-  setup_world(&mut w, dimensions);
-  dispatch_loop(window, &mut w);
-}
-
-fn setup_world(world: &mut World, dimensions: Dimensions) {
-  world.register::<terrain::TerrainDrawable>();
-  world.register::<graphics::camera::CameraInputState>();
-  world.register::<character::CharacterDrawable>();
-  world.register::<hud::hud_objects::HudObjects>();
-  world.register::<terrain_object::terrain_objects::TerrainObjects>();
-  world.register::<terrain_shape::terrain_shape_objects::TerrainShapeObjects>();
-  world.register::<Zombies>();
-  world.register::<Bullets>();
-  world.register::<CharacterSprite>();
-  world.register::<character::controls::CharacterInputState>();
-  world.register::<MouseInputState>();
-
-  world.insert(dimensions);
-  world.insert(character::controls::CharacterInputState::new());
-  world.insert(MouseInputState::new());
-  world.insert(DeltaTime(0.0));
-  world.insert(GameTime(0));
-
-  let mut hills = terrain_shape::terrain_shape_objects::TerrainShapeObjects::new();
-
-  for hill in SMALL_HILLS.iter() {
-    hills.small_hill(hill[0], hill[1]);
-  }
-
-  world.create_entity()
-    .with(terrain::TerrainDrawable::new())
-    .with(character::CharacterDrawable::new())
-    .with(hud::hud_objects::HudObjects::new())
-    .with(terrain_object::terrain_objects::TerrainObjects::new())
-    .with(hills)
-    .with(Zombies::new())
-    .with(Bullets::new())
-    .with(CharacterSprite::new())
-    .with(graphics::camera::CameraInputState::new())
-    .with(character::controls::CharacterInputState::new())
-    .with(MouseInputState::new()).build();
+  setup_world(&mut w, dimensions, seed, config, campaign)?;
+  let start_tick = match load_path {
+    Some(path) => load_game(&mut w, &path)?,
+    None => 0,
+  };
+  let recorder = match record_path {
+    Some(path) => Some(InputRecorder::new(&path, seed, config, campaign)?),
+    None => None,
+  };
+  dispatch_loop(window, &mut w, start_tick, fixed_seed, recorder, replay, edit)
 }
 
 fn dispatch_loop<W, D, F>(window: &mut W,
-                          w: &mut World)
+                          w: &mut World,
+                          start_tick: u64,
+                          fixed_seed: bool,
+                          recorder: Option<(InputRecorder, channel::Sender<InputEvent>)>,
+                          mut replay: Option<Replay>,
+                          edit: bool) -> Result<(), GameError>
   where W: Window<D, F>,
         D: gfx::Device + 'static,
         F: gfx::Factory<D::Resources>,
         D::CommandBuffer: Send {
   let (mut device_renderer, encoder_queue) = DeviceRenderer::new(window.create_buffers(2));
-  let draw = {
+  let mut draw = {
     let rtv = window.get_render_target_view();
     let dsv = window.get_depth_stencil_view();
-    DrawSystem::new(window.get_factory(), &rtv, &dsv, encoder_queue)
+    let config = w.read_resource::<GameConfig>();
+    let bindings = window.get_bindings().clone();
+    DrawSystem::new(window.get_factory(), &rtv, &dsv, encoder_queue, &config, bindings)?
   };
+  let mut hot_reload = if cfg!(feature = "hotreload") {
+    let (hot_reload, reload) = HotReload::new();
+    draw.reload_from(reload);
+    Some(hot_reload)
+  } else {
+    None
+  };
+
+  let (audio_system, audio_control) = AudioSystem::new(default_backend()?);
+  let (systems, simulation_controls) = SimulationSystems::new();
+  let (mut editor, editor_control) = MapEditor::new();
+  let mut controls = TilemapControls::new(audio_control, simulation_controls);
+  controls.edit_with(editor_control);
+  let mut recorder = recorder.map(|(recorder, record_control)| {
+    controls.record_to(record_control);
+    recorder
+  });
 
-  let (audio_system, audio_control) = AudioSystem::new();
-  let (terrain_system, terrain_control) = CameraControlSystem::new();
-  let (character_system, character_control) = CharacterControlSystem::new();
-  let (mouse_system, mouse_control) = MouseControlSystem::new();
-  let controls = TilemapControls::new(audio_control, terrain_control, character_control, mouse_control);
+  let mut simulation_dispatcher = with_simulation_systems(DispatcherBuilder::new(), systems).build();
 
-  let mut dispatcher = DispatcherBuilder::new()
+  let mut render_dispatcher = DispatcherBuilder::new()
     .with(draw, "drawing", &[])
-    .with(terrain::PreDrawSystem, "draw-prep-terrain", &["drawing"])
-    .with(character::PreDrawSystem, "draw-prep-character", &["drawing"])
-    .with(zombie::PreDrawSystem, "draw-prep-zombie", &["drawing"])
-    .with(bullet::PreDrawSystem, "draw-prep-bullet", &["drawing"])
-    .with(hud::PreDrawSystem, "draw-prep-hud", &[])
-    .with(terrain_system, "terrain-system", &[])
-    .with(terrain_object::PreDrawSystem, "draw-prep-terrain_object", &["terrain-system"])
-    .with(terrain_shape::PreDrawSystem, "draw-prep-terrain_shape_object", &["terrain-system"])
-    .with(character_system, "character-system", &[])
-    .with(mouse_system, "mouse-system", &[])
     .with(audio_system, "audio-system", &[])
-    .with(CollisionSystem, "collision-system", &["mouse-system"])
     .build();
 
-  window.set_controls(controls);
+  // While a replay is running its messages are fed in place of window input.
+  let mut replay_controls = if replay.is_some() {
+    Some(controls)
+  } else {
+    window.set_controls(controls);
+    None
+  };
 
-  let start_time = time::Instant::now();
+  // A replay has nobody to press start, so it goes straight into the game.
+  let mut state = if replay.is_some() {
+    GameState::Playing
+  } else if edit {
+    editor.open(w)?;
+    window.set_editing(true);
+    GameState::Playing.edit_with(editor.tool)
+  } else {
+    GameState::Title
+  };
+  let mut tick = start_tick;
+  let mut accumulator = 0.0;
   let mut last_time = time::Instant::now();
   loop {
     let elapsed = last_time.elapsed();
-    let delta = f64::from(elapsed.subsec_nanos()) / 1e9 + elapsed.as_secs() as f64;
-    // Throttle update speed
-    if delta >= 0.0083 {
-      last_time = time::Instant::now();
-      dispatcher.dispatch(&w);
-      w.maintain();
+    last_time = time::Instant::now();
+    let frame_time = (f64::from(elapsed.subsec_nanos()) / 1e9 + elapsed.as_secs() as f64).min(MAX_FRAME_TIME);
+
+    if state == GameState::Playing {
+      accumulator += frame_time;
+      while accumulator >= FIXED_TIMESTEP {
+        if let (Some(r), Some(c)) = (replay.as_mut(), replay_controls.as_mut()) {
+          r.feed(tick, c);
+        }
+        step(&mut simulation_dispatcher, w, tick);
+        tick += 1;
+        accumulator -= FIXED_TIMESTEP;
+        // Checked every tick so no further step runs on a dead player or on a map that was already left.
+        match update_level(w, tick)? {
+          LevelProgress::Playing => (),
+          LevelProgress::Entered => accumulator = 0.0,
+          LevelProgress::PlayerDied => {
+            state = GameState::GameOver;
+            accumulator = 0.0;
+            break;
+          }
+          LevelProgress::Completed => {
+            state = GameState::Completed;
+            accumulator = 0.0;
+            break;
+          }
+        }
+      }
+    }
 
-      *w.write_resource::<DeltaTime>() = DeltaTime(delta);
-      *w.write_resource::<GameTime>() = GameTime(start_time.elapsed().as_secs());
+    if let GameState::Editing { ref mut tool, .. } = state {
+      editor.update(w, frame_time);
+      *tool = editor.tool;
+    }
 
-      device_renderer.draw(window.get_device());
+    if let Some(hot_reload) = hot_reload.as_mut() {
+      hot_reload.update(w, window.get_factory(), frame_time, editor.map_name());
+    }
 
-      window.swap_window();
+    w.insert(state);
+    *w.write_resource::<FrameDelta>() = FrameDelta(frame_time);
+    *w.write_resource::<Interpolation>() = Interpolation((accumulator / FIXED_TIMESTEP) as f32);
+    render_dispatcher.dispatch(w);
+
+    device_renderer.draw(window.get_device());
+    window.swap_window();
+
+    let status = window.poll_events();
+    if let Some(r) = replay.as_ref() {
+      // Gameplay input is thrown away while replaying, the window can still be closed.
+      if status == WindowStatus::Close || status == WindowStatus::Quit {
+        break;
+      }
+      if r.is_finished() || state != GameState::Playing {
+        println!("Replay finished at tick {}", tick);
+        if let Some(c) = replay_controls.take() {
+          window.set_controls(c);
+        }
+        replay = None;
+      }
+    } else {
+      match status {
+        WindowStatus::QuickSave if state.is_editing() => match editor.save() {
+          Ok(path) => println!("Map saved to {}", path),
+          Err(e) => eprintln!("Saving the map failed: {}", e),
+        },
+        WindowStatus::QuickSave if state == GameState::Playing || state == GameState::Paused => match save_game(w, tick, QUICKSAVE_PATH) {
+          Ok(()) => println!("Game saved to {}", QUICKSAVE_PATH),
+          Err(e) => eprintln!("Quicksave failed: {}", e),
+        },
+        WindowStatus::QuickLoad if !state.is_editing() => match load_game(w, QUICKSAVE_PATH) {
+          Ok(saved_tick) => {
+            // A replay starts from the recorded seed, it cannot reproduce the loaded state.
+            if let Some(mut r) = recorder.take() {
+              r.record_pending(tick)?;
+              println!("Recording stopped, a quickload cannot be replayed");
+            }
+            tick = saved_tick;
+            accumulator = 0.0;
+            state = GameState::Playing;
+          }
+          Err(e) => eprintln!("Quickload failed: {}", e),
+        },
+        _ => (),
+      }
+
+      let (mut next_state, transition) = state.next(&status);
+      match transition {
+        Transition::Quit => break,
+        Transition::Restart | Transition::NewGame => {
+          let seed = restart_seed(w, fixed_seed);
+          println!("seed={}", seed);
+          if let Some(r) = recorder.as_mut() {
+            r.record_restart(tick, seed)?;
+          }
+          if transition == Transition::Restart {
+            restart_world(w, seed)?;
+          } else {
+            restart_campaign(w, seed)?;
+          }
+          tick = 0;
+          accumulator = 0.0;
+        }
+        // Map edits are not part of the recorded input, a replay would run on the unedited map.
+        Transition::OpenEditor if recorder.is_some() => println!("The map editor is disabled while recording"),
+        Transition::OpenEditor => match editor.open(w) {
+          Ok(()) => next_state = state.edit_with(editor.tool),
+          Err(e) => eprintln!("Could not open the map editor: {}", e),
+        },
+        Transition::None => (),
+      }
+      state = next_state;
+      window.set_editing(state.is_editing());
     }
 
-    if let WindowStatus::Close = window.poll_events() {
-      break;
+    if let Some(r) = recorder.as_mut() {
+      r.record_pending(tick)?;
     }
   }
+  Ok(())
 }
@@ -1,21 +1,25 @@
 use gfx::format::SurfaceType;
 use gfx::handle::{DepthStencilView, RenderTargetView};
 use gfx::memory::Typed;
-use glutin::{KeyboardInput, MouseButton, PossiblyCurrent, WindowedContext};
+use glutin::{ElementState, PossiblyCurrent, WindowedContext};
 use glutin::dpi::LogicalSize;
 use glutin::ElementState::{Pressed, Released};
-use glutin::VirtualKeyCode::{A, D, Escape, R, S, W, X, Z};
 use std::fmt::{Display, Formatter, Result};
 
 use crate::character::controls::CharacterControl;
-use crate::game::constants::{GAME_TITLE, RESOLUTION_X, RESOLUTION_Y};
+use crate::editor::EditorControl;
+use crate::game::constants::{GAME_TITLE, KEYBINDINGS_PATH};
+use crate::gfx_app::bindings::{Action, Input, InputBindings};
 use crate::gfx_app::controls::{Control, TilemapControls};
 
+pub mod bindings;
+mod bindings_test;
+pub mod hot_reload;
+mod hot_reload_test;
 pub mod init;
 pub mod renderer;
 pub mod system;
 pub mod controls;
-pub mod mouse_controls;
 
 pub type ColorFormat = gfx::format::Rgba8;
 pub type DepthFormat = gfx::format::DepthStencil;
@@ -26,18 +30,20 @@ pub const DEPTH_FORMAT_VALUE: SurfaceType = SurfaceType::D24_S8;
 #[derive(Debug)]
 pub struct GameOptions {
   windowed_mode: bool,
+  resolution: (u32, u32),
 }
 
 impl Display for GameOptions {
   fn fmt(&self, f: &mut Formatter) -> Result {
-    write!(f, "{}", format!("windowed_mode={}", self.windowed_mode))
+    write!(f, "{}", format!("windowed_mode={}, resolution={}x{}", self.windowed_mode, self.resolution.0, self.resolution.1))
   }
 }
 
 impl GameOptions {
-  pub fn new(windowed_mode: bool) -> GameOptions {
+  pub fn new(windowed_mode: bool, resolution: (u32, u32)) -> GameOptions {
     GameOptions {
       windowed_mode,
+      resolution,
     }
   }
 }
@@ -51,11 +57,12 @@ pub struct WindowContext {
   render_target_view: RenderTargetView<gfx_device_gl::Resources, ColorFormat>,
   depth_stencil_view: DepthStencilView<gfx_device_gl::Resources, DepthFormat>,
   mouse_pos: (f64, f64),
-  game_options: GameOptions
+  game_options: GameOptions,
+  bindings: InputBindings,
 }
 
 impl WindowContext {
-  pub fn new(game_options: GameOptions) -> WindowContext {
+  pub fn new(game_options: GameOptions, bindings: InputBindings) -> WindowContext {
     let events_loop = glutin::EventsLoop::new();
 
     let window_title = glutin::WindowBuilder::new()
@@ -64,7 +71,7 @@ impl WindowContext {
     println!("{}", game_options);
 
     let builder = if game_options.windowed_mode {
-      let logical_size = LogicalSize::new(RESOLUTION_X.into(), RESOLUTION_Y.into());
+      let logical_size = LogicalSize::new(game_options.resolution.0.into(), game_options.resolution.1.into());
       window_title
         .with_dimensions(logical_size)
         .with_decorations(false)
@@ -126,6 +133,7 @@ impl WindowContext {
       depth_stencil_view: DepthStencilView::new(dsv),
       mouse_pos: (0.0, 0.0),
       game_options,
+      bindings,
     }
   }
 }
@@ -134,12 +142,21 @@ impl WindowContext {
 pub enum WindowStatus {
   Open,
   Close,
+  QuickSave,
+  QuickLoad,
+  ReloadBindings,
+  Pause,
+  Confirm,
+  Quit,
+  FocusLost,
+  ToggleEditor,
 }
 
 pub trait Window<D: gfx::Device, F: gfx::Factory<D::Resources>> {
   fn swap_window(&mut self);
   fn create_buffers(&mut self, count: usize) -> Vec<D::CommandBuffer>;
   fn set_controls(&mut self, controls: controls::TilemapControls);
+  fn set_editing(&mut self, editing: bool);
   fn get_viewport_size(&mut self) -> (f32, f32);
   fn get_device(&mut self) -> &mut D;
   fn get_factory(&mut self) -> &mut F;
@@ -148,6 +165,7 @@ pub trait Window<D: gfx::Device, F: gfx::Factory<D::Resources>> {
   fn get_depth_stencil_view(&mut self) -> DepthStencilView<D::Resources, DepthFormat>;
   fn poll_events(&mut self) -> WindowStatus;
   fn is_windowed(&self) -> bool;
+  fn get_bindings(&self) -> &InputBindings;
 }
 
 impl Window<gfx_device_gl::Device, gfx_device_gl::Factory> for WindowContext {
@@ -171,9 +189,15 @@ impl Window<gfx_device_gl::Device, gfx_device_gl::Factory> for WindowContext {
     self.controls = Some(controls);
   }
 
+  fn set_editing(&mut self, editing: bool) {
+    if let Some(ref mut controls) = self.controls {
+      controls.set_editing(editing);
+    }
+  }
+
   fn get_viewport_size(&mut self) -> (f32, f32) {
     if self.game_options.windowed_mode {
-      (RESOLUTION_X as f32, RESOLUTION_Y as f32)
+      (self.game_options.resolution.0 as f32, self.game_options.resolution.1 as f32)
     } else {
       let monitor = self.events_loop.get_available_monitors().next().expect("No monitor found");
       let monitor_resolution = monitor.get_dimensions();
@@ -206,100 +230,130 @@ impl Window<gfx_device_gl::Device, gfx_device_gl::Factory> for WindowContext {
   }
 
   fn poll_events(&mut self) -> WindowStatus {
-    use glutin::WindowEvent::{CursorMoved, CloseRequested, MouseInput};
+    use glutin::WindowEvent::{CursorMoved, CloseRequested, Focused, MouseInput};
 
-    let controls = match self.controls {
-      Some(ref mut c) => c,
-      None => panic!("Terrain controls have not been initialized"),
-    };
+    // Without controls, e.g. while a replay feeds the input, only quitting is honoured.
+    let mut controls = self.controls.as_mut();
 
     let m_pos = &mut self.mouse_pos;
+    let bindings = &self.bindings;
     let mut game_status = WindowStatus::Open;
 
     self.events_loop.poll_events(|event| {
-      game_status = if let glutin::Event::WindowEvent { event, .. } = event {
+      let event_status = if let glutin::Event::WindowEvent { event, .. } = event {
         match event {
-          glutin::WindowEvent::KeyboardInput { input, .. } => { process_keyboard_input(input, controls) }
-          MouseInput { state: Pressed, button: MouseButton::Left, .. } => {
-            controls.mouse_left_click(Some(*m_pos));
-            WindowStatus::Open
+          glutin::WindowEvent::KeyboardInput { input: glutin::KeyboardInput { state, virtual_keycode: Some(key), .. }, .. } => {
+            process_input(Input::Key(key), state, *m_pos, controls.as_deref_mut(), bindings)
           }
-          MouseInput { state: Released, button: MouseButton::Left, .. } => {
-            controls.mouse_left_click(None);
-            WindowStatus::Open
+          MouseInput { state, button, .. } => {
+            process_input(Input::Mouse(button), state, *m_pos, controls.as_deref_mut(), bindings)
           }
           CursorMoved { position, .. } => {
             *m_pos = ((position.x as f32).into(), (position.y as f32).into());
+            if let Some(controls) = controls.as_mut().filter(|c| c.is_editing()) {
+              controls.edit(EditorControl::Cursor(m_pos.0, m_pos.1));
+            }
             WindowStatus::Open
           }
           CloseRequested => WindowStatus::Close,
+          Focused(false) => WindowStatus::FocusLost,
           _ => WindowStatus::Open,
         }
       } else {
         WindowStatus::Open
       };
+      if game_status == WindowStatus::Open {
+        game_status = event_status;
+      }
     });
+
+    if game_status == WindowStatus::ReloadBindings {
+      match InputBindings::load(KEYBINDINGS_PATH) {
+        Ok(bindings) => {
+          self.bindings = bindings;
+          println!("Reloaded key bindings from {}", KEYBINDINGS_PATH);
+        }
+        Err(e) => eprintln!("Keeping current key bindings: {}", e),
+      }
+      game_status = WindowStatus::Open;
+    }
     game_status
   }
 
   fn is_windowed(&self) -> bool {
     self.game_options.windowed_mode
   }
+
+  fn get_bindings(&self) -> &InputBindings {
+    &self.bindings
+  }
 }
 
-fn process_keyboard_input(input: glutin::KeyboardInput, controls: &mut TilemapControls) -> WindowStatus {
-  match input {
-    KeyboardInput { state: Pressed, virtual_keycode: Some(Z), .. } => {
-      controls.zoom(&Control::Negative);
-    }
-    KeyboardInput { state: Pressed, virtual_keycode: Some(X), .. } => {
-      controls.zoom(&Control::Plus);
-    }
-    KeyboardInput { state: Released, virtual_keycode: Some(Z), .. } |
-    KeyboardInput { state: Released, virtual_keycode: Some(X), .. } => {
-      controls.zoom(&Control::Released);
-    }
-    KeyboardInput { state: Pressed, virtual_keycode: Some(W), .. } => {
-      controls.move_character(CharacterControl::Up);
-    }
-    KeyboardInput { state: Pressed, virtual_keycode: Some(S), .. } => {
-      controls.move_character(CharacterControl::Down);
-    }
-    KeyboardInput { state: Released, virtual_keycode: Some(W), .. } |
-    KeyboardInput { state: Released, virtual_keycode: Some(S), .. } => {
-      controls.move_character(CharacterControl::YMoveStop);
-    }
-    KeyboardInput { state: Pressed, virtual_keycode: Some(A), .. } => {
-      controls.move_character(CharacterControl::Left);
-    }
-    KeyboardInput { state: Pressed, virtual_keycode: Some(D), .. } => {
-      controls.move_character(CharacterControl::Right);
-    }
-    KeyboardInput { state: Released, virtual_keycode: Some(A), .. } |
-    KeyboardInput { state: Released, virtual_keycode: Some(D), .. } => {
-      controls.move_character(CharacterControl::XMoveStop);
-    }
-    KeyboardInput { state: Pressed, virtual_keycode: Some(R), .. } => {
-      controls.reload_weapon(true);
-    }
-    KeyboardInput { state: Released, virtual_keycode: Some(R), .. } => {
-      controls.reload_weapon(false);
-    }
-    KeyboardInput { state: Pressed, modifiers, .. } => {
-      if modifiers.ctrl {
-        controls.ctrl_pressed(true);
-      }
-    }
-    KeyboardInput { state: Released, modifiers, .. } => {
-      if !modifiers.ctrl {
-        controls.ctrl_pressed(false);
-      }
-    }
+fn process_input(input: Input,
+                 state: ElementState,
+                 mouse_pos: (f64, f64),
+                 controls: Option<&mut TilemapControls>,
+                 bindings: &InputBindings) -> WindowStatus {
+  let (action, controls) = match (bindings.action(input), controls) {
+    (Some(action), Some(controls)) => (action, controls),
+    (Some(Action::Quit), None) if state == Pressed => return WindowStatus::Quit,
+    _ => return WindowStatus::Open,
+  };
+  if controls.is_still_held(input, state == Pressed, action, bindings) {
+    return WindowStatus::Open;
+  }
+  if controls.is_editing() {
+    return process_editor_input(action, state, controls);
   }
-  if let Some(Escape) = input.virtual_keycode {
-    WindowStatus::Close
-  } else {
-    WindowStatus::Open
+  match (action, state) {
+    (Action::MoveUp, Pressed) => controls.move_character(CharacterControl::Up),
+    (Action::MoveDown, Pressed) => controls.move_character(CharacterControl::Down),
+    (Action::MoveUp, Released) | (Action::MoveDown, Released) => controls.move_character(CharacterControl::YMoveStop),
+    (Action::MoveLeft, Pressed) => controls.move_character(CharacterControl::Left),
+    (Action::MoveRight, Pressed) => controls.move_character(CharacterControl::Right),
+    (Action::MoveLeft, Released) | (Action::MoveRight, Released) => controls.move_character(CharacterControl::XMoveStop),
+    (Action::Aim, state) => controls.ctrl_pressed(state == Pressed),
+    (Action::Fire, Pressed) => controls.mouse_left_click(Some(mouse_pos)),
+    (Action::Fire, Released) => controls.mouse_left_click(None),
+    (Action::Reload, state) => controls.reload_weapon(state == Pressed),
+    (Action::ZoomIn, Pressed) => controls.zoom(&Control::Negative),
+    (Action::ZoomOut, Pressed) => controls.zoom(&Control::Plus),
+    (Action::ZoomIn, Released) | (Action::ZoomOut, Released) => controls.zoom(&Control::Released),
+    (Action::QuickSave, Pressed) => return WindowStatus::QuickSave,
+    (Action::QuickLoad, Pressed) => return WindowStatus::QuickLoad,
+    (Action::ReloadBindings, Pressed) => return WindowStatus::ReloadBindings,
+    (Action::Pause, Pressed) => return WindowStatus::Pause,
+    (Action::Confirm, Pressed) => return WindowStatus::Confirm,
+    (Action::Quit, Pressed) => return WindowStatus::Quit,
+    (Action::ToggleEditor, Pressed) => return WindowStatus::ToggleEditor,
+    _ => (),
   }
+  WindowStatus::Open
 }
 
+/// Movement keys pan the map editor camera and the fire and erase buttons edit the tile under the cursor.
+fn process_editor_input(action: Action, state: ElementState, controls: &mut TilemapControls) -> WindowStatus {
+  let control = match (action, state) {
+    (Action::MoveUp, Pressed) => EditorControl::Up,
+    (Action::MoveDown, Pressed) => EditorControl::Down,
+    (Action::MoveUp, Released) | (Action::MoveDown, Released) => EditorControl::YMoveStop,
+    (Action::MoveLeft, Pressed) => EditorControl::Left,
+    (Action::MoveRight, Pressed) => EditorControl::Right,
+    (Action::MoveLeft, Released) | (Action::MoveRight, Released) => EditorControl::XMoveStop,
+    (Action::ZoomIn, Pressed) => EditorControl::ZoomIn,
+    (Action::ZoomOut, Pressed) => EditorControl::ZoomOut,
+    (Action::ZoomIn, Released) | (Action::ZoomOut, Released) => EditorControl::ZoomStop,
+    (Action::Fire, state) => EditorControl::Apply(state == Pressed),
+    (Action::Erase, state) => EditorControl::Erase(state == Pressed),
+    (Action::NextTool, Pressed) => EditorControl::NextTool,
+    (Action::NextTile, Pressed) => EditorControl::NextTile,
+    (Action::PreviousTile, Pressed) => EditorControl::PreviousTile,
+    (Action::QuickSave, Pressed) => return WindowStatus::QuickSave,
+    (Action::ReloadBindings, Pressed) => return WindowStatus::ReloadBindings,
+    (Action::Quit, Pressed) => return WindowStatus::Quit,
+    (Action::ToggleEditor, Pressed) => return WindowStatus::ToggleEditor,
+    _ => return WindowStatus::Open,
+  };
+  controls.edit(control);
+  WindowStatus::Open
+}
@@ -1,16 +1,25 @@
 use std::time::Instant;
 
-use specs::prelude::{Read, WriteStorage};
+use crossbeam_channel as channel;
+use specs::prelude::{Read, ReadExpect, ReadStorage, WriteExpect, WriteStorage};
 
 use crate::{bullet, terrain_shape};
 use crate::character;
 use crate::critter::CharacterSprite;
-use crate::game::constants::{CURRENT_AMMO_TEXT, GAME_VERSION, HUD_TEXTS};
+use crate::editor::{brush_text, EDITOR_TOOLS, EditorBrush, EditorTool};
+use crate::game::constants::{CURRENT_MAGAZINE_TEXT, GAME_VERSION};
 use crate::gfx_app::{ColorFormat, DepthFormat};
+use crate::gfx_app::bindings::InputBindings;
+use crate::gfx_app::hot_reload::Reload;
 use crate::gfx_app::renderer::EncoderQueue;
-use crate::graphics::{DeltaTime, orientation::{Orientation, Stance}};
+use crate::game::components::{Animation, WorldPosition};
+use crate::game::config::GameConfig;
+use crate::game::error::GameError;
+use crate::game::state::GameState;
+use crate::graphics::{camera::CameraInputState, dimensions::Dimensions, FrameDelta, Interpolation, orientation::{Orientation, Stance}};
 use crate::graphics::Drawables;
 use crate::hud;
+use crate::shaders::Position;
 use crate::terrain;
 use crate::terrain_object;
 use crate::terrain_object::TerrainTexture;
@@ -26,7 +35,13 @@ pub struct DrawSystem<D: gfx::Device> {
   terrain_object_system: [terrain_object::TerrainObjectDrawSystem<D::Resources>; 3],
   terrain_shape_system: [terrain_shape::TerrainShapeDrawSystem<D::Resources>; 9],
   text_system: [hud::TextDrawSystem<D::Resources>; 3],
+  overlay_system: hud::TextDrawSystem<D::Resources>,
+  overlay: hud::TextDrawable,
+  bindings: InputBindings,
+  brush_system: hud::DynamicTextDrawSystem<D::Resources>,
+  brush: hud::TextDrawable,
   encoder_queue: EncoderQueue<D>,
+  reload: Option<channel::Receiver<Reload<D::Resources>>>,
   game_time: Instant,
   frames: u32,
   cool_down: f64,
@@ -38,15 +53,28 @@ impl<D: gfx::Device> DrawSystem<D> {
   pub fn new<F>(factory: &mut F,
                 rtv: &gfx::handle::RenderTargetView<D::Resources, ColorFormat>,
                 dsv: &gfx::handle::DepthStencilView<D::Resources, DepthFormat>,
-                encoder_queue: EncoderQueue<D>)
-                -> DrawSystem<D>
+                encoder_queue: EncoderQueue<D>,
+                config: &GameConfig,
+                bindings: InputBindings)
+                -> Result<DrawSystem<D>, GameError>
     where F: gfx::Factory<D::Resources> {
-    DrawSystem {
+    let hud_texts = hud::hud_texts(config);
+    let hud_texts = hud_texts.iter().map(String::as_str).collect::<Vec<_>>();
+    let current_ammo_text = format!("Ammo {}", config.magazine_size);
+    // The overlay texts are rendered up front, so they name the inputs bound when the game started.
+    let mut overlay_states = vec![GameState::Title, GameState::Paused, GameState::GameOver, GameState::Completed];
+    overlay_states.extend(EDITOR_TOOLS.iter().map(|tool| GameState::Playing.edit_with(*tool)));
+    let overlay_texts = overlay_states.iter()
+      .filter_map(|state| state.overlay_text(&bindings))
+      .collect::<Vec<_>>();
+    let overlay_texts = overlay_texts.iter().map(String::as_str).collect::<Vec<_>>();
+
+    Ok(DrawSystem {
       render_target_view: rtv.clone(),
       depth_stencil_view: dsv.clone(),
-      terrain_system: terrain::TerrainDrawSystem::new(factory, rtv.clone(), dsv.clone()),
-      character_system: character::CharacterDrawSystem::new(factory, rtv.clone(), dsv.clone()),
-      zombie_system: zombie::ZombieDrawSystem::new(factory, rtv.clone(), dsv.clone()),
+      terrain_system: terrain::TerrainDrawSystem::new(factory, rtv.clone(), dsv.clone())?,
+      character_system: character::CharacterDrawSystem::new(factory, rtv.clone(), dsv.clone())?,
+      zombie_system: zombie::ZombieDrawSystem::new(factory, rtv.clone(), dsv.clone(), &config.zombie_archetypes)?,
       bullet_system: bullet::BulletDrawSystem::new(factory, rtv.clone(), dsv.clone()),
       terrain_object_system: [
         terrain_object::TerrainObjectDrawSystem::new(factory, rtv.clone(), dsv.clone(), TerrainTexture::Ammo),
@@ -65,16 +93,57 @@ impl<D: gfx::Device> DrawSystem<D> {
         terrain_shape::TerrainShapeDrawSystem::new(factory, rtv.clone(), dsv.clone(), Orientation::Up),
       ],
       text_system: [
-        hud::TextDrawSystem::new(factory, &HUD_TEXTS, GAME_VERSION, rtv.clone(), dsv.clone()),
-        hud::TextDrawSystem::new(factory, &HUD_TEXTS, CURRENT_AMMO_TEXT, rtv.clone(), dsv.clone()),
-        hud::TextDrawSystem::new(factory, &HUD_TEXTS, CURRENT_AMMO_TEXT, rtv.clone(), dsv.clone())
+        hud::TextDrawSystem::new(factory, &hud_texts, GAME_VERSION, rtv.clone(), dsv.clone())?,
+        hud::TextDrawSystem::new(factory, &hud_texts, &current_ammo_text, rtv.clone(), dsv.clone())?,
+        hud::TextDrawSystem::new(factory, &hud_texts, CURRENT_MAGAZINE_TEXT, rtv.clone(), dsv.clone())?
       ],
+      overlay_system: hud::TextDrawSystem::new(factory, &overlay_texts, overlay_texts[0], rtv.clone(), dsv.clone())?,
+      overlay: hud::TextDrawable::new(overlay_texts[0], Position::new(0.8, -0.95)),
+      bindings,
+      brush_system: hud::DynamicTextDrawSystem::new(factory, rtv.clone(), dsv.clone())?,
+      brush: hud::TextDrawable::new("", Position::new(0.8, -0.91)),
       encoder_queue,
+      reload: None,
       game_time: Instant::now(),
       frames: 0,
       cool_down: 1.0,
       run_cool_down: 1.0,
       fire_cool_down: 1.0,
+    })
+  }
+
+  /// Swaps in assets reloaded by `HotReload` before each frame.
+  pub fn reload_from(&mut self, reload: channel::Receiver<Reload<D::Resources>>) {
+    self.reload = Some(reload);
+  }
+
+  fn apply_reload(&mut self, reload: Reload<D::Resources>) {
+    match reload {
+      Reload::CharacterSprites(data) => self.character_system.set_sprite_data(data),
+      Reload::ZombieSprites(data) => self.zombie_system.set_sprite_data(data),
+      Reload::ZombieArchetypes(archetypes, sprite_sheets) => self.zombie_system.set_archetypes(&archetypes, sprite_sheets),
+      Reload::TileSheets => self.terrain_system.reload_tile_sheets(),
+      Reload::BulletPipeline(pso) => self.bullet_system.set_pipeline(pso),
+      Reload::CritterPipeline(pso) => {
+        self.character_system.set_pipeline(pso.clone());
+        self.zombie_system.set_pipeline(pso);
+      }
+      Reload::StaticElementPipeline(pso) => {
+        for system in self.terrain_object_system.iter_mut() {
+          system.set_pipeline(pso.clone());
+        }
+        for system in self.terrain_shape_system.iter_mut() {
+          system.set_pipeline(pso.clone());
+        }
+      }
+      Reload::TilemapPipeline(pso) => self.terrain_system.set_pipeline(pso),
+      Reload::TextPipeline(pso) => {
+        for system in self.text_system.iter_mut() {
+          system.set_pipeline(pso.clone());
+        }
+        self.overlay_system.set_pipeline(pso.clone());
+        self.brush_system.set_pipeline(pso);
+      }
     }
   }
 
@@ -98,22 +167,37 @@ impl<'a, D> specs::prelude::System<'a> for DrawSystem<D>
   where D: gfx::Device,
         D::CommandBuffer: Send {
   type SystemData = (WriteStorage<'a, terrain::TerrainDrawable>,
-                     WriteStorage<'a, terrain_shape::terrain_shape_objects::TerrainShapeObjects>,
                      WriteStorage<'a, character::CharacterDrawable>,
                      WriteStorage<'a, CharacterSprite>,
                      WriteStorage<'a, hud::hud_objects::HudObjects>,
-                     WriteStorage<'a, zombie::zombies::Zombies>,
-                     WriteStorage<'a, bullet::bullets::Bullets>,
-                     WriteStorage<'a, terrain_object::terrain_objects::TerrainObjects>,
-                     Read<'a, DeltaTime>);
+                     ReadStorage<'a, CameraInputState>,
+                     ReadStorage<'a, WorldPosition>,
+                     WriteStorage<'a, Animation>,
+                     ReadStorage<'a, zombie::ZombieAi>,
+                     ReadStorage<'a, bullet::Bullet>,
+                     ReadStorage<'a, terrain_object::TerrainObject>,
+                     ReadStorage<'a, terrain_shape::TerrainShape>,
+                     Read<'a, Dimensions>,
+                     Read<'a, FrameDelta>,
+                     Read<'a, Interpolation>,
+                     ReadExpect<'a, GameState>,
+                     Read<'a, EditorBrush>,
+                     WriteExpect<'a, terrain::tile_map::Terrain>);
 
-  fn run(&mut self, (mut terrain, mut terrain_shape, mut character, mut character_sprite, mut hud_objects, mut zombies, mut bullets, mut terrain_objects, dt): Self::SystemData) {
+  fn run(&mut self, (mut terrain, mut character, mut character_sprite, mut hud_objects, camera, positions, mut animations,
+                     zombies, bullets, terrain_objects, terrain_shapes, dim, dt, interpolation, state, brush, mut tile_map): Self::SystemData) {
     use specs::join::Join;
     let mut encoder = self.encoder_queue.receiver
       .recv()
       .expect("Encoder error");
 
+    let reloaded = self.reload.as_ref().map(|reload| reload.try_iter().collect::<Vec<_>>()).unwrap_or_default();
+    for reload in reloaded {
+      self.apply_reload(reload);
+    }
+
     self.update_cooldowns(dt.0);
+    let alpha = interpolation.0;
 
     let current_time = Instant::now();
     self.frames += 1;
@@ -129,9 +213,10 @@ impl<'a, D> specs::prelude::System<'a> for DrawSystem<D>
     encoder.clear(&self.render_target_view, [16.0 / 256.0, 16.0 / 256.0, 20.0 / 256.0, 1.0]);
     encoder.clear_depth(&self.depth_stencil_view, 1.0);
 
-    for (t, t_shape, c, cs, hds, zs, bs, obj) in (&mut terrain, &mut terrain_shape, &mut character, &mut character_sprite, &mut hud_objects,
-                                         &mut zombies, &mut bullets, &mut terrain_objects).join() {
-      self.terrain_system.draw(t, time_passed,  &mut encoder);
+    for (t, c, cs, hds, ca) in (&mut terrain, &mut character, &mut character_sprite, &mut hud_objects, &camera).join() {
+      let projection = dim.world_to_projection(ca);
+      let view = ca.view_translation(alpha);
+      self.terrain_system.draw(t, &mut tile_map, time_passed, &view, &mut encoder);
 
       for hud in &mut hds.objects {
         self.text_system[0].draw(hud, &mut encoder);
@@ -142,12 +227,12 @@ impl<'a, D> specs::prelude::System<'a> for DrawSystem<D>
         if c.stance == Stance::Walking {
           cs.update_run();
         }
-        for z in &mut zs.zombies {
-          match z.stance {
-            Stance::NormalDeath => z.update_death_idx(5),
-            Stance::CriticalDeath => z.update_death_idx(7),
-            Stance::Walking => z.update_alive_idx(7),
-            Stance::Still => z.update_alive_idx(3),
+        for (_, a) in (&zombies, &mut animations).join() {
+          match a.stance {
+            Stance::NormalDeath => a.update_death_idx(5),
+            Stance::CriticalDeath => a.update_death_idx(7),
+            Stance::Walking => a.update_alive_idx(7),
+            Stance::Still => a.update_alive_idx(3),
             _ => ()
           };
         }
@@ -156,22 +241,22 @@ impl<'a, D> specs::prelude::System<'a> for DrawSystem<D>
       }
 
       if self.run_cool_down == 0.0 {
-        for z in &mut zs.zombies {
-          if let Stance::Running = z.stance {
-            z.update_alive_idx(7)
+        for (_, a) in (&zombies, &mut animations).join() {
+          if let Stance::Running = a.stance {
+            a.update_alive_idx(7)
           }
         }
       }
 
       let mut drawables: Vec<Drawables> = vec![];
-      drawables.append(&mut bs.bullets.iter().map(|b| Drawables::Bullet(b)).collect());
-      drawables.append(&mut zs.zombies.iter_mut().map(|z| Drawables::Zombie(z)).collect());
+      drawables.append(&mut (&bullets, &positions).join().map(|(b, p)| Drawables::Bullet(p.interpolated(alpha), b)).collect());
+      drawables.append(&mut (&zombies, &positions, &mut animations).join().map(|(z, p, a)| Drawables::Zombie(p.interpolated(alpha), a, z.archetype)).collect());
 
-      for o in &obj.objects {
+      for (o, p) in (&terrain_objects, &positions).join() {
         match o.object_type {
-          TerrainTexture::Ammo => drawables.push(Drawables::TerrainAmmo(o)),
-          TerrainTexture::House => drawables.push(Drawables::TerrainHouse(o)),
-          TerrainTexture::Tree => drawables.push(Drawables::TerrainTree(o)),
+          TerrainTexture::Ammo => drawables.push(Drawables::TerrainAmmo(p.current)),
+          TerrainTexture::House => drawables.push(Drawables::TerrainHouse(p.current)),
+          TerrainTexture::Tree => drawables.push(Drawables::TerrainTree(p.current)),
         };
       }
 
@@ -183,32 +268,44 @@ impl<'a, D> specs::prelude::System<'a> for DrawSystem<D>
           .expect("Z-axis sorting failed")
       });
 
-      for ts in &t_shape.objects {
-        match ts.get_shape() {
-          Orientation::Right => self.terrain_shape_system[0].draw(ts, time_passed, &mut encoder),
-          Orientation::DownRight => self.terrain_shape_system[1].draw(ts, time_passed, &mut encoder),
-          Orientation::Down => self.terrain_shape_system[2].draw(ts, time_passed, &mut encoder),
-          Orientation::DownLeft => self.terrain_shape_system[3].draw(ts, time_passed, &mut encoder),
-          Orientation::Left => self.terrain_shape_system[4].draw(ts, time_passed, &mut encoder),
-          Orientation::UpLeft => self.terrain_shape_system[5].draw(ts, time_passed, &mut encoder),
-          Orientation::UpRight => self.terrain_shape_system[6].draw(ts, time_passed, &mut encoder),
-          Orientation::Normal => self.terrain_shape_system[7].draw(ts, time_passed, &mut encoder),
-          Orientation::Up => self.terrain_shape_system[8].draw(ts, time_passed, &mut encoder),
-        }
+      for (ts, p) in (&terrain_shapes, &positions).join() {
+        let shape_idx = match ts.get_shape() {
+          Orientation::Right => 0,
+          Orientation::DownRight => 1,
+          Orientation::Down => 2,
+          Orientation::DownLeft => 3,
+          Orientation::Left => 4,
+          Orientation::UpLeft => 5,
+          Orientation::UpRight => 6,
+          Orientation::Normal => 7,
+          Orientation::Up => 8,
+        };
+        self.terrain_shape_system[shape_idx].draw(&p.current, &projection, time_passed, &view, &mut encoder);
       }
 
       for e in &mut drawables {
         match *e {
-          Drawables::Bullet(ref e) => { self.bullet_system.draw(e, &mut encoder) }
-          Drawables::Zombie(ref mut e) => { self.zombie_system.draw(e, &mut encoder) }
-          Drawables::TerrainAmmo(ref mut e) => { self.terrain_object_system[0].draw(e, time_passed, &mut encoder) }
-          Drawables::TerrainHouse(ref mut e) => { self.terrain_object_system[1].draw(e, time_passed, &mut encoder) }
-          Drawables::TerrainTree(ref mut e) => { self.terrain_object_system[2].draw(e, time_passed, &mut encoder) }
-          Drawables::Character(ref mut e) => { self.character_system.draw(e, cs, &mut encoder) }
+          Drawables::Bullet(ref p, b) => { self.bullet_system.draw(p, b, &projection, &view, &mut encoder) }
+          Drawables::Zombie(ref p, ref mut a, archetype) => { self.zombie_system.draw(p, a, archetype, &projection, &view, &mut encoder) }
+          Drawables::TerrainAmmo(ref p) => { self.terrain_object_system[0].draw(p, &projection, time_passed, &view, &mut encoder) }
+          Drawables::TerrainHouse(ref p) => { self.terrain_object_system[1].draw(p, &projection, time_passed, &view, &mut encoder) }
+          Drawables::TerrainTree(ref p) => { self.terrain_object_system[2].draw(p, &projection, time_passed, &view, &mut encoder) }
+          Drawables::Character(ref mut e) => { self.character_system.draw(e, cs, &view, alpha, &mut encoder) }
         }
       }
     }
 
+    if let Some(text) = state.overlay_text(&self.bindings) {
+      self.overlay.update(text);
+      self.overlay_system.draw(&self.overlay, &mut encoder);
+    }
+    if let GameState::Editing { tool: EditorTool::Paint, .. } = *state {
+      self.brush.update(brush_text(*brush));
+      if let Err(e) = self.brush_system.draw(&self.brush, &mut encoder) {
+        eprintln!("Could not draw the editor brush: {}", e);
+      }
+    }
+
     self.encoder_queue.sender.send(encoder).expect("Encoder queue update error");
   }
 }
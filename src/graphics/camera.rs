@@ -1,27 +1,46 @@
 use crossbeam_channel as channel;
-use specs::prelude::WriteStorage;
+use specs::prelude::{Read, ReadStorage, WriteStorage};
 
-use crate::game::constants::VIEW_DISTANCE;
-use crate::shaders::Position;
+use crate::character::controls::CharacterInputState;
+use crate::game::{config::GameConfig, constants::VIEW_DISTANCE};
+use crate::graphics::interpolate;
+use crate::shaders::{Position, ViewTranslation};
 
 #[derive(Clone)]
 pub struct CameraInputState {
   pub distance: f32,
-  pub movement: Position,
+  pub position: Position,
+  last_tick_position: Position,
 }
 
 impl CameraInputState {
-  pub fn new() -> CameraInputState {
+  pub fn new(distance: f32) -> CameraInputState {
     CameraInputState {
-      distance: VIEW_DISTANCE,
-      movement: Position::origin(),
+      distance,
+      position: Position::origin(),
+      last_tick_position: Position::origin(),
     }
   }
+
+  pub fn follow(&mut self, target: Position) {
+    self.last_tick_position = self.position;
+    self.position = target;
+  }
+
+  pub fn jump_to(&mut self, target: Position) {
+    self.position = target;
+    self.last_tick_position = target;
+  }
+
+  pub fn view_translation(&self, alpha: f32) -> ViewTranslation {
+    let camera_position = interpolate(self.last_tick_position, self.position, alpha);
+    ViewTranslation::new(Position::origin() - camera_position)
+  }
 }
 
 impl Default for CameraInputState {
   fn default() -> CameraInputState {
-    CameraInputState::new()
+    CameraInputState::new(VIEW_DISTANCE)
   }
 }
 
@@ -29,6 +48,7 @@ impl specs::prelude::Component for CameraInputState {
   type Storage = specs::storage::HashMapStorage<CameraInputState>;
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum CameraControl {
   ZoomOut,
   ZoomIn,
@@ -57,8 +77,11 @@ impl CameraControlSystem {
 }
 
 impl<'a> specs::prelude::System<'a> for CameraControlSystem {
-  type SystemData = WriteStorage<'a, CameraInputState>;
-  fn run(&mut self, mut map_input: Self::SystemData) {
+  type SystemData = (WriteStorage<'a, CameraInputState>,
+                     ReadStorage<'a, CharacterInputState>,
+                     Read<'a, GameConfig>);
+
+  fn run(&mut self, (mut map_input, character_input, config): Self::SystemData) {
     use specs::join::Join;
 
     while let Ok(control) = self.queue.try_recv() {
@@ -69,9 +92,10 @@ impl<'a> specs::prelude::System<'a> for CameraControlSystem {
         _ => (),
       }
     }
-    if let Some(zoom) = self.zoom_level {
-      for m in (&mut map_input).join() {
-        if m.distance > 200.0 && zoom < 0.0 || m.distance < 600.0 && zoom > 0.0 {
+    for (m, ci) in (&mut map_input, &character_input).join() {
+      m.follow(ci.position);
+      if let Some(zoom) = self.zoom_level {
+        if m.distance > config.zoom_min && zoom < 0.0 || m.distance < config.zoom_max && zoom > 0.0 {
           m.distance += zoom;
         }
       }
@@ -109,10 +109,10 @@ fn tile_to_coords_test() {
   use crate::graphics::coords_to_tile;
   use crate::shaders::Position;
 
-  let up = Position::new(0.0, -5385.0);
-  let down = Position::new(0.0, 5385.0);
-  let right = Position::new(-5995.0, 0.0);
-  let left = Position::new(5995.0, 0.0);
+  let up = Position::new(0.0, 5385.0);
+  let down = Position::new(0.0, -5385.0);
+  let right = Position::new(5995.0, 0.0);
+  let left = Position::new(-5995.0, 0.0);
 
   assert_eq!(coords_to_tile(up), Point2::new(1, 1), "Up corner");
 
@@ -122,3 +122,30 @@ fn tile_to_coords_test() {
 
   assert_eq!(coords_to_tile(left), Point2::new(1, 126), "Left corner");
 }
+
+#[test]
+fn interpolate_test() {
+  use crate::graphics::interpolate;
+  use crate::shaders::Position;
+
+  let previous = Position::new(10.0, -20.0);
+  let current = Position::new(20.0, 0.0);
+
+  assert_eq!(interpolate(previous, current, 0.0), previous, "Alpha 0 should be previous tick position");
+  assert_eq!(interpolate(previous, current, 1.0), current, "Alpha 1 should be current tick position");
+  assert_eq!(interpolate(previous, current, 0.5), Position::new(15.0, -10.0), "Alpha 0.5 should be halfway");
+}
+
+#[test]
+fn screen_to_world_test() {
+  use crate::graphics::{camera::CameraInputState, dimensions::Dimensions, screen_to_world};
+  use crate::shaders::Position;
+
+  let dim = Dimensions::new(1600.0, 900.0, 1.0, true);
+  let mut camera = CameraInputState::new(300.0);
+  camera.jump_to(Position::new(100.0, -50.0));
+
+  assert_eq!(screen_to_world((800.0, 450.0), &camera, &dim), camera.position, "Window center is the camera position");
+  assert_eq!(screen_to_world((1600.0, 0.0), &camera, &dim), Position::new(700.0, 550.0), "Top right corner");
+  assert_eq!(screen_to_world((0.0, 900.0), &camera, &dim), Position::new(-500.0, -650.0), "Bottom left corner");
+}
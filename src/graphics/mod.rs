@@ -1,17 +1,15 @@
 use std::f32;
 
 use cgmath::{Angle, Deg, Point2};
-use num::{Num, NumCast};
 
-use crate::bullet::BulletDrawable;
+use crate::bullet::Bullet;
 use crate::character::CharacterDrawable;
-use crate::game::{constants::{RESOLUTION_Y, TERRAIN_OBJECTS, TILE_SIZE, TILES_PCS_H, TILES_PCS_W, Y_OFFSET}, get_rand_from_range};
+use crate::game::{components::Animation, constants::{TILE_SIZE, Y_OFFSET}, GameRng};
 use crate::game::constants::TILE_WIDTH;
-use crate::gfx_app::{mouse_controls::MouseInputState};
-use crate::graphics::{dimensions::Dimensions, orientation::Orientation};
+use crate::game::mouse_controls::MouseInputState;
+use crate::graphics::{camera::CameraInputState, dimensions::Dimensions, orientation::Orientation};
 use crate::shaders::Position;
-use crate::terrain_object::TerrainObjectDrawable;
-use crate::zombie::ZombieDrawable;
+use crate::terrain::collision_grid::CollisionGrid;
 
 pub mod camera;
 pub mod dimensions;
@@ -21,6 +19,8 @@ pub mod orientation;
 pub mod texture;
 
 const Y_MODIFIER: f32 = 0.9;
+/// Random tiles tried around a position before falling back to the closest open one.
+const RANDOM_TILE_ATTEMPTS: usize = 25;
 
 #[derive(Default)]
 pub struct DeltaTime(pub f64);
@@ -28,8 +28,14 @@ pub struct DeltaTime(pub f64);
 #[derive(Default)]
 pub struct GameTime(pub u64);
 
-pub fn flip_y_axel(point: Point2<f32>) -> Point2<f32> {
-  Point2::new(point.x, RESOLUTION_Y as f32 - point.y)
+#[derive(Default)]
+pub struct FrameDelta(pub f64);
+
+#[derive(Default)]
+pub struct Interpolation(pub f32);
+
+pub fn flip_y_axel(point: Point2<f32>, window_height: f32) -> Point2<f32> {
+  Point2::new(point.x, window_height - point.y)
 }
 
 pub fn direction(start_point: Point2<f32>, end_point: Point2<f32>) -> f32 {
@@ -65,13 +71,17 @@ pub fn orientation_to_direction(angle_in_degrees: f32) -> Orientation {
 pub fn get_orientation_from_center(mouse_input: &MouseInputState, dim: &Dimensions) -> Orientation {
   if let Some(end_point_gl) = mouse_input.left_click_point {
     let start_point = Point2::new(dim.window_width / 2.0 * dim.hidpi_factor, dim.window_height / 2.0 * dim.hidpi_factor);
-    let dir = direction(start_point, flip_y_axel(end_point_gl));
+    let dir = direction(start_point, flip_y_axel(end_point_gl, dim.window_height));
     orientation_to_direction(dir)
   } else {
     Orientation::Right
   }
 }
 
+pub fn interpolate(previous: Position, current: Position, alpha: f32) -> Position {
+  previous + Position::new((current.x() - previous.x()) * alpha, (current.y() - previous.y()) * alpha)
+}
+
 pub fn overlaps(area: Position, el: Position, width: f32, height: f32) -> bool {
   area.x() - width < el.x() &&
     area.x() + width > el.x() &&
@@ -79,38 +89,18 @@ pub fn overlaps(area: Position, el: Position, width: f32, height: f32) -> bool {
     area.y() + height > el.y()
 }
 
-pub fn is_within_map_borders(point: Point2<usize>) -> bool {
-  point.x < (TILES_PCS_W - 1) && point.y < (TILES_PCS_H - 1)
-}
-
-pub fn can_move(screen_pos: Position) -> bool {
-  let point = coords_to_tile(screen_pos);
-  is_within_map_borders(Point2::new(point.x as usize, point.y as usize))
+pub fn is_within_map(world_pos: Position, grid: &CollisionGrid) -> bool {
+  grid.contains(coords_to_tile(world_pos))
 }
 
-fn is_not_terrain_object<T>(pos: Point2<T>) -> bool
-  where T: NumCast + Num, i32: std::cmp::PartialEq<T> {
-  !TERRAIN_OBJECTS.iter().any(|e| (e[0] == pos.x) && (e[1] == pos.y))
+pub fn can_move_to_tile(world_pos: Position, grid: &CollisionGrid) -> bool {
+  !grid.is_blocked(coords_to_tile(world_pos))
 }
 
-fn is_map_tile(pos: Point2<i32>) -> bool {
-  pos.x > 0 && pos.y > 0 && pos.x < (TILES_PCS_W - 2) as i32 && pos.y < (TILES_PCS_H - 2) as i32
-}
-
-pub fn can_move_to_tile(screen_pos: Position) -> bool {
-  let tile_pos = coords_to_tile(screen_pos);
-  is_not_terrain_object(tile_pos) && is_map_tile(tile_pos)
-}
-
-pub fn check_terrain_elevation(critter_pos: Position, objects: &[[i32; 2]]) -> f32 {
+pub fn check_terrain_elevation(critter_pos: Position, hills: &[Position]) -> f32 {
   let offset = TILE_SIZE / 2.0;
-  let nearest_hill = objects.iter()
-    .map(|x| {
-      position_distance(
-        critter_pos,
-        Position::new(TILE_SIZE * -x[0] as f32, TILE_SIZE * -x[1] as f32).tile_center(0.0, offset)
-      )
-    })
+  let nearest_hill = hills.iter()
+    .map(|hill| position_distance(critter_pos, hill.tile_center(0.0, offset)))
     .fold(100_000_000f32, |mut min, val| {
       if val < min {
         min = val;
@@ -135,31 +125,51 @@ pub fn set_position(x: i32, y: i32) -> Position {
 }
 
 pub fn coords_to_tile(position: Position) -> Point2<i32> {
-  let pos = Point2::new(-position.x(), position.y() / Y_MODIFIER + Y_OFFSET);
+  let pos = Point2::new(position.x(), -position.y() / Y_MODIFIER + Y_OFFSET);
   Point2::new(((pos.x + pos.y) / TILE_WIDTH) as i32, ((pos.y - pos.x) / TILE_WIDTH) as i32)
 }
 
+/// Inverse of `coords_to_tile` for fractional tile coordinates.
+pub fn tile_to_coords(tile: Point2<f32>) -> Position {
+  let (x, y) = ((tile.x - tile.y) * TILE_WIDTH / 2.0, (tile.x + tile.y) * TILE_WIDTH / 2.0);
+  Position::new(x, (Y_OFFSET - y) * Y_MODIFIER)
+}
+
+/// World position under a point of the window, in the same units the mouse is reported in.
+/// Sprites are offset from the camera in clip space, so the screen edges are twice the camera distance away
+/// because `world_to_projection` applies the view matrix as both model and view.
+pub fn screen_to_world(point: (f64, f64), camera: &CameraInputState, dim: &Dimensions) -> Position {
+  let ndc_x = point.0 as f32 / dim.window_width * 2.0 - 1.0;
+  let ndc_y = 1.0 - point.1 as f32 / dim.window_height * 2.0;
+  camera.position + Position::new(ndc_x * camera.distance * 2.0, ndc_y * camera.distance * 2.0)
+}
+
 fn round(number: f32, precision: usize) -> f32 {
   let ten: f32 = 10.0;
   let divider = ten.powf(precision as f32);
   (number * divider).round() / divider
 }
 
-pub fn get_nearest_random_tile_position(pos: Position) -> Position {
-  fn iter(pos: Position) -> Position {
-    let offset = Position::new(get_rand_from_range(-2, 2) as f32, get_rand_from_range(-2, 2) as f32);
+pub fn get_nearest_random_tile_position(pos: Position, grid: &CollisionGrid, rng: &mut GameRng) -> Position {
+  fn iter(pos: Position, rng: &mut GameRng) -> Position {
+    let offset = Position::new(rng.get_rand_from_range(-2, 2) as f32, rng.get_rand_from_range(-2, 2) as f32);
     let offset_point = Position::new(
       round(offset.x() * TILE_WIDTH - offset.y() / TILE_WIDTH * Y_MODIFIER, 3),
       round(offset.y() * TILE_WIDTH - offset.y() / TILE_WIDTH * Y_MODIFIER, 3),
     );
     pos + offset_point
   }
-  loop {
-    let res = iter(pos);
-    if can_move_to_tile(res) {
+  for _ in 0..RANDOM_TILE_ATTEMPTS {
+    let res = iter(pos, rng);
+    if can_move_to_tile(res, grid) {
       return res;
     }
   }
+  // Everything around is blocked, e.g. walled in on a generated map, so head for the closest open tile.
+  match grid.nearest_free(coords_to_tile(pos)) {
+    Some(tile) => tile_to_coords(Point2::new(tile.x as f32 + 0.5, tile.y as f32 + 0.5)),
+    None => pos,
+  }
 }
 
 pub fn distance(a: f32, b: f32) -> f32 {
@@ -172,22 +182,22 @@ fn position_distance(a: Position, b: Position) -> f32 {
 }
 
 pub enum Drawables<'b> {
-  Bullet(&'b BulletDrawable),
+  Bullet(Position, &'b Bullet),
   Character(&'b mut CharacterDrawable),
-  TerrainAmmo(&'b TerrainObjectDrawable),
-  TerrainHouse(&'b TerrainObjectDrawable),
-  TerrainTree(&'b TerrainObjectDrawable),
-  Zombie(&'b mut ZombieDrawable),
+  TerrainAmmo(Position),
+  TerrainHouse(Position),
+  TerrainTree(Position),
+  Zombie(Position, &'b mut Animation, usize),
 }
 
 impl<'b> Drawables<'b> {
   pub fn get_vertical_pos(drawable: &Drawables) -> f32 {
     match drawable {
-      Drawables::Bullet(e) => e.position.y(),
-      Drawables::Zombie(e) => e.position.y(),
-      Drawables::TerrainAmmo(e) => e.position.y(),
-      Drawables::TerrainHouse(e) => e.position.y(),
-      Drawables::TerrainTree(e) => e.position.y(),
+      Drawables::Bullet(p, _) => p.y(),
+      Drawables::Zombie(p, _, _) => p.y(),
+      Drawables::TerrainAmmo(p) => p.y(),
+      Drawables::TerrainHouse(p) => p.y(),
+      Drawables::TerrainTree(p) => p.y(),
       Drawables::Character(e) => e.position.y(),
     }
   }
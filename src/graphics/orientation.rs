@@ -1,4 +1,5 @@
 use std::fmt::{Display, Formatter, Result};
+use std::str::FromStr;
 
 #[derive(Clone, Copy, PartialEq)]
 pub enum Orientation {
@@ -29,6 +30,25 @@ impl Display for Orientation {
   }
 }
 
+impl FromStr for Orientation {
+  type Err = ();
+
+  fn from_str(s: &str) -> std::result::Result<Orientation, ()> {
+    match s {
+      "Right" => Ok(Orientation::Right),
+      "UpRight" => Ok(Orientation::UpRight),
+      "Up" => Ok(Orientation::Up),
+      "UpLeft" => Ok(Orientation::UpLeft),
+      "Left" => Ok(Orientation::Left),
+      "DownLeft" => Ok(Orientation::DownLeft),
+      "Down" => Ok(Orientation::Down),
+      "DownRight" => Ok(Orientation::DownRight),
+      "Normal" => Ok(Orientation::Normal),
+      _ => Err(()),
+    }
+  }
+}
+
 #[derive(Clone, PartialEq)]
 pub enum Stance {
   Walking,
@@ -52,3 +72,19 @@ impl Display for Stance {
   }
 }
 
+
+impl FromStr for Stance {
+  type Err = ();
+
+  fn from_str(s: &str) -> std::result::Result<Stance, ()> {
+    match s {
+      "Walking" => Ok(Stance::Walking),
+      "Running" => Ok(Stance::Running),
+      "Firing" => Ok(Stance::Firing),
+      "Still" => Ok(Stance::Still),
+      "NormalDeath" => Ok(Stance::NormalDeath),
+      "CriticalDeath" => Ok(Stance::CriticalDeath),
+      _ => Err(()),
+    }
+  }
+}
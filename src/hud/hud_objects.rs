@@ -1,6 +1,7 @@
 
-use crate::game::constants::{CURRENT_AMMO_TEXT, CURRENT_MAGAZINE_TEXT, GAME_VERSION};
-use crate::hud::TextDrawable;
+use crate::game::config::GameConfig;
+use crate::game::constants::{CURRENT_MAGAZINE_TEXT, GAME_VERSION};
+use crate::hud::{health_text, TextDrawable, wave_text};
 use crate::shaders::Position;
 
 pub struct HudObjects {
@@ -8,12 +9,14 @@ pub struct HudObjects {
 }
 
 impl HudObjects {
-  pub fn new() -> HudObjects {
+  pub fn new(config: &GameConfig) -> HudObjects {
     HudObjects {
       objects: vec![
         TextDrawable::new(GAME_VERSION, Position::origin()),
-        TextDrawable::new(CURRENT_AMMO_TEXT, Position::new(1.9, -1.9)),
+        TextDrawable::new(&format!("Ammo {}", config.magazine_size), Position::new(1.9, -1.9)),
         TextDrawable::new(CURRENT_MAGAZINE_TEXT, Position::new(1.9, -1.94)),
+        TextDrawable::new(&health_text(config.player_health, config), Position::new(1.9, -1.86)),
+        TextDrawable::new(&wave_text(1), Position::new(1.9, -1.82)),
       ]
     }
   }
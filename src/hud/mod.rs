@@ -1,15 +1,20 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use cgmath::Point2;
-use rusttype::FontCollection;
-use specs::{ReadStorage, WriteStorage};
+use gfx::format::{ChannelType, R8_G8_B8_A8, Swizzle};
+use rusttype::{Font, FontCollection};
+use specs::{Read, ReadStorage, WriteStorage};
 
 use crate::character::CharacterDrawable;
+use crate::game::config::GameConfig;
+use crate::game::constants::{GAME_VERSION, HUD_MAX_WAVE};
+use crate::game::error::GameError;
 use crate::gfx_app::ColorFormat;
 use crate::gfx_app::DepthFormat;
 use crate::graphics::{mesh::RectangularTexturedMesh};
 use crate::graphics::texture::{text_texture, Texture};
 use crate::shaders::{Position, text_pipeline};
+use crate::zombie::waves::ZombieWaves;
 use crate::graphics::mesh::Geometry;
 
 pub mod font;
@@ -17,6 +22,35 @@ pub mod hud_objects;
 
 const SHADER_VERT: &[u8] = include_bytes!("../shaders/text.v.glsl");
 const SHADER_FRAG: &[u8] = include_bytes!("../shaders/text.f.glsl");
+const FONT_PATH: &str = "assets/DejaVuSans.ttf";
+/// Pixel size of the texture a `DynamicTextDrawSystem` draws into, text past the right edge is cut off.
+const DYNAMIC_TEXT_SIZE: (usize, usize) = (512, 100);
+
+fn load_font() -> Result<Font<'static>, GameError> {
+  let font_bytes = &include_bytes!("../../assets/DejaVuSans.ttf")[..];
+  FontCollection::from_bytes(font_bytes as &[u8])
+    .and_then(|collection| collection.into_font())
+    .map_err(|e| GameError::parse(FONT_PATH, e.to_string()))
+}
+
+/// Every text the HUD can show, rendered up front into the texture cache.
+pub fn hud_texts(config: &GameConfig) -> Vec<String> {
+  let mut texts = vec![GAME_VERSION.to_string()];
+  texts.extend((0..=config.magazine_size).map(|ammo| format!("Ammo {}", ammo)));
+  texts.extend((0..=2).map(|magazines| format!("Magazines {}/2", magazines)));
+  texts.extend((0..=config.player_health).map(|health| health_text(health, config)));
+  texts.extend((1..=HUD_MAX_WAVE).map(wave_text));
+  texts
+}
+
+pub fn health_text(health: usize, config: &GameConfig) -> String {
+  format!("Health {}/{}", health, config.player_health)
+}
+
+/// Waves past `HUD_MAX_WAVE` keep showing the last pre-rendered number.
+pub fn wave_text(wave: usize) -> String {
+  format!("Wave {}", wave.min(HUD_MAX_WAVE))
+}
 
 pub struct TextDrawable {
   text: String,
@@ -43,6 +77,8 @@ impl specs::prelude::Component for TextDrawable {
 pub struct TextDrawSystem<R: gfx::Resources> {
   bundle: gfx::pso::bundle::Bundle<R, text_pipeline::Data<R>>,
   texture_cache: HashMap<String, Texture<R>>,
+  /// Texts without a rendered texture, each reported once.
+  missing_texts: HashSet<String>,
   pub current_text: String,
 }
 
@@ -51,23 +87,22 @@ impl<R: gfx::Resources> TextDrawSystem<R> {
                 texts: &[&str],
                 current_text: &str,
                 rtv: gfx::handle::RenderTargetView<R, ColorFormat>,
-                dsv: gfx::handle::DepthStencilView<R, DepthFormat>) -> TextDrawSystem<R>
+                dsv: gfx::handle::DepthStencilView<R, DepthFormat>) -> Result<TextDrawSystem<R>, GameError>
     where F: gfx::Factory<R> {
     use gfx::traits::FactoryExt;
 
-    let font_bytes = &include_bytes!("../../assets/DejaVuSans.ttf")[..];
-    let font = FontCollection::from_bytes(font_bytes as &[u8])
-      .unwrap_or_else(|e| panic!("Font loading error: {}", e))
-      .into_font().unwrap_or_else(|e| panic!("into_font error: {}", e));
+    let font = load_font()?;
 
     let mut texture_cache: HashMap<String, Texture<R>> = HashMap::new();
 
     text_texture(factory, &font, texts, &mut texture_cache);
 
     let pso = factory.create_pipeline_simple(SHADER_VERT, SHADER_FRAG, text_pipeline::new())
-      .expect("HUD shader loading error");
+      .map_err(|e| GameError::Device { context: format!("HUD shader loading error: {}", e) })?;
 
-    let texture = texture_cache[current_text].clone();
+    let texture = texture_cache.get(current_text)
+      .ok_or_else(|| GameError::missing_data(FONT_PATH, format!("a rendered texture for HUD text \"{}\"", current_text)))?
+      .clone();
 
     let rect_mesh = RectangularTexturedMesh::new(factory, texture, Geometry::Rectangle, Point2::new(1.0, 1.0), None, None, None);
 
@@ -79,23 +114,124 @@ impl<R: gfx::Resources> TextDrawSystem<R> {
       out_depth: dsv,
     };
 
-    TextDrawSystem {
+    Ok(TextDrawSystem {
       bundle: gfx::Bundle::new(rect_mesh.mesh.slice, pso, pipeline_data),
       texture_cache,
+      missing_texts: HashSet::new(),
       current_text: current_text.to_string(),
-    }
+    })
+  }
+
+  pub fn set_pipeline(&mut self, pso: gfx::PipelineState<R, text_pipeline::Meta>) {
+    self.bundle.pso = pso;
   }
 
   pub fn draw<C>(&mut self,
                  drawable: &TextDrawable,
                  encoder: &mut gfx::Encoder<R, C>)
     where C: gfx::CommandBuffer<R> {
-    encoder.update_constant_buffer(&self.bundle.data.position_cb, &drawable.position);
     if self.current_text.trim() != drawable.text.trim() {
-      self.current_text = drawable.text.to_owned();
-      self.bundle.data.text_sheet.0 = self.texture_cache[&drawable.text].raw.clone();
+      match self.texture_cache.get(&drawable.text) {
+        Some(texture) => {
+          self.current_text = drawable.text.to_owned();
+          self.bundle.data.text_sheet.0 = texture.raw.clone();
+        }
+        None => {
+          if self.missing_texts.insert(drawable.text.clone()) {
+            eprintln!("HUD text \"{}\" was not rendered up front, skipping it", drawable.text);
+          }
+          return;
+        }
+      }
     }
+    encoder.update_constant_buffer(&self.bundle.data.position_cb, &drawable.position);
+    self.bundle.encode(encoder);
+  }
+}
+
+/// Draws a text that changes too often to render every value up front, like the editor brush tile.
+/// The text is drawn into one texture again whenever it changes.
+pub struct DynamicTextDrawSystem<R: gfx::Resources> {
+  bundle: gfx::pso::bundle::Bundle<R, text_pipeline::Data<R>>,
+  texture: gfx::handle::Texture<R, R8_G8_B8_A8>,
+  font: Font<'static>,
+  current_text: Option<String>,
+}
+
+impl<R: gfx::Resources> DynamicTextDrawSystem<R> {
+  pub fn new<F>(factory: &mut F,
+                rtv: gfx::handle::RenderTargetView<R, ColorFormat>,
+                dsv: gfx::handle::DepthStencilView<R, DepthFormat>) -> Result<DynamicTextDrawSystem<R>, GameError>
+    where F: gfx::Factory<R> {
+    use gfx::traits::FactoryExt;
+
+    let (width, height) = DYNAMIC_TEXT_SIZE;
+    let kind = gfx::texture::Kind::D2(width as u16, height as u16, gfx::texture::AaMode::Single);
+    let texture = factory.create_texture::<R8_G8_B8_A8>(kind,
+                                                        1,
+                                                        gfx::memory::Bind::SHADER_RESOURCE | gfx::memory::Bind::TRANSFER_DST,
+                                                        gfx::memory::Usage::Dynamic,
+                                                        Some(ChannelType::Unorm))
+      .map_err(|e| GameError::Device { context: format!("Text texture creation error: {}", e) })?;
+    let view = factory.view_texture_as_shader_resource::<ColorFormat>(&texture, (0, 0), Swizzle::new())
+      .map_err(|e| GameError::Device { context: format!("Text texture view error: {:?}", e) })?;
+
+    let pso = factory.create_pipeline_simple(SHADER_VERT, SHADER_FRAG, text_pipeline::new())
+      .map_err(|e| GameError::Device { context: format!("HUD shader loading error: {}", e) })?;
+    let rect_mesh = RectangularTexturedMesh::new(factory, Texture::new(view, None), Geometry::Rectangle, Point2::new(1.0, 1.0), None, None, None);
+    let pipeline_data = text_pipeline::Data {
+      vbuf: rect_mesh.mesh.vertex_buffer,
+      position_cb: factory.create_constant_buffer(1),
+      text_sheet: (rect_mesh.mesh.texture.raw, factory.create_sampler_linear()),
+      out_color: rtv,
+      out_depth: dsv,
+    };
+
+    Ok(DynamicTextDrawSystem {
+      bundle: gfx::Bundle::new(rect_mesh.mesh.slice, pso, pipeline_data),
+      texture,
+      font: load_font()?,
+      current_text: None,
+    })
+  }
+
+  pub fn set_pipeline(&mut self, pso: gfx::PipelineState<R, text_pipeline::Meta>) {
+    self.bundle.pso = pso;
+  }
+
+  /// A text whose texture could not be updated is reported once and not retried until the text changes.
+  pub fn draw<C>(&mut self,
+                 drawable: &TextDrawable,
+                 encoder: &mut gfx::Encoder<R, C>) -> Result<(), GameError>
+    where C: gfx::CommandBuffer<R> {
+    if self.current_text.as_ref() != Some(&drawable.text) {
+      self.current_text = Some(drawable.text.clone());
+      let (width, height) = DYNAMIC_TEXT_SIZE;
+      let (size, text_pixels) = font::draw_text(&self.font, height as f32, &drawable.text);
+      // The last column stays transparent, the sampler repeats it past the edge of the texture.
+      let mut pixels = vec![[0u8; 4]; width * height];
+      for y in 0..(size.y as usize).min(height) {
+        for x in 0..(size.x as usize).min(width - 1) {
+          let i = (y * size.x as usize + x) * 4;
+          pixels[y * width + x] = [text_pixels[i], text_pixels[i + 1], text_pixels[i + 2], text_pixels[i + 3]];
+        }
+      }
+      let image = gfx::texture::ImageInfoCommon {
+        xoffset: 0,
+        yoffset: 0,
+        zoffset: 0,
+        width: width as u16,
+        height: height as u16,
+        depth: 1,
+        format: (),
+        mipmap: 0,
+      };
+      encoder.update_texture::<R8_G8_B8_A8, ColorFormat>(&self.texture, None, image, &pixels)
+        .map_err(|e| GameError::Device { context: format!("Text texture update error: {:?}", e) })?;
+    }
+    encoder.update_constant_buffer(&self.bundle.data.position_cb, &drawable.position);
     self.bundle.encode(encoder);
+    Ok(())
   }
 }
 
@@ -103,16 +239,21 @@ pub struct PreDrawSystem;
 
 impl<'a> specs::prelude::System<'a> for PreDrawSystem {
   type SystemData = (ReadStorage<'a, CharacterDrawable>,
-                     WriteStorage<'a, hud_objects::HudObjects>);
+                     WriteStorage<'a, hud_objects::HudObjects>,
+                     Read<'a, ZombieWaves>,
+                     Read<'a, GameConfig>);
 
-  fn run(&mut self, (character_drawable, mut hud_objects): Self::SystemData) {
+  fn run(&mut self, (character_drawable, mut hud_objects, waves, config): Self::SystemData) {
     use specs::join::Join;
 
     for (cd, huds) in (&character_drawable, &mut hud_objects).join() {
       let new_ammo_text = format!("Ammo {}", cd.stats.ammunition);
       let new_mag_text = format!("Magazines {}/2", cd.stats.magazines);
+      let new_health_text = health_text(cd.stats.health, &config);
       huds.objects[1].update(new_ammo_text);
       huds.objects[2].update(new_mag_text);
+      huds.objects[3].update(new_health_text);
+      huds.objects[4].update(wave_text(waves.wave));
     }
   }
 }
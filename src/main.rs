@@ -4,8 +4,12 @@ extern crate gfx;
 
 use getopts::Options;
 
-use crate::game::constants::{GAME_TITLE, GAME_VERSION};
+use crate::game::campaign::Campaign;
+use crate::game::config::GameConfig;
+use crate::game::constants::{CAMPAIGN_FILE_PATH, CONFIG_FILE_PATH, GAME_TITLE, GAME_VERSION, HEADLESS_DEFAULT_TICKS, KEYBINDINGS_PATH, ZOMBIE_ARCHETYPES_PATH};
+use crate::game::error::GameError;
 use crate::gfx_app::GameOptions;
+use crate::terrain::generator::{self, GENERATED_MAP_SIZE};
 
 mod audio;
 mod bullet;
@@ -13,6 +17,7 @@ mod gfx_app;
 mod game;
 mod data;
 mod critter;
+mod editor;
 pub mod graphics;
 mod hud;
 mod terrain_object;
@@ -23,7 +28,51 @@ mod shaders;
 mod zombie;
 
 fn print_usage() {
-  println!("USAGE:\nhinterland [FLAGS]\n\nFLAGS:\n-h, --help\t\t\tPrints help information\n-v, --version\t\t\tPrints version information\n-w, --windowed_mode\t\tRun game in windowed mode");
+  println!("USAGE:\nhinterland [FLAGS] [OPTIONS]\n\nFLAGS:\n-h, --help\t\t\tPrints help information\n-v, --version\t\t\tPrints version information\n-w, --windowed_mode\t\tRun game in windowed mode\n--headless\t\t\tRun simulation without window, rendering or audio\n\nOPTIONS:\n--ticks <N>\t\t\tNumber of simulation ticks in headless mode (default 1000)\n--seed <SEED>\t\t\tSeed for the game random number generator\n--record <FILE>\t\t\tRecord player input to a replay file\n--replay <FILE>\t\t\tPlay back input from a replay file, using its recorded seed\n--load <FILE>\t\t\tStart from a saved game state\n--map <FILE>\t\t\tPlay a single Tiled map instead of the campaign\n--generate <SEED>\t\tPlay a procedurally generated map\n--map-size <WxH>\t\tSize of the generated map in tiles (default 128x128)\n--export <FILE>\t\t\tWrite the generated map as a Tiled map and exit\n--edit <FILE>\t\t\tOpen a Tiled map in the map editor\n--config <FILE>\t\t\tGameplay and display settings (default config.json)\n--set <KEY=VALUE>\t\tOverride a single setting, may be repeated");
+}
+
+fn load_config(matches: &getopts::Matches) -> Result<GameConfig, GameError> {
+  let (path, required) = match matches.opt_str("config") {
+    Some(path) => (path, true),
+    None => (CONFIG_FILE_PATH.to_string(), false),
+  };
+  let mut config = GameConfig::load(&path, required)?;
+  for setting in matches.opt_strs("set") {
+    config.set_override(&setting)?;
+  }
+  let source = if matches.opt_present("set") { format!("{} with --set overrides", path) } else { path };
+  config.validate(&source)?;
+  config.zombie_archetypes = data::load_zombie_archetypes(ZOMBIE_ARCHETYPES_PATH)?;
+  Ok(config)
+}
+
+fn generated_map(matches: &getopts::Matches) -> Option<(u64, usize, usize)> {
+  let seed = matches.opt_str("generate")?;
+  let seed = seed.parse::<u64>().unwrap_or_else(|e| {
+    eprintln!("Error: Invalid --generate value {}: {}", seed, e);
+    std::process::exit(1);
+  });
+  let size = matches.opt_str("map-size").unwrap_or_else(|| format!("{}x{}", GENERATED_MAP_SIZE, GENERATED_MAP_SIZE));
+  let (width, height) = match size.split('x').map(str::parse::<usize>).collect::<Vec<_>>().as_slice() {
+    [Ok(width), Ok(height)] => (*width, *height),
+    _ => {
+      eprintln!("Error: Invalid --map-size value {}, expected WIDTHxHEIGHT", size);
+      std::process::exit(1);
+    }
+  };
+  Some((seed, width, height))
+}
+
+fn load_campaign(matches: &getopts::Matches) -> Result<Campaign, GameError> {
+  if let Some((seed, width, height)) = generated_map(matches) {
+    let name = generator::map_name(seed, width, height);
+    generator::check_size(&name, width, height)?;
+    return Ok(Campaign::single(&name));
+  }
+  match matches.opt_str("edit").or_else(|| matches.opt_str("map")) {
+    Some(map) => Ok(Campaign::single(&map)),
+    None => Campaign::load(CAMPAIGN_FILE_PATH),
+  }
 }
 
 fn print_version() {
@@ -36,6 +85,19 @@ pub fn main() {
   opts.optflag("w", "windowed_mode", "Run game in windowed mode");
   opts.optflag("h", "help", "Prints help information");
   opts.optflag("v", "version", "Prints version information");
+  opts.optflag("", "headless", "Run simulation without window, rendering or audio");
+  opts.optopt("", "ticks", "Number of simulation ticks in headless mode", "N");
+  opts.optopt("", "seed", "Seed for the game random number generator", "SEED");
+  opts.optopt("", "record", "Record player input to a replay file", "FILE");
+  opts.optopt("", "replay", "Play back input from a replay file", "FILE");
+  opts.optopt("", "load", "Start from a saved game state", "FILE");
+  opts.optopt("", "map", "Play a single Tiled map instead of the campaign", "FILE");
+  opts.optopt("", "generate", "Play a procedurally generated map", "SEED");
+  opts.optopt("", "map-size", "Size of the generated map in tiles", "WxH");
+  opts.optopt("", "export", "Write the generated map as a Tiled map and exit", "FILE");
+  opts.optopt("", "edit", "Open a Tiled map in the map editor", "FILE");
+  opts.optopt("", "config", "Gameplay and display settings", "FILE");
+  opts.optmulti("", "set", "Override a single setting", "KEY=VALUE");
 
   let matches = match opts.parse(&args[1..]) {
     Ok(matching_args) => { matching_args }
@@ -52,7 +114,93 @@ pub fn main() {
     return;
   }
 
-  let game_opt = GameOptions::new(matches.opt_present("windowed_mode"));
-  let mut window = gfx_app::WindowContext::new(game_opt);
-  gfx_app::init::run(&mut window);
+  if let Some(path) = matches.opt_str("export") {
+    let (seed, width, height) = generated_map(&matches).unwrap_or_else(|| {
+      eprintln!("Error: --export needs --generate");
+      std::process::exit(1);
+    });
+    let name = generator::map_name(seed, width, height);
+    let exported = generator::check_size(&name, width, height)
+      .and_then(|_| generator::generate(seed, width, height).export(&path));
+    if let Err(e) = exported {
+      eprintln!("Error: {}", e);
+      std::process::exit(1);
+    }
+    println!("Exported {} to {}", name, path);
+    return;
+  }
+
+  let config = load_config(&matches).unwrap_or_else(|e| {
+    eprintln!("Error: {}", e);
+    std::process::exit(1);
+  });
+  let campaign = load_campaign(&matches).unwrap_or_else(|e| {
+    eprintln!("Error: {}", e);
+    std::process::exit(1);
+  });
+
+  if matches.opt_present("record") && matches.opt_present("replay") {
+    eprintln!("Error: --record and --replay cannot be used together");
+    std::process::exit(1);
+  }
+  if matches.opt_present("edit") && (matches.opt_present("headless") || matches.opt_present("replay") || matches.opt_present("record")) {
+    eprintln!("Error: --edit cannot be used with --headless, --replay or --record");
+    std::process::exit(1);
+  }
+
+  if matches.opt_present("record") && matches.opt_present("load") {
+    eprintln!("Error: --record and --load cannot be used together");
+    std::process::exit(1);
+  }
+
+  let replay = matches.opt_str("replay").map(|path| {
+    game::replay::Replay::load(&path).unwrap_or_else(|e| {
+      eprintln!("Error: {}", e);
+      std::process::exit(1);
+    })
+  });
+
+  // A replay is played with the settings and maps it was recorded with, the archetypes come from the data file.
+  let (config, campaign) = match &replay {
+    Some(r) => (GameConfig { zombie_archetypes: config.zombie_archetypes, ..r.config.clone() }, r.campaign.clone()),
+    None => (config, campaign),
+  };
+
+  let seed = match (matches.opt_str("seed"), &replay) {
+    (_, Some(r)) => r.seed,
+    (Some(s), None) => s.parse::<u64>().unwrap_or_else(|e| {
+      eprintln!("Error: Invalid --seed value {}: {}", s, e);
+      std::process::exit(1);
+    }),
+    (None, None) => game::new_seed(),
+  };
+  println!("seed={}", seed);
+
+  if matches.opt_present("headless") {
+    let ticks = match matches.opt_str("ticks") {
+      Some(t) => t.parse::<u64>().unwrap_or_else(|e| {
+        eprintln!("Error: Invalid --ticks value {}: {}", t, e);
+        std::process::exit(1);
+      }),
+      None => HEADLESS_DEFAULT_TICKS,
+    };
+    if let Err(e) = game::simulation::run_headless(ticks, seed, &config, &campaign, replay, matches.opt_str("load")) {
+      eprintln!("Error: {}", e);
+      std::process::exit(1);
+    }
+    return;
+  }
+
+  let game_opt = GameOptions::new(matches.opt_present("windowed_mode"), (config.resolution_x, config.resolution_y));
+  let bindings = gfx_app::bindings::InputBindings::load(KEYBINDINGS_PATH).unwrap_or_else(|e| {
+    eprintln!("Error: {}", e);
+    std::process::exit(1);
+  });
+  let mut window = gfx_app::WindowContext::new(game_opt, bindings);
+  let fixed_seed = matches.opt_present("seed");
+  if let Err(e) = gfx_app::init::run(&mut window, seed, fixed_seed, &config, &campaign, matches.opt_str("record"), replay, matches.opt_str("load"),
+                                     matches.opt_present("edit")) {
+    eprintln!("Error: {}", e);
+    std::process::exit(1);
+  }
 }
@@ -12,6 +12,10 @@ uniform b_BulletPosition {
   vec2 a_position;
 };
 
+uniform b_ViewTranslation {
+  vec2 u_ViewTranslation;
+};
+
 uniform b_BulletRotation {
   float a_rotation;
 };
@@ -21,5 +25,5 @@ void main() {
                       sin(a_rotation),  cos(a_rotation),   0.0,
                       0.0,              0.0,               1.0) * a_Pos;
 
-  gl_Position = vec4(a_position, 0.0, 0.0) + vec4(rot_pos, 1.0) * u_Proj * u_View * u_Model;
+  gl_Position = vec4(a_position + u_ViewTranslation, 0.0, 0.0) + vec4(rot_pos, 1.0) * u_Proj * u_View * u_Model;
 }
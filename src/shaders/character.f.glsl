@@ -5,6 +5,10 @@ out vec4 Target0;
 
 uniform sampler2D t_CharacterSheet;
 
+uniform b_Tint {
+  vec4 u_Tint;
+};
+
 void main() {
   vec4 tex = texture(t_CharacterSheet, v_BufPos).rgba;
   if(tex.a < 0.1) {
@@ -13,5 +17,5 @@ void main() {
   tex.r = smoothstep(0.1, 1.0, tex.r);
   tex.g = smoothstep(0.1, 1.0, tex.g);
   tex.b = smoothstep(0.1, 1.0, tex.b);
-  Target0 = tex;
+  Target0 = tex * u_Tint;
 }
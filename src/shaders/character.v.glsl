@@ -21,6 +21,10 @@ uniform b_CharacterPosition {
   vec2 a_position;
 };
 
+uniform b_ViewTranslation {
+  vec2 u_ViewTranslation;
+};
+
 void main() {
   v_BufPos = vec2(a_BufPos);
 
@@ -31,5 +35,5 @@ void main() {
   v_BufPos.x /= x_div;
   v_BufPos.x += a_index / x_div;
 
-  gl_Position = vec4(a_position, 0.0, 0.0) + u_Proj * u_View * u_Model * vec4(a_Pos, 1.0);
+  gl_Position = vec4(a_position + u_ViewTranslation, 0.0, 0.0) + u_Proj * u_View * u_Model * vec4(a_Pos, 1.0);
 }
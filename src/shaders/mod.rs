@@ -6,14 +6,14 @@ use cgmath::BaseFloat;
 use gfx;
 
 gfx_defines! {
-  constant TileMapData {
-    data: [f32; 4] = "data",
-  }
-
   constant Position {
     position: [f32; 2] = "a_position",
   }
 
+  constant ViewTranslation {
+    translation: [f32; 2] = "u_ViewTranslation",
+  }
+
   constant Rotation {
     rotation: f32 = "a_rotation",
   }
@@ -23,8 +23,10 @@ gfx_defines! {
   }
 
   constant TilemapSettings {
+    tilesets: [[f32; 4]; 4] = "u_Tilesets",
+    tileset_spacing: [[f32; 4]; 4] = "u_TilesetSpacing",
+    layer_opacity: [f32; 4] = "u_LayerOpacity",
     world_size: [f32; 2] = "u_WorldSize",
-    tilesheet_size: [f32; 2] = "u_TilesheetSize",
   }
 
   vertex VertexData {
@@ -39,10 +41,15 @@ gfx_defines! {
     index: f32 = "a_index",
   }
 
+  constant Tint {
+    color: [f32; 4] = "u_Tint",
+  }
+
   pipeline bullet_pipeline {
     vbuf: gfx::VertexBuffer<VertexData> = (),
     projection_cb: gfx::ConstantBuffer<Projection> = "b_VsLocals",
     position_cb: gfx::ConstantBuffer<Position> = "b_BulletPosition",
+    view_cb: gfx::ConstantBuffer<ViewTranslation> = "b_ViewTranslation",
     rotation_cb: gfx::ConstantBuffer<Rotation> = "b_BulletRotation",
     out_color: gfx::RenderTarget<gfx::format::Rgba8> = "Target0",
     out_depth: gfx::DepthTarget<gfx::format::DepthStencil> = gfx::preset::depth::LESS_EQUAL_WRITE,
@@ -52,7 +59,9 @@ gfx_defines! {
     vbuf: gfx::VertexBuffer<VertexData> = (),
     projection_cb: gfx::ConstantBuffer<Projection> = "b_VsLocals",
     position_cb: gfx::ConstantBuffer<Position> = "b_CharacterPosition",
+    view_cb: gfx::ConstantBuffer<ViewTranslation> = "b_ViewTranslation",
     character_sprite_cb: gfx::ConstantBuffer<CharacterSheet> = "b_CharacterSprite",
+    tint_cb: gfx::ConstantBuffer<Tint> = "b_Tint",
     charactersheet: gfx::TextureSampler<[f32; 4]> = "t_CharacterSheet",
     out_color: gfx::RenderTarget<gfx::format::Rgba8> = "Target0",
     out_depth: gfx::DepthTarget<gfx::format::DepthStencil> = gfx::preset::depth::LESS_EQUAL_WRITE,
@@ -61,9 +70,10 @@ gfx_defines! {
   pipeline tilemap_pipeline {
     vbuf: gfx::VertexBuffer<VertexData> = (),
     position_cb: gfx::ConstantBuffer<Position> = "b_TileMapPosition",
+    view_cb: gfx::ConstantBuffer<ViewTranslation> = "b_ViewTranslation",
     time_passed_cb: gfx::ConstantBuffer<Time> = "b_TimeModulo",
     projection_cb: gfx::ConstantBuffer<Projection> = "b_VsLocals",
-    tilemap: gfx::ConstantBuffer<TileMapData> = "b_TileMap",
+    tilemap: gfx::TextureSampler<u32> = "t_TileMap",
     tilemap_cb: gfx::ConstantBuffer<TilemapSettings> = "b_PsLocals",
     tilesheet: gfx::TextureSampler<[f32; 4]> = "t_TileSheet",
     out_color: gfx::RenderTarget<gfx::format::Rgba8> = "Target0",
@@ -73,6 +83,7 @@ gfx_defines! {
   pipeline static_element_pipeline {
     vbuf: gfx::VertexBuffer<VertexData> = (),
     position_cb: gfx::ConstantBuffer<Position> = "b_StaticElementPosition",
+    view_cb: gfx::ConstantBuffer<ViewTranslation> = "b_ViewTranslation",
     time_passed_cb: gfx::ConstantBuffer<Time> = "b_TimeModulo",
     projection_cb: gfx::ConstantBuffer<Projection> = "b_VsLocals",
     static_element_sheet: gfx::TextureSampler<[f32; 4]> = "t_StaticElementSheet",
@@ -104,6 +115,14 @@ impl Time {
   }
 }
 
+impl ViewTranslation {
+  pub fn new(translation: Position) -> ViewTranslation {
+    ViewTranslation {
+      translation: translation.position
+    }
+  }
+}
+
 impl Rotation {
   pub fn new(rotation: f32) -> Rotation {
     Rotation {
@@ -129,16 +148,6 @@ impl Iterator for VertexData {
   }
 }
 
-impl TileMapData {
-  pub fn new_empty() -> TileMapData {
-    TileMapData { data: [32.0, 32.0, 0.0, 0.0] }
-  }
-
-  pub fn new(data: [f32; 4]) -> TileMapData {
-    TileMapData { data }
-  }
-}
-
 impl Position {
   pub fn new<T: BaseFloat>(x: T, y: T) -> Position where f32: std::convert::From<T> {
     Position { position: [f32::from(x), f32::from(y)] }
@@ -3,22 +3,21 @@
 in vec2 v_BufPos;
 out vec4 Target0;
 
-struct TileMapData {
-  vec4 data;
-};
-
-const int TILEMAP_BUF_LENGTH = 4096;
+const int MAX_TILE_LAYERS = 4;
+const int MAX_TILESETS = 4;
+const float TILESHEET_SIZE = 1024.0;
 
-layout (std140) uniform b_TileMap {
-  TileMapData u_Data[TILEMAP_BUF_LENGTH];
-};
+uniform usampler2DArray t_TileMap;
 
+// Per tileset: first gid, columns and tile size in pixels; margin and spacing in pixels.
 layout (std140) uniform b_PsLocals {
+  mat4 u_Tilesets;
+  mat4 u_TilesetSpacing;
+  vec4 u_LayerOpacity;
   vec2 u_WorldSize;
-  vec2 u_TilesheetSize;
 };
 
-uniform sampler2D t_TileSheet;
+uniform sampler2DArray t_TileSheet;
 
 uniform b_TimeModulo {
   float a_time;
@@ -41,23 +40,34 @@ void main() {
   vec2 bufTileCoords = floor(v_BufPos);
   vec2 rawUvOffsets = vec2(v_BufPos.x - bufTileCoords.x, 1.0 - (v_BufPos.y - bufTileCoords.y));
 
-  int bufIdx = int((bufTileCoords.y * u_WorldSize.x) + bufTileCoords.x);
-  vec4 entry = u_Data[bufIdx].data;
-  vec2 coords = vec2(0.0, 0.0);
-
-  if (bufIdx < TILEMAP_BUF_LENGTH) {
-    coords = vec2(mod(entry.x, u_TilesheetSize.y), floor(entry.x / u_TilesheetSize.x));
-  } else if (bufIdx < (TILEMAP_BUF_LENGTH * 2)) {
-    entry = u_Data[bufIdx - TILEMAP_BUF_LENGTH].data;
-    coords = vec2(mod(entry.y, u_TilesheetSize.y), floor(entry.y / u_TilesheetSize.x));
-  } else if (bufIdx < (TILEMAP_BUF_LENGTH * 3)) {
-    entry = u_Data[bufIdx - TILEMAP_BUF_LENGTH * 2].data;
-    coords = vec2(mod(entry.z, u_TilesheetSize.y), floor(entry.z / u_TilesheetSize.x));
-  } else {
-    entry = u_Data[bufIdx-TILEMAP_BUF_LENGTH * 3].data;
-    coords = vec2(mod(entry.w, u_TilesheetSize.y), floor(entry.w / u_TilesheetSize.x));
+  ivec2 tilePos = clamp(ivec2(bufTileCoords), ivec2(0, 0), ivec2(u_WorldSize) - 1);
+  // Layers are blended bottom up with the "over" operator, gid 0 marks an empty tile.
+  vec4 tex = vec4(0.0);
+  for (int layer = 0; layer < MAX_TILE_LAYERS; layer++) {
+    uint gid = texelFetch(t_TileMap, ivec3(tilePos, layer), 0).r;
+    float opacity = u_LayerOpacity[layer];
+    if (gid == 0u || opacity <= 0.0) {
+      continue;
+    }
+    // Tilesets are sorted by first gid, unused slots have a first gid of 0.
+    int tileset = 0;
+    for (int i = 1; i < MAX_TILESETS; i++) {
+      if (u_Tilesets[i].x > 0.0 && float(gid) >= u_Tilesets[i].x) {
+        tileset = i;
+      }
+    }
+    vec4 grid = u_Tilesets[tileset];
+    vec4 spacing = u_TilesetSpacing[tileset];
+    float entry = float(gid) - grid.x;
+    vec2 coords = vec2(mod(entry, grid.y), floor(entry / grid.y));
+    vec2 pixel = spacing.x + coords * (grid.zw + spacing.y) + rawUvOffsets * grid.zw;
+    vec4 layerTex = textureLod(t_TileSheet, vec3(pixel / TILESHEET_SIZE, float(tileset)), 0.0);
+    float alpha = layerTex.a * opacity;
+    float outAlpha = alpha + tex.a * (1.0 - alpha);
+    if (outAlpha > 0.0) {
+      tex = vec4((layerTex.rgb * alpha + tex.rgb * tex.a * (1.0 - alpha)) / outAlpha, outAlpha);
+    }
   }
-  vec2 uvCoords = (coords.xy + rawUvOffsets) / u_TilesheetSize.xy;
 
   vec3 norm = normalize(Normal);
   vec3 lightDir = normalize(lightPos - vec3(v_BufPos, 0.0));
@@ -65,7 +75,6 @@ void main() {
   float diff = max(dot(norm, lightDir), 0.0);
   vec3 diffuse = diff * lightColor;
 
-  vec4 tex = texture(t_TileSheet, uvCoords);
   tex *= vec4(diffuse + ambientColor, 1.0);
   if(tex.a < 0.1) {
     discard;
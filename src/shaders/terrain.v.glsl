@@ -14,7 +14,11 @@ uniform b_TileMapPosition {
   vec2 a_position;
 };
 
+uniform b_ViewTranslation {
+  vec2 u_ViewTranslation;
+};
+
 void main() {
   v_BufPos = a_BufPos;
-  gl_Position = vec4(a_position, 0.0, 0.0) + u_Proj * u_View * u_Model * vec4(a_Pos, 0.0, 1.0);
+  gl_Position = vec4(a_position + u_ViewTranslation, 0.0, 0.0) + u_Proj * u_View * u_Model * vec4(a_Pos, 0.0, 1.0);
 }
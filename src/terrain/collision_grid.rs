@@ -0,0 +1,130 @@
+use std::collections::VecDeque;
+
+use cgmath::Point2;
+use tiled::{Map, PropertyValue};
+
+use crate::data::get_map_tile;
+
+const COLLIDES_PROPERTY: &str = "collides";
+
+/// One bit per map tile telling whether characters, zombies and bullets can pass through it.
+pub struct CollisionGrid {
+  width: usize,
+  height: usize,
+  blocked: Vec<u64>,
+  revision: u64,
+}
+
+impl CollisionGrid {
+  pub fn new(width: usize, height: usize) -> CollisionGrid {
+    CollisionGrid {
+      width,
+      height,
+      blocked: vec![0; width * height / 64 + 1],
+      revision: 0,
+    }
+  }
+
+  /// Blocks the map edge, tiles whose tileset entry has `collides` set and the given object footprints.
+  pub fn from_map(map: &Map, footprints: &[[i32; 2]]) -> CollisionGrid {
+    let (width, height) = (map.width as usize, map.height as usize);
+    let mut grid = CollisionGrid::new(width, height);
+
+    for y in 0..height {
+      for x in 0..width {
+        let is_edge = x == 0 || y == 0 || x + 2 >= width || y + 2 >= height;
+        let collides = (0..map.layers.len())
+          .filter_map(|layer| get_map_tile(map, layer, x, y))
+          .any(|gid| tile_collides(map, gid));
+        if is_edge || collides {
+          grid.set_blocked(Point2::new(x as i32, y as i32), true);
+        }
+      }
+    }
+    grid.set_footprint(footprints, true);
+    grid
+  }
+
+  pub fn width(&self) -> usize {
+    self.width
+  }
+
+  pub fn height(&self) -> usize {
+    self.height
+  }
+
+  /// Bumped on every change so cached path data knows when to recompute.
+  pub fn revision(&self) -> u64 {
+    self.revision
+  }
+
+  fn index(&self, tile: Point2<i32>) -> Option<usize> {
+    if tile.x >= 0 && (tile.x as usize) < self.width && tile.y >= 0 && (tile.y as usize) < self.height {
+      Some(tile.y as usize * self.width + tile.x as usize)
+    } else {
+      None
+    }
+  }
+
+  pub fn contains(&self, tile: Point2<i32>) -> bool {
+    self.index(tile).is_some()
+  }
+
+  /// Tiles outside the map are always blocked.
+  pub fn is_blocked(&self, tile: Point2<i32>) -> bool {
+    match self.index(tile) {
+      Some(idx) => self.blocked[idx / 64] & (1 << (idx % 64)) != 0,
+      None => true,
+    }
+  }
+
+  pub fn set_blocked(&mut self, tile: Point2<i32>, blocked: bool) {
+    if let Some(idx) = self.index(tile) {
+      if blocked {
+        self.blocked[idx / 64] |= 1 << (idx % 64);
+      } else {
+        self.blocked[idx / 64] &= !(1 << (idx % 64));
+      }
+      self.revision += 1;
+    }
+  }
+
+  /// Marks every tile an object covers, call with `false` when the object is destroyed.
+  pub fn set_footprint(&mut self, tiles: &[[i32; 2]], blocked: bool) {
+    for tile in tiles {
+      self.set_blocked(Point2::new(tile[0], tile[1]), blocked);
+    }
+  }
+
+  /// Closest passable tile to `tile` in steps between neighbouring tiles, searched outwards from `tile`
+  /// itself, or None when every tile of the map is blocked.
+  pub fn nearest_free(&self, tile: Point2<i32>) -> Option<Point2<i32>> {
+    let clamped = Point2::new(tile.x.max(0).min(self.width as i32 - 1), tile.y.max(0).min(self.height as i32 - 1));
+    let mut visited = vec![false; self.width * self.height];
+    let mut queue = VecDeque::new();
+    visited[self.index(clamped)?] = true;
+    queue.push_back(clamped);
+    while let Some(current) = queue.pop_front() {
+      if !self.is_blocked(current) {
+        return Some(current);
+      }
+      for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)].iter() {
+        let next = Point2::new(current.x + dx, current.y + dy);
+        if let Some(idx) = self.index(next) {
+          if !visited[idx] {
+            visited[idx] = true;
+            queue.push_back(next);
+          }
+        }
+      }
+    }
+    None
+  }
+}
+
+fn tile_collides(map: &Map, gid: u32) -> bool {
+  map.get_tileset_by_gid(gid)
+    .and_then(|tileset| tileset.tiles.iter().find(|tile| tile.id + tileset.first_gid == gid))
+    .and_then(|tile| tile.properties.get(COLLIDES_PROPERTY))
+    == Some(&PropertyValue::BoolValue(true))
+}
@@ -0,0 +1,81 @@
+#[test]
+fn map_collision_grid_test() {
+  use cgmath::Point2;
+  use crate::data::load_map_file;
+  use crate::game::constants::MAP_FILE_PATH;
+  use crate::terrain::collision_grid::CollisionGrid;
+
+  let footprints = [[55, 54], [56, 55], [72, 65]];
+  let grid = CollisionGrid::from_map(&load_map_file(MAP_FILE_PATH).unwrap(), &footprints);
+  assert_eq!((grid.width(), grid.height()), (128, 128));
+  for tile in footprints.iter() {
+    assert!(grid.is_blocked(Point2::new(tile[0], tile[1])));
+  }
+  assert!(!grid.is_blocked(Point2::new(60, 60)));
+  assert!(grid.is_blocked(Point2::new(0, 60)));
+  assert!(grid.is_blocked(Point2::new(60, 126)));
+  assert!(grid.is_blocked(Point2::new(-1, 60)));
+}
+
+#[test]
+fn collision_grid_dynamic_update_test() {
+  use cgmath::Point2;
+  use crate::graphics::{can_move_to_tile, set_position};
+  use crate::terrain::collision_grid::CollisionGrid;
+
+  let mut grid = CollisionGrid::new(128, 128);
+  let footprint = [[70, 70], [71, 70]];
+  let revision = grid.revision();
+  grid.set_footprint(&footprint, true);
+  assert!(grid.is_blocked(Point2::new(70, 70)) && grid.is_blocked(Point2::new(71, 70)));
+  assert!(!grid.is_blocked(Point2::new(72, 70)));
+  assert!(grid.revision() > revision);
+
+  grid.set_footprint(&footprint, false);
+  assert!(!grid.is_blocked(Point2::new(70, 70)));
+  assert!(can_move_to_tile(set_position(0, 0), &grid));
+}
+
+#[test]
+fn nearest_free_tile_test() {
+  use cgmath::Point2;
+  use crate::terrain::collision_grid::CollisionGrid;
+
+  let mut grid = CollisionGrid::new(8, 8);
+  assert_eq!(grid.nearest_free(Point2::new(3, 3)), Some(Point2::new(3, 3)));
+  assert_eq!(grid.nearest_free(Point2::new(-5, 20)), Some(Point2::new(0, 7)), "Tiles outside the map start from the edge");
+
+  for x in 0..8 {
+    for y in 0..8 {
+      grid.set_blocked(Point2::new(x, y), x < 6);
+    }
+  }
+  assert_eq!(grid.nearest_free(Point2::new(2, 4)), Some(Point2::new(6, 4)));
+
+  for y in 0..8 {
+    grid.set_blocked(Point2::new(6, y), true);
+    grid.set_blocked(Point2::new(7, y), true);
+  }
+  assert_eq!(grid.nearest_free(Point2::new(2, 4)), None);
+}
+
+#[test]
+fn walled_in_random_tile_position_test() {
+  use cgmath::Point2;
+  use crate::game::GameRng;
+  use crate::graphics::{can_move_to_tile, coords_to_tile, get_nearest_random_tile_position, tile_to_coords};
+  use crate::terrain::collision_grid::CollisionGrid;
+
+  let mut grid = CollisionGrid::new(128, 128);
+  for x in 50..71 {
+    for y in 50..71 {
+      grid.set_blocked(Point2::new(x, y), true);
+    }
+  }
+  let walled_in = tile_to_coords(Point2::new(60.5, 60.5));
+
+  let target = get_nearest_random_tile_position(walled_in, &grid, &mut GameRng::new(1));
+  assert!(can_move_to_tile(target, &grid), "Should find a way out when every nearby tile is blocked");
+  let tile = coords_to_tile(target);
+  assert_eq!((tile.x - 60).abs() + (tile.y - 60).abs(), 11, "Should pick one of the closest open tiles");
+}
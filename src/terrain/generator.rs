@@ -0,0 +1,397 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Component, Path};
+
+use tiled::PropertyValue;
+
+use crate::game::constants::TERRAIN_TILESET_PATH;
+use crate::game::error::GameError;
+use crate::game::GameRng;
+use crate::terrain::map_document::{DocumentLayer, DocumentTileset, MapDocument, Placeable};
+use crate::terrain::tile_map::MAX_MAP_SIZE;
+
+/// Campaign entries starting with this are generated instead of read from a file, e.g. `generated:42:128x128`.
+pub const GENERATED_MAP_PREFIX: &str = "generated:";
+pub const GENERATED_MAP_SIZE: usize = 128;
+const MIN_GENERATED_SIZE: usize = 32;
+
+// Global tile ids in terrain.png.
+const GRASS: u32 = 353;
+const FLOWERS: u32 = 358;
+const DIRT: u32 = 162;
+const WATER: u32 = 189;
+const ROAD: u32 = 393;
+
+const ZOMBIE_SPAWNS: usize = 8;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Ground {
+  Grass,
+  Flowers,
+  Dirt,
+  Water,
+  Road,
+}
+
+impl Ground {
+  fn gid(self) -> u32 {
+    match self {
+      Ground::Grass => GRASS,
+      Ground::Flowers => FLOWERS,
+      Ground::Dirt => DIRT,
+      Ground::Water => WATER,
+      Ground::Road => ROAD,
+    }
+  }
+}
+
+/// Random values on a lattice of `cell` tiles, smoothly interpolated to a number between 0 and 1.
+struct ValueNoise {
+  cell: f32,
+  columns: usize,
+  lattice: Vec<f32>,
+}
+
+impl ValueNoise {
+  fn new(width: usize, height: usize, cell: usize, rng: &mut GameRng) -> ValueNoise {
+    let (columns, rows) = (width / cell + 2, height / cell + 2);
+    let lattice = (0..columns * rows).map(|_| rng.get_rand_from_range(0, 1000) as f32 / 1000.0).collect();
+    ValueNoise { cell: cell as f32, columns, lattice }
+  }
+
+  fn at(&self, col: usize, row: usize) -> f32 {
+    let (x, y) = (col as f32 / self.cell, row as f32 / self.cell);
+    let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+    let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+    let (tx, ty) = (smooth(x.fract()), smooth(y.fract()));
+    let value = |c: usize, r: usize| self.lattice[r * self.columns + c];
+    let top = value(x0, y0) + (value(x0 + 1, y0) - value(x0, y0)) * tx;
+    let bottom = value(x0, y0 + 1) + (value(x0 + 1, y0 + 1) - value(x0, y0 + 1)) * tx;
+    top + (bottom - top) * ty
+  }
+}
+
+/// A map built from a seed, ready to be written out as TMX.
+pub struct GeneratedMap {
+  pub width: usize,
+  pub height: usize,
+  ground: Vec<Ground>,
+  /// Tiles covered by houses and trees.
+  occupied: Vec<bool>,
+  /// What goes on which TMX tile, in the order it was placed.
+  placements: Vec<(Placeable, usize, usize)>,
+}
+
+impl GeneratedMap {
+  fn idx(&self, col: usize, row: usize) -> usize {
+    row * self.width + col
+  }
+
+  fn ground(&self, col: usize, row: usize) -> Ground {
+    self.ground[self.idx(col, row)]
+  }
+
+  fn set_ground(&mut self, col: usize, row: usize, ground: Ground) {
+    let idx = self.idx(col, row);
+    self.ground[idx] = ground;
+  }
+
+  /// Same border the collision grid blocks, counted in TMX rows.
+  fn is_edge(&self, col: usize, row: usize) -> bool {
+    col == 0 || col >= self.width - 2 || row <= 1 || row == self.height - 1
+  }
+
+  fn is_walkable(&self, col: usize, row: usize) -> bool {
+    !self.is_edge(col, row) && self.ground(col, row) != Ground::Water && !self.occupied[self.idx(col, row)]
+  }
+
+  /// Free grass or dirt that a house or tree can be put on.
+  fn is_buildable(&self, col: usize, row: usize) -> bool {
+    self.is_walkable(col, row) && self.ground(col, row) != Ground::Road
+  }
+
+  fn near_road(&self, col: usize, row: usize, distance: usize) -> bool {
+    let (cols, rows) = (col.saturating_sub(distance)..(col + distance + 1).min(self.width), row.saturating_sub(distance)..(row + distance + 1).min(self.height));
+    rows.flat_map(|r| cols.clone().map(move |c| (c, r))).any(|(c, r)| self.ground(c, r) == Ground::Road)
+  }
+
+  fn paint_ground(&mut self, rng: &mut GameRng) {
+    let elevation = ValueNoise::new(self.width, self.height, 16, rng);
+    let detail = ValueNoise::new(self.width, self.height, 5, rng);
+    let moisture = ValueNoise::new(self.width, self.height, 12, rng);
+    for row in 0..self.height {
+      for col in 0..self.width {
+        let height = elevation.at(col, row) * 0.75 + detail.at(col, row) * 0.25;
+        let ground = if height < 0.3 {
+          Ground::Water
+        } else if moisture.at(col, row) < 0.3 {
+          Ground::Dirt
+        } else if rng.get_rand_from_range(0, 25) == 0 {
+          Ground::Flowers
+        } else {
+          Ground::Grass
+        };
+        self.set_ground(col, row, ground);
+      }
+    }
+  }
+
+  /// Two wide roads crossing the whole map, gently wandering, plus a shorter branch. Roads bridge water.
+  fn lay_roads(&mut self, rng: &mut GameRng) -> (usize, usize) {
+    let (width, height) = (self.width, self.height);
+    let cross_col = rng.get_rand_from_range(width / 3, width * 2 / 3);
+    let cross_row = rng.get_rand_from_range(height / 3, height * 2 / 3);
+
+    let rows = wander(width, cross_col, cross_row, 3, height - 4, rng);
+    for (col, &row) in rows.iter().enumerate() {
+      self.set_ground(col, row, Ground::Road);
+      self.set_ground(col, row + 1, Ground::Road);
+    }
+    let cols = wander(height, cross_row, cross_col, 2, width - 4, rng);
+    for (row, &col) in cols.iter().enumerate() {
+      self.set_ground(col, row, Ground::Road);
+      self.set_ground(col + 1, row, Ground::Road);
+    }
+
+    // A side road on the far side of the map, joined to the nearest main road by a short connector.
+    let branch_row = if cross_row > height / 2 { rng.get_rand_from_range(4, height / 3) } else { rng.get_rand_from_range(height * 2 / 3, height - 4) };
+    let (from, to) = if rng.get_random_bool() { (0, cross_col) } else { (cross_col, width) };
+    for col in from..to {
+      self.set_ground(col, branch_row, Ground::Road);
+      self.set_ground(col, branch_row + 1, Ground::Road);
+    }
+    let col = (from + to) / 2;
+    let connector = if branch_row < rows[col] { branch_row + 2..rows[col] } else { rows[col] + 2..branch_row };
+    for row in connector {
+      self.set_ground(col, row, Ground::Road);
+      self.set_ground(col + 1, row, Ground::Road);
+    }
+
+    // The player starts on the crossing with dry land around it.
+    for row in cross_row.saturating_sub(4)..(cross_row + 6).min(height) {
+      for col in cross_col.saturating_sub(4)..(cross_col + 6).min(width) {
+        if self.ground(col, row) == Ground::Water {
+          self.set_ground(col, row, Ground::Grass);
+        }
+      }
+    }
+    (cross_col, cross_row)
+  }
+
+  fn place_houses(&mut self, spawn: (usize, usize), rng: &mut GameRng) {
+    let wanted = self.width * self.height / 1024 + 1;
+    let mut placed = 0;
+    for _ in 0..wanted * 40 {
+      if placed == wanted {
+        break;
+      }
+      let (col, row) = (rng.get_rand_from_range(2, self.width - 2), rng.get_rand_from_range(3, self.height - 1));
+      let footprint = [(col - 1, row - 1), (col, row - 1), (col - 1, row), (col, row)];
+      if distance(spawn, (col, row)) < 8.0
+        || !footprint.iter().all(|&(c, r)| self.is_buildable(c, r))
+        || !self.near_road(col, row, 3) {
+        continue;
+      }
+      for &(c, r) in footprint.iter() {
+        let idx = self.idx(c, r);
+        self.occupied[idx] = true;
+      }
+      self.placements.push((Placeable::House, col, row));
+      placed += 1;
+    }
+  }
+
+  /// Trees stand off the roads and block the tile they stand on.
+  fn place_trees(&mut self, spawn: (usize, usize), rng: &mut GameRng) {
+    let wanted = self.width * self.height / 256;
+    let mut placed = 0;
+    for _ in 0..wanted * 20 {
+      if placed == wanted {
+        break;
+      }
+      let (col, row) = (rng.get_rand_from_range(1, self.width - 2), rng.get_rand_from_range(4, self.height - 1));
+      if distance(spawn, (col, row)) < 5.0
+        || !self.is_buildable(col, row)
+        || self.near_road(col, row, 2) {
+        continue;
+      }
+      let idx = self.idx(col, row);
+      self.occupied[idx] = true;
+      self.placements.push((Placeable::Tree, col, row));
+      placed += 1;
+    }
+  }
+
+  /// Tiles reachable from `start` by walking between neighbouring tiles.
+  fn reachable_from(&self, start: (usize, usize)) -> Vec<bool> {
+    let mut reachable = vec![false; self.width * self.height];
+    let mut queue = VecDeque::new();
+    reachable[self.idx(start.0, start.1)] = true;
+    queue.push_back(start);
+    while let Some((col, row)) = queue.pop_front() {
+      let neighbours = [(col + 1, row), (col.wrapping_sub(1), row), (col, row + 1), (col, row.wrapping_sub(1))];
+      for &(c, r) in neighbours.iter() {
+        if c < self.width && r < self.height && self.is_walkable(c, r) && !reachable[self.idx(c, r)] {
+          let idx = self.idx(c, r);
+          reachable[idx] = true;
+          queue.push_back((c, r));
+        }
+      }
+    }
+    reachable
+  }
+
+  /// Reachable tile closest to `target`.
+  fn nearest_reachable(&self, reachable: &[bool], target: (f32, f32)) -> (usize, usize) {
+    (0..self.height)
+      .flat_map(|row| (0..self.width).map(move |col| (col, row)))
+      .filter(|&(col, row)| reachable[self.idx(col, row)])
+      .min_by(|a, b| distance_to(*a, target).partial_cmp(&distance_to(*b, target)).expect("Distance comparison failed"))
+      .expect("Player spawn is always reachable")
+  }
+
+  fn place_markers(&mut self, spawn: (usize, usize), rng: &mut GameRng) {
+    let reachable = self.reachable_from(spawn);
+    self.placements.push((Placeable::PlayerSpawn, spawn.0, spawn.1));
+
+    let radius = self.width.min(self.height) as f32 * 0.4;
+    for i in 0..ZOMBIE_SPAWNS {
+      let angle = i as f32 / ZOMBIE_SPAWNS as f32 * std::f32::consts::PI * 2.0;
+      let target = (spawn.0 as f32 + angle.cos() * radius, spawn.1 as f32 + angle.sin() * radius);
+      let (col, row) = self.nearest_reachable(&reachable, target);
+      self.placements.push((Placeable::ZombieSpawn, col, row));
+    }
+
+    for _ in 0..4 {
+      let angle = rng.get_rand_from_range(0, 360) as f32;
+      let radius = rng.get_rand_from_range(6, (self.width.min(self.height) / 3).max(7)) as f32;
+      let target = (spawn.0 as f32 + angle.to_radians().cos() * radius, spawn.1 as f32 + angle.to_radians().sin() * radius);
+      let (col, row) = self.nearest_reachable(&reachable, target);
+      self.placements.push((Placeable::Ammo, col, row));
+    }
+
+    for _ in 0..3 {
+      let (col, row) = (rng.get_rand_from_range(2, self.width - 2), rng.get_rand_from_range(2, self.height - 1));
+      if self.is_buildable(col, row) {
+        self.placements.push((Placeable::Hill, col, row));
+      }
+    }
+  }
+
+  /// The map as an editable document, `tileset_image` is written as the tileset image source.
+  pub fn to_document(&self, tileset_image: &str) -> MapDocument {
+    let tileset = DocumentTileset {
+      first_gid: 1,
+      name: "terrain".to_string(),
+      tile_width: 32,
+      tile_height: 32,
+      margin: 0,
+      spacing: 0,
+      image_source: tileset_image.to_string(),
+      image_width: 1024,
+      image_height: 1024,
+      tile_properties: vec![(WATER - 1, vec![("collides".to_string(), PropertyValue::BoolValue(true))])],
+    };
+    let layer = DocumentLayer {
+      name: "Ground".to_string(),
+      opacity: 1.0,
+      visible: true,
+      tiles: self.ground.iter().map(|ground| ground.gid()).collect(),
+    };
+    let mut document = MapDocument::new(self.width, self.height, vec![tileset], vec![layer]);
+    for &(placeable, col, row) in self.placements.iter() {
+      document.place(placeable, col, row);
+    }
+    document
+  }
+
+  pub fn to_tmx(&self, tileset_image: &str) -> String {
+    self.to_document(tileset_image).to_tmx()
+  }
+
+  /// Writes the map as TMX, pointing the tileset at the game's tile sheet from wherever the file ends up.
+  pub fn export(&self, path: &str) -> Result<(), GameError> {
+    let dir = Path::new(path).parent().unwrap_or_else(|| Path::new(""));
+    let is_plain_relative = dir.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    let tileset_image = if is_plain_relative {
+      let depth = dir.components().filter(|c| *c != Component::CurDir).count();
+      format!("{}{}", "../".repeat(depth), TERRAIN_TILESET_PATH)
+    } else {
+      fs::canonicalize(TERRAIN_TILESET_PATH)
+        .map_err(|e| GameError::io(TERRAIN_TILESET_PATH, e))?
+        .to_string_lossy()
+        .into_owned()
+    };
+    self.to_document(&tileset_image).save(path)
+  }
+}
+
+/// Offsets of a road along `length` tiles that goes through `start` at `crossing`
+/// and drifts by a tile every so often, staying between `min` and `max`.
+fn wander(length: usize, crossing: usize, start: usize, min: usize, max: usize, rng: &mut GameRng) -> Vec<usize> {
+  let mut offsets = vec![start; length];
+  for direction in &[-1i32, 1] {
+    let (mut idx, mut offset) = (crossing as i32, start as i32);
+    while idx >= 0 && idx < length as i32 {
+      if (idx - crossing as i32).abs() > 6 && idx % 12 == 0 {
+        offset = (offset + rng.get_rand_from_range(-1, 2)).max(min as i32).min(max as i32);
+      }
+      offsets[idx as usize] = offset as usize;
+      idx += direction;
+    }
+  }
+  offsets
+}
+
+fn distance(a: (usize, usize), b: (usize, usize)) -> f32 {
+  distance_to(a, (b.0 as f32, b.1 as f32))
+}
+
+fn distance_to(a: (usize, usize), b: (f32, f32)) -> f32 {
+  ((a.0 as f32 - b.0).powi(2) + (a.1 as f32 - b.1).powi(2)).sqrt()
+}
+
+/// Builds a map with grass, dirt and water regions, a road network, houses, trees and spawn points.
+/// The same seed and size always give the same map.
+pub fn generate(seed: u64, width: usize, height: usize) -> GeneratedMap {
+  let mut rng = GameRng::new(seed);
+  let mut map = GeneratedMap {
+    width,
+    height,
+    ground: vec![Ground::Grass; width * height],
+    occupied: vec![false; width * height],
+    placements: vec![],
+  };
+  map.paint_ground(&mut rng);
+  let spawn = map.lay_roads(&mut rng);
+  map.place_houses(spawn, &mut rng);
+  map.place_trees(spawn, &mut rng);
+  map.place_markers(spawn, &mut rng);
+  map
+}
+
+pub fn map_name(seed: u64, width: usize, height: usize) -> String {
+  format!("{}{}:{}x{}", GENERATED_MAP_PREFIX, seed, width, height)
+}
+
+/// Seed and size of a generated map name, `None` if the name is not one.
+pub fn parse_map_name(name: &str) -> Option<Result<(u64, usize, usize), GameError>> {
+  if !name.starts_with(GENERATED_MAP_PREFIX) {
+    return None;
+  }
+  let fields = name[GENERATED_MAP_PREFIX.len()..]
+    .split(&[':', 'x'][..])
+    .map(|field| field.parse::<u64>().ok())
+    .collect::<Option<Vec<_>>>();
+  Some(match fields.as_deref() {
+    Some(&[seed, width, height]) => check_size(name, width as usize, height as usize).map(|_| (seed, width as usize, height as usize)),
+    _ => Err(GameError::parse(name, format!("expected {}<seed>:<width>x<height>", GENERATED_MAP_PREFIX))),
+  })
+}
+
+pub fn check_size(name: &str, width: usize, height: usize) -> Result<(), GameError> {
+  if width < MIN_GENERATED_SIZE || height < MIN_GENERATED_SIZE || width > MAX_MAP_SIZE || height > MAX_MAP_SIZE {
+    return Err(GameError::parse(name, format!("generated maps are between {}x{} and {}x{} tiles", MIN_GENERATED_SIZE, MIN_GENERATED_SIZE, MAX_MAP_SIZE, MAX_MAP_SIZE)));
+  }
+  Ok(())
+}
@@ -0,0 +1,48 @@
+#[test]
+fn generated_map_is_deterministic_test() {
+  use crate::game::constants::TERRAIN_TILESET_PATH;
+  use crate::terrain::generator::generate;
+
+  let map = generate(42, 128, 128).to_tmx(TERRAIN_TILESET_PATH);
+  assert_eq!(map, generate(42, 128, 128).to_tmx(TERRAIN_TILESET_PATH));
+  assert_ne!(map, generate(43, 128, 128).to_tmx(TERRAIN_TILESET_PATH));
+}
+
+#[test]
+fn generated_map_is_playable_test() {
+  use crate::data::load_map;
+  use crate::graphics::coords_to_tile;
+  use crate::terrain::{collision_grid::CollisionGrid, generator::map_name, map_objects::MapObjects, path_finding::FlowField, tile_map::Terrain};
+
+  for &(seed, width, height) in [(1, 128, 128), (7, 128, 128), (42, 64, 48)].iter() {
+    let name = map_name(seed, width, height);
+    let map = load_map(&name).unwrap();
+    let objects = MapObjects::from_map(&map, &name).unwrap();
+    let terrain = Terrain::from_map(&map, &name).unwrap();
+    assert_eq!((terrain.width, terrain.height), (width, height));
+    assert!(!objects.houses.is_empty());
+    assert!(!objects.trees.is_empty());
+    assert_eq!(objects.ammo.len(), 4);
+
+    let grid = CollisionGrid::from_map(&map, &objects.blocked_tiles);
+    let player = coords_to_tile(objects.player_spawn);
+    assert!(!grid.is_blocked(player));
+    let mut flow_field = FlowField::default();
+    flow_field.update(player, &grid);
+    for spawn in objects.zombie_spawns.iter().chain(objects.ammo.iter()) {
+      assert!(flow_field.distance(coords_to_tile(*spawn)).is_some(), "{} spawn {:?} is unreachable", name, spawn);
+    }
+  }
+}
+
+#[test]
+fn generated_map_name_test() {
+  use crate::terrain::generator::{map_name, parse_map_name};
+
+  assert_eq!(map_name(42, 128, 96), "generated:42:128x96");
+  assert_eq!(parse_map_name("generated:42:128x96").unwrap().unwrap(), (42, 128, 96));
+  assert!(parse_map_name("assets/maps/tilemap.tmx").is_none());
+  assert!(parse_map_name("generated:42:16x16").unwrap().is_err());
+  assert!(parse_map_name("generated:42").unwrap().is_err());
+  assert!(parse_map_name("generated:a:128x128").unwrap().is_err());
+}
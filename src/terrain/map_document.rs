@@ -0,0 +1,341 @@
+use std::fs;
+
+use tiled::{Map, PropertyValue};
+
+use crate::game::error::GameError;
+use crate::terrain::map_objects::object_size;
+
+const OBJECTS_GROUP: &str = "Objects";
+const SPAWNS_GROUP: &str = "Spawns";
+
+/// Things that can be put on a map tile, each written the way the hand made maps place them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Placeable {
+  House,
+  Tree,
+  Hill,
+  Ammo,
+  PlayerSpawn,
+  ZombieSpawn,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DocumentTileset {
+  pub first_gid: u32,
+  pub name: String,
+  pub tile_width: u32,
+  pub tile_height: u32,
+  pub margin: u32,
+  pub spacing: u32,
+  pub image_source: String,
+  pub image_width: u32,
+  pub image_height: u32,
+  /// Tile id and its properties, for the tiles that have any.
+  pub tile_properties: Vec<(u32, Vec<(String, PropertyValue)>)>,
+}
+
+impl DocumentTileset {
+  pub fn columns(&self) -> u32 {
+    (self.image_width + self.spacing).saturating_sub(self.margin * 2) / (self.tile_width + self.spacing).max(1)
+  }
+
+  pub fn tile_count(&self) -> u32 {
+    let rows = (self.image_height + self.spacing).saturating_sub(self.margin * 2) / (self.tile_height + self.spacing).max(1);
+    self.columns() * rows
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DocumentLayer {
+  pub name: String,
+  pub opacity: f32,
+  pub visible: bool,
+  /// Global tile ids row by row from the top, like the TMX stores them.
+  pub tiles: Vec<u32>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DocumentObject {
+  pub name: String,
+  pub obj_type: String,
+  pub x: f32,
+  pub y: f32,
+  pub width: f32,
+  pub height: f32,
+  pub properties: Vec<(String, PropertyValue)>,
+}
+
+impl DocumentObject {
+  fn new(obj_type: &str, x: f32, y: f32, width: f32, height: f32) -> DocumentObject {
+    DocumentObject { name: String::new(), obj_type: obj_type.to_string(), x, y, width, height, properties: vec![] }
+  }
+
+  fn marker(name: &str, value: &str, x: f32, y: f32) -> DocumentObject {
+    let mut object = DocumentObject::new("", x, y, 0.0, 0.0);
+    object.properties.push((name.to_string(), PropertyValue::StringValue(value.to_string())));
+    object
+  }
+
+  fn has_property(&self, name: &str, value: &str) -> bool {
+    self.properties.iter().any(|(n, v)| n == name && *v == PropertyValue::StringValue(value.to_string()))
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DocumentObjectGroup {
+  pub name: String,
+  pub objects: Vec<DocumentObject>,
+}
+
+/// Everything of a map the game reads, in TMX terms so it can be edited and written back out.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MapDocument {
+  pub width: usize,
+  pub height: usize,
+  pub tile_width: u32,
+  pub tile_height: u32,
+  pub tilesets: Vec<DocumentTileset>,
+  pub layers: Vec<DocumentLayer>,
+  pub object_groups: Vec<DocumentObjectGroup>,
+}
+
+fn sorted_properties(properties: &tiled::Properties) -> Vec<(String, PropertyValue)> {
+  let mut properties = properties.iter().map(|(name, value)| (name.clone(), value.clone())).collect::<Vec<_>>();
+  properties.sort_by(|a, b| a.0.cmp(&b.0));
+  properties
+}
+
+fn escape(text: &str) -> String {
+  text.replace('&', "&amp;").replace('"', "&quot;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn properties_tmx(properties: &[(String, PropertyValue)], indent: &str) -> String {
+  if properties.is_empty() {
+    return String::new();
+  }
+  let mut tmx = format!("{}<properties>\n", indent);
+  for (name, value) in properties {
+    let (value_type, value) = match value {
+      PropertyValue::BoolValue(v) => (" type=\"bool\"", v.to_string()),
+      PropertyValue::FloatValue(v) => (" type=\"float\"", v.to_string()),
+      PropertyValue::IntValue(v) => (" type=\"int\"", v.to_string()),
+      PropertyValue::ColorValue(v) => (" type=\"color\"", format!("#{:08x}", v)),
+      PropertyValue::StringValue(v) => ("", escape(v)),
+    };
+    tmx.push_str(&format!("{} <property name=\"{}\"{} value=\"{}\"/>\n", indent, escape(name), value_type, value));
+  }
+  tmx.push_str(&format!("{}</properties>\n", indent));
+  tmx
+}
+
+impl MapDocument {
+  pub fn new(width: usize, height: usize, tilesets: Vec<DocumentTileset>, layers: Vec<DocumentLayer>) -> MapDocument {
+    MapDocument { width, height, tile_width: 32, tile_height: 32, tilesets, layers, object_groups: vec![] }
+  }
+
+  /// Copies the tilesets, tile layers and object groups of a parsed map, image layers are left out.
+  pub fn from_map(map: &Map, path: &str) -> Result<MapDocument, GameError> {
+    let tilesets = map.tilesets.iter()
+      .map(|tileset| {
+        let image = tileset.images.first()
+          .ok_or_else(|| GameError::parse(path, format!("tileset \"{}\" has no image", tileset.name)))?;
+        let mut tile_properties = tileset.tiles.iter()
+          .filter(|tile| !tile.properties.is_empty())
+          .map(|tile| (tile.id, sorted_properties(&tile.properties)))
+          .collect::<Vec<_>>();
+        tile_properties.sort_by_key(|(id, _)| *id);
+        Ok(DocumentTileset {
+          first_gid: tileset.first_gid,
+          name: tileset.name.clone(),
+          tile_width: tileset.tile_width,
+          tile_height: tileset.tile_height,
+          margin: tileset.margin,
+          spacing: tileset.spacing,
+          image_source: image.source.clone(),
+          image_width: image.width.max(0) as u32,
+          image_height: image.height.max(0) as u32,
+          tile_properties,
+        })
+      })
+      .collect::<Result<Vec<_>, GameError>>()?;
+
+    let (width, height) = (map.width as usize, map.height as usize);
+    let layers = map.layers.iter()
+      .map(|layer| {
+        let tiles = layer.tiles.iter().flat_map(|row| row.iter().cloned()).collect::<Vec<_>>();
+        if tiles.len() != width * height {
+          return Err(GameError::parse(path, format!("layer \"{}\" is not {}x{} tiles", layer.name, width, height)));
+        }
+        Ok(DocumentLayer { name: layer.name.clone(), opacity: layer.opacity, visible: layer.visible, tiles })
+      })
+      .collect::<Result<Vec<_>, GameError>>()?;
+
+    let object_groups = map.object_groups.iter()
+      .map(|group| DocumentObjectGroup {
+        name: group.name.clone(),
+        objects: group.objects.iter()
+          .map(|object| {
+            let (width, height) = object_size(object);
+            DocumentObject {
+              name: object.name.clone(),
+              obj_type: object.obj_type.clone(),
+              x: object.x,
+              y: object.y,
+              width,
+              height,
+              properties: sorted_properties(&object.properties),
+            }
+          })
+          .collect(),
+      })
+      .collect();
+
+    Ok(MapDocument {
+      width,
+      height,
+      tile_width: map.tile_width,
+      tile_height: map.tile_height,
+      tilesets,
+      layers,
+      object_groups,
+    })
+  }
+
+  /// Global tile id at TMX column and row of a layer.
+  pub fn tile(&self, layer: usize, col: usize, row: usize) -> u32 {
+    self.layers[layer].tiles[row * self.width + col]
+  }
+
+  pub fn set_tile(&mut self, layer: usize, col: usize, row: usize, gid: u32) {
+    let width = self.width;
+    self.layers[layer].tiles[row * width + col] = gid;
+  }
+
+  fn group(&mut self, name: &str) -> &mut Vec<DocumentObject> {
+    let idx = match self.object_groups.iter().position(|group| group.name == name) {
+      Some(idx) => idx,
+      None => {
+        self.object_groups.push(DocumentObjectGroup { name: name.to_string(), objects: vec![] });
+        self.object_groups.len() - 1
+      }
+    };
+    &mut self.object_groups[idx].objects
+  }
+
+  /// Puts `placeable` on TMX tile (`col`, `row`).
+  /// There can only be one player spawn so placing it moves the existing one.
+  pub fn place(&mut self, placeable: Placeable, col: usize, row: usize) {
+    // Isometric object coordinates count tile heights along both axes.
+    let size = self.tile_height as f32;
+    let (x, y) = (col as f32 * size, row as f32 * size);
+    let (center_x, center_y) = (x + size / 2.0, y + size / 2.0);
+    match placeable {
+      Placeable::House => self.group(OBJECTS_GROUP).push(DocumentObject::new("house", x, y, 0.0, 0.0)),
+      Placeable::Tree => self.group(OBJECTS_GROUP).push(DocumentObject::new("tree", x, y, 0.0, 0.0)),
+      Placeable::Hill => self.group(OBJECTS_GROUP).push(DocumentObject::new("hill", x, y, 0.0, 0.0)),
+      Placeable::Ammo => self.group(OBJECTS_GROUP).push(DocumentObject::marker("pickup", "ammo", center_x, center_y)),
+      Placeable::PlayerSpawn => {
+        for group in self.object_groups.iter_mut() {
+          group.objects.retain(|object| !object.has_property("spawn", "player"));
+        }
+        self.group(SPAWNS_GROUP).push(DocumentObject::marker("spawn", "player", center_x, center_y));
+      }
+      Placeable::ZombieSpawn => self.group(SPAWNS_GROUP).push(DocumentObject::marker("spawn", "zombie", center_x, center_y)),
+    }
+  }
+
+  /// Removes every house, tree, hill, pickup and spawn point on TMX tile (`col`, `row`).
+  /// Returns how many objects were removed.
+  pub fn remove_at(&mut self, col: usize, row: usize) -> usize {
+    let size = self.tile_height as f32;
+    let is_removed = |object: &DocumentObject| {
+      let on_tile = (object.x / size).floor() as usize == col && (object.y / size).floor() as usize == row;
+      let is_placed = match object.obj_type.as_str() {
+        "house" | "tree" | "hill" => true,
+        "" => !object.properties.is_empty(),
+        _ => false,
+      };
+      on_tile && is_placed
+    };
+
+    let removed = self.object_groups.iter()
+      .flat_map(|group| group.objects.iter())
+      .filter(|object| is_removed(object))
+      .count();
+    for group in self.object_groups.iter_mut() {
+      group.objects.retain(|object| !is_removed(object));
+    }
+    removed
+  }
+
+  pub fn to_tmx(&self) -> String {
+    let object_count = self.object_groups.iter().map(|group| group.objects.len()).sum::<usize>();
+    let mut tmx = String::new();
+    tmx.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    tmx.push_str(&format!("<map version=\"1.2\" tiledversion=\"1.2.0\" orientation=\"isometric\" renderorder=\"left-down\" width=\"{}\" height=\"{}\" tilewidth=\"{}\" tileheight=\"{}\" infinite=\"0\" nextlayerid=\"{}\" nextobjectid=\"{}\">\n",
+                          self.width, self.height, self.tile_width, self.tile_height, self.layers.len() + self.object_groups.len() + 1, object_count + 1));
+
+    for tileset in self.tilesets.iter() {
+      let spacing = if tileset.spacing > 0 { format!(" spacing=\"{}\"", tileset.spacing) } else { String::new() };
+      let margin = if tileset.margin > 0 { format!(" margin=\"{}\"", tileset.margin) } else { String::new() };
+      tmx.push_str(&format!(" <tileset firstgid=\"{}\" name=\"{}\" tilewidth=\"{}\" tileheight=\"{}\"{}{} tilecount=\"{}\" columns=\"{}\">\n",
+                            tileset.first_gid, escape(&tileset.name), tileset.tile_width, tileset.tile_height, spacing, margin, tileset.tile_count(), tileset.columns()));
+      tmx.push_str(&format!("  <image source=\"{}\" width=\"{}\" height=\"{}\"/>\n", escape(&tileset.image_source), tileset.image_width, tileset.image_height));
+      for (id, properties) in tileset.tile_properties.iter() {
+        tmx.push_str(&format!("  <tile id=\"{}\">\n{}  </tile>\n", id, properties_tmx(properties, "   ")));
+      }
+      tmx.push_str(" </tileset>\n");
+    }
+
+    let mut id = 1;
+    for layer in self.layers.iter() {
+      let visible = if layer.visible { "" } else { " visible=\"0\"" };
+      let opacity = if layer.opacity < 1.0 { format!(" opacity=\"{}\"", layer.opacity) } else { String::new() };
+      tmx.push_str(&format!(" <layer id=\"{}\" name=\"{}\" width=\"{}\" height=\"{}\"{}{}>\n  <data encoding=\"csv\">\n",
+                            id, escape(&layer.name), self.width, self.height, visible, opacity));
+      let rows = layer.tiles.chunks(self.width)
+        .map(|row| row.iter().map(u32::to_string).collect::<Vec<_>>().join(","))
+        .collect::<Vec<_>>();
+      tmx.push_str(&rows.join(",\n"));
+      tmx.push_str("\n</data>\n </layer>\n");
+      id += 1;
+    }
+
+    let mut object_id = 1;
+    for group in self.object_groups.iter() {
+      tmx.push_str(&format!(" <objectgroup id=\"{}\" name=\"{}\">\n", id, escape(&group.name)));
+      for object in group.objects.iter() {
+        let mut attributes = format!("id=\"{}\"", object_id);
+        if !object.name.is_empty() {
+          attributes.push_str(&format!(" name=\"{}\"", escape(&object.name)));
+        }
+        if !object.obj_type.is_empty() {
+          attributes.push_str(&format!(" type=\"{}\"", escape(&object.obj_type)));
+        }
+        attributes.push_str(&format!(" x=\"{}\" y=\"{}\"", object.x, object.y));
+        if object.width > 0.0 || object.height > 0.0 {
+          attributes.push_str(&format!(" width=\"{}\" height=\"{}\"", object.width, object.height));
+        }
+        if object.properties.is_empty() {
+          tmx.push_str(&format!("  <object {}/>\n", attributes));
+        } else {
+          tmx.push_str(&format!("  <object {}>\n{}  </object>\n", attributes, properties_tmx(&object.properties, "   ")));
+        }
+        object_id += 1;
+      }
+      tmx.push_str(" </objectgroup>\n");
+      id += 1;
+    }
+    tmx.push_str("</map>\n");
+    tmx
+  }
+
+  /// Parses the document back the way a map file is read.
+  pub fn to_map(&self, name: &str) -> Result<Map, GameError> {
+    tiled::parse(self.to_tmx().as_bytes()).map_err(|e| GameError::parse(name, format!("{:?}", e)))
+  }
+
+  pub fn save(&self, path: &str) -> Result<(), GameError> {
+    fs::write(path, self.to_tmx()).map_err(|e| GameError::write(path, e))
+  }
+}
@@ -0,0 +1,51 @@
+#[test]
+fn map_file_round_trips_test() {
+  use crate::data::load_map_file;
+  use crate::game::constants::MAP_FILE_PATH;
+  use crate::terrain::{map_document::MapDocument, map_objects::MapObjects};
+
+  let map = load_map_file(MAP_FILE_PATH).unwrap();
+  let document = MapDocument::from_map(&map, MAP_FILE_PATH).unwrap();
+  let written = document.to_map(MAP_FILE_PATH).unwrap();
+  assert_eq!(MapDocument::from_map(&written, MAP_FILE_PATH).unwrap(), document);
+  assert_eq!(written.layers[0].tiles, map.layers[0].tiles);
+
+  let (objects, written_objects) = (MapObjects::from_map(&map, MAP_FILE_PATH).unwrap(), MapObjects::from_map(&written, MAP_FILE_PATH).unwrap());
+  assert_eq!(written_objects.houses, objects.houses);
+  assert_eq!(written_objects.trees, objects.trees);
+  assert_eq!(written_objects.blocked_tiles, objects.blocked_tiles);
+  assert_eq!(written_objects.exit_tiles, objects.exit_tiles);
+  assert_eq!(written_objects.zombie_spawns, objects.zombie_spawns);
+}
+
+#[test]
+fn placed_objects_can_be_removed_test() {
+  use crate::data::load_map_file;
+  use crate::game::constants::MAP_FILE_PATH;
+  use crate::terrain::{map_document::{MapDocument, Placeable}, map_objects::MapObjects};
+
+  let map = load_map_file(MAP_FILE_PATH).unwrap();
+  let original = MapDocument::from_map(&map, MAP_FILE_PATH).unwrap();
+  let mut document = original.clone();
+  document.place(Placeable::House, 40, 40);
+  document.place(Placeable::Ammo, 40, 40);
+  document.place(Placeable::ZombieSpawn, 41, 40);
+  let objects = MapObjects::from_map(&document.to_map(MAP_FILE_PATH).unwrap(), MAP_FILE_PATH).unwrap();
+  assert_eq!(objects.houses.len(), 3);
+  assert!(objects.blocked_tiles.contains(&[39, 127 - 39]));
+  assert_eq!(document.remove_at(40, 40), 2);
+  assert_eq!(document.remove_at(41, 40), 1);
+  assert_eq!(document, original);
+
+  // Placing the player spawn moves it instead of adding a second one.
+  document.place(Placeable::PlayerSpawn, 40, 40);
+  let objects = MapObjects::from_map(&document.to_map(MAP_FILE_PATH).unwrap(), MAP_FILE_PATH).unwrap();
+  assert_ne!(objects.player_spawn, MapObjects::from_map(&map, MAP_FILE_PATH).unwrap().player_spawn);
+
+  // Removing a tree takes its blocked tile with it.
+  let mut document = original.clone();
+  assert_eq!(document.remove_at(1776 / 32, 1872 / 32), 1);
+  let objects = MapObjects::from_map(&document.to_map(MAP_FILE_PATH).unwrap(), MAP_FILE_PATH).unwrap();
+  assert!(!objects.blocked_tiles.contains(&[1776 / 32, 127 - 1872 / 32]));
+  assert_eq!(document.remove_at(0, 0), 0);
+}
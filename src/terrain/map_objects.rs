@@ -0,0 +1,132 @@
+use cgmath::Point2;
+use tiled::{Map, Object, ObjectShape, PropertyValue};
+
+use crate::game::error::GameError;
+use crate::graphics::tile_to_coords;
+use crate::shaders::Position;
+
+/// Everything placed on the map's object layers, converted to world positions and game tiles.
+#[derive(Clone)]
+pub struct MapObjects {
+  pub houses: Vec<Position>,
+  pub trees: Vec<Position>,
+  pub hills: Vec<Position>,
+  pub ammo: Vec<Position>,
+  pub blocked_tiles: Vec<[i32; 2]>,
+  /// Reaching any of these tiles moves on to the next map of the campaign.
+  pub exit_tiles: Vec<[i32; 2]>,
+  pub player_spawn: Position,
+  pub zombie_spawns: Vec<Position>,
+}
+
+/// Width and height of a rectangle or an ellipse object, zero for points and polygons.
+pub fn object_size(object: &Object) -> (f32, f32) {
+  match object.shape {
+    ObjectShape::Rect { width, height } | ObjectShape::Ellipse { width, height } => (width, height),
+    _ => (0.0, 0.0),
+  }
+}
+
+/// Game tiles of the TMX columns and rows in the given ranges, end exclusive.
+fn tiles_between(map: &Map, (first_col, last_col): (i32, i32), (first_row, last_row): (i32, i32)) -> Vec<[i32; 2]> {
+  let mut tiles = vec![];
+  for row in first_row..last_row {
+    for col in first_col..last_col {
+      tiles.push([col, map.height as i32 - 1 - row]);
+    }
+  }
+  tiles
+}
+
+/// Game tiles covered by a rectangle object, at least the tile its corner is on.
+fn covered_tiles(object: &Object, map: &Map) -> Vec<[i32; 2]> {
+  let tile_size = map.tile_height as f32;
+  let (first_col, first_row) = ((object.x / tile_size) as i32, (object.y / tile_size) as i32);
+  let (width, height) = object_size(object);
+  let (last_col, last_row) = (((object.x + width) / tile_size).ceil() as i32, ((object.y + height) / tile_size).ceil() as i32);
+  tiles_between(map, (first_col, last_col.max(first_col + 1)), (first_row, last_row.max(first_row + 1)))
+}
+
+/// Game tiles a house or a tree stands on: the two by two tiles around the corner a house is placed at,
+/// and the tile of a tree.
+fn footprint(object: &Object, map: &Map) -> Vec<[i32; 2]> {
+  let tile_size = map.tile_height as f32;
+  let (col, row) = ((object.x / tile_size) as i32, (object.y / tile_size) as i32);
+  match object.obj_type.as_str() {
+    "house" => tiles_between(map, (col - 1, col + 1), (row - 1, row + 1)),
+    "tree" => tiles_between(map, (col, col + 1), (row, row + 1)),
+    _ => vec![],
+  }
+}
+
+fn string_property<'a>(object: &'a Object, name: &str) -> Option<&'a str> {
+  match object.properties.get(name) {
+    Some(PropertyValue::StringValue(value)) => Some(value.as_str()),
+    _ => None,
+  }
+}
+
+impl MapObjects {
+  /// Objects are recognised by their Tiled type (`house`, `tree`, `hill`, `collision`, `exit`)
+  /// or by a `pickup=ammo` or `spawn=player|zombie` property. Houses and trees block the tiles they stand on.
+  pub fn from_map(map: &Map, path: &str) -> Result<MapObjects, GameError> {
+    let mut objects = MapObjects {
+      houses: vec![],
+      trees: vec![],
+      hills: vec![],
+      ammo: vec![],
+      blocked_tiles: vec![],
+      exit_tiles: vec![],
+      player_spawn: Position::origin(),
+      zombie_spawns: vec![],
+    };
+    let mut player_spawn = None;
+    let tile_size = map.tile_height as f32;
+    // Tiled counts isometric object coordinates in tile heights along both axes, rows from the top.
+    let to_tile = |x: f32, y: f32| Point2::new(x / tile_size, map.height as f32 - y / tile_size);
+
+    for object in map.object_groups.iter().flat_map(|group| group.objects.iter()) {
+      let invalid = |what: &str, value: &str| GameError::parse(path, format!("object {} has unknown {} \"{}\"", object.id, what, value));
+      let position = tile_to_coords(to_tile(object.x, object.y));
+
+      match object.obj_type.as_str() {
+        "house" => {
+          objects.houses.push(position);
+          objects.blocked_tiles.append(&mut footprint(object, map));
+        }
+        "tree" => {
+          objects.trees.push(position);
+          objects.blocked_tiles.append(&mut footprint(object, map));
+        }
+        "hill" => objects.hills.push(position),
+        "collision" => objects.blocked_tiles.append(&mut covered_tiles(object, map)),
+        "exit" => objects.exit_tiles.append(&mut covered_tiles(object, map)),
+        "" => (),
+        other => return Err(invalid("type", other)),
+      }
+
+      match string_property(object, "pickup") {
+        Some("ammo") => objects.ammo.push(position),
+        Some(other) => return Err(invalid("pickup", other)),
+        None => (),
+      }
+
+      match string_property(object, "spawn") {
+        Some("player") if player_spawn.is_some() => {
+          return Err(GameError::parse(path, format!("object {} is a second player spawn", object.id)));
+        }
+        Some("player") => player_spawn = Some(position),
+        Some("zombie") => objects.zombie_spawns.push(position),
+        Some(other) => return Err(invalid("spawn", other)),
+        None => (),
+      }
+    }
+
+    objects.player_spawn = player_spawn
+      .ok_or_else(|| GameError::missing_data(path, "an object with spawn=player".to_string()))?;
+    if objects.zombie_spawns.is_empty() {
+      return Err(GameError::missing_data(path, "an object with spawn=zombie".to_string()));
+    }
+    Ok(objects)
+  }
+}
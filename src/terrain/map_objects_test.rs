@@ -0,0 +1,48 @@
+#[test]
+fn map_file_objects_test() {
+  use crate::data::load_map_file;
+  use crate::game::constants::MAP_FILE_PATH;
+  use crate::graphics::set_position;
+  use crate::shaders::Position;
+  use crate::terrain::map_objects::MapObjects;
+
+  let objects = MapObjects::from_map(&load_map_file(MAP_FILE_PATH).unwrap(), MAP_FILE_PATH).unwrap();
+  let near = |a: Position, b: Position| (a.x() - b.x()).abs() < 0.1 && (a.y() - b.y()).abs() < 0.1;
+
+  assert_eq!((objects.houses.len(), objects.trees.len(), objects.hills.len(), objects.ammo.len()), (2, 5, 3, 4));
+  assert!(near(objects.houses[0], set_position(1, 17)));
+  assert!(near(objects.trees[1], set_position(8, -8)));
+  assert!(near(objects.hills[2], set_position(-14, -6)));
+  assert!(near(objects.ammo[3], set_position(1, 14)));
+  assert!(near(objects.player_spawn, Position::origin()));
+  assert_eq!(objects.zombie_spawns.len(), 8);
+  assert!(near(objects.zombie_spawns[0], Position::new(1200.0, 10.0)));
+
+  assert_eq!(objects.blocked_tiles.len(), 13);
+  // Around both houses and under the trees at (1952, 1792), (2304, 2048) and (1792, 1728).
+  for tile in [[55, 54], [56, 55], [65, 57], [66, 56], [61, 71], [72, 63], [56, 73]].iter() {
+    assert!(objects.blocked_tiles.contains(tile), "{:?} should be blocked", tile);
+  }
+}
+
+#[test]
+fn invalid_map_objects_test() {
+  use crate::terrain::map_objects::MapObjects;
+
+  let map_with = |objects: &str| {
+    let tmx = format!(r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.2" orientation="isometric" renderorder="left-down" width="4" height="4" tilewidth="32" tileheight="32">
+ <objectgroup id="1" name="Objects">{}</objectgroup>
+</map>"#, objects);
+    tiled::parse(tmx.as_bytes()).unwrap()
+  };
+  let player = r#"<object id="1" x="64" y="64"><properties><property name="spawn" value="player"/></properties></object>"#;
+  let zombie = r#"<object id="2" x="0" y="0"><properties><property name="spawn" value="zombie"/></properties></object>"#;
+
+  assert!(MapObjects::from_map(&map_with(&format!("{}{}", player, zombie)), "test").is_ok());
+  assert!(MapObjects::from_map(&map_with(zombie), "test").is_err(), "Missing player spawn");
+  assert!(MapObjects::from_map(&map_with(player), "test").is_err(), "Missing zombie spawn");
+  let windmill = r#"<object id="3" type="windmill" x="0" y="0"/>"#;
+  let error = MapObjects::from_map(&map_with(&format!("{}{}{}", player, zombie, windmill)), "test").err().unwrap().to_string();
+  assert!(error.contains("windmill"), "Unexpected error: {}", error);
+}
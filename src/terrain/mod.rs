@@ -1,20 +1,42 @@
+use std::path::PathBuf;
+
 use cgmath::Point2;
-use genmesh::{generators::{IndexedPolygon, Plane, SharedVertex}, Triangulate, Vertices};
-use specs::prelude::{Read, ReadStorage, WriteStorage};
+use gfx::format::{ChannelType, R16, R8_G8_B8_A8, Rgba8, Swizzle, Uint};
+use specs::prelude::{Read, ReadExpect, ReadStorage, WriteStorage};
 
 use crate::character::controls::CharacterInputState;
-use crate::game::constants::{ASPECT_RATIO, TILE_SIZE, TILES_PCS_H, TILES_PCS_W, VIEW_DISTANCE};
+use crate::game::config::GameConfig;
+use crate::game::constants::{ORIGIN_TILE, TILE_SIZE};
+use crate::game::error::GameError;
 use crate::gfx_app::{ColorFormat, DepthFormat};
 use crate::graphics::{camera::CameraInputState, can_move_to_tile, coords_to_tile, dimensions::{Dimensions, get_projection, get_view_matrix}};
-use crate::graphics::mesh::TexturedMesh;
-use crate::graphics::texture::{load_texture, Texture};
-use crate::shaders::{Position, Projection, tilemap_pipeline, TilemapSettings, Time, VertexData};
-
+use crate::shaders::{Position, Projection, tilemap_pipeline, TilemapSettings, Time, VertexData, ViewTranslation};
+use crate::terrain::collision_grid::CollisionGrid;
+use crate::terrain::tile_map::{MAX_MAP_SIZE, MAX_TILE_LAYERS, MAX_TILESETS, MAX_TILESHEET_SIZE, TerrainTileset};
+
+pub mod collision_grid;
+mod collision_grid_test;
+pub mod generator;
+mod generator_test;
+pub mod map_document;
+mod map_document_test;
+pub mod map_objects;
+mod map_objects_test;
 pub mod path_finding;
+mod path_finding_test;
 pub mod tile_map;
-
-fn cartesian_to_isometric(point_x: f32, point_y: f32) -> (f32, f32) {
-  ((point_x - point_y), (point_x + point_y) / (16.0 / 9.0))
+mod tile_map_test;
+
+/// Corners of the quad the map is drawn on. `a_BufPos` counts tiles, so the quad grows with the map
+/// while tile (ORIGIN_TILE, ORIGIN_TILE) stays at the world origin.
+fn terrain_vertices(width: usize, height: usize) -> [VertexData; 4] {
+  let (width, height) = (width as f32, height as f32);
+  let vertex = |x: f32, y: f32| {
+    let vertex_x = TILE_SIZE * 2.0 / 1.5 * (x + y - height);
+    let vertex_y = TILE_SIZE * 2.0 / 1.666 * (9.0 / 16.0) * (y - x + 2.0 * ORIGIN_TILE - height);
+    VertexData::new([vertex_x, vertex_y], [x, y])
+  };
+  [vertex(0.0, 0.0), vertex(width, 0.0), vertex(width, height), vertex(0.0, height)]
 }
 
 pub struct TerrainDrawable {
@@ -24,9 +46,9 @@ pub struct TerrainDrawable {
 }
 
 impl TerrainDrawable {
-  pub fn new() -> TerrainDrawable {
-    let view = get_view_matrix(VIEW_DISTANCE);
-    let projection = get_projection(view, ASPECT_RATIO);
+  pub fn new(config: &GameConfig) -> TerrainDrawable {
+    let view = get_view_matrix(config.view_distance);
+    let projection = get_projection(view, config.aspect_ratio());
     TerrainDrawable {
       projection,
       position: Position::origin(),
@@ -34,12 +56,11 @@ impl TerrainDrawable {
     }
   }
 
-  pub fn update(&mut self, world_to_clip: &Projection, ci: &mut CharacterInputState) {
+  pub fn update(&mut self, world_to_clip: &Projection, ci: &mut CharacterInputState, grid: &CollisionGrid) {
     self.projection = *world_to_clip;
-    if can_move_to_tile(ci.movement) {
+    if can_move_to_tile(ci.position, grid) {
       ci.is_colliding = false;
-      self.position = ci.movement;
-      self.tile_position = coords_to_tile(self.position);
+      self.tile_position = coords_to_tile(ci.position);
     } else {
       ci.is_colliding = true;
     }
@@ -55,91 +76,161 @@ const SHADER_FRAG: &[u8] = include_bytes!("../shaders/terrain.f.glsl");
 
 pub struct TerrainDrawSystem<R: gfx::Resources> {
   bundle: gfx::pso::bundle::Bundle<R, tilemap_pipeline::Data<R>>,
-  is_tile_map_dirty: bool,
+  tilemap: gfx::handle::Texture<R, R16>,
+  tile_sheet: gfx::handle::Texture<R, R8_G8_B8_A8>,
+  /// Images currently in the tile sheet texture, so they are only uploaded again when a map uses other tilesets.
+  tile_sheet_images: Vec<PathBuf>,
 }
 
 impl<R: gfx::Resources> TerrainDrawSystem<R> {
   pub fn new<F>(factory: &mut F,
                 rtv: gfx::handle::RenderTargetView<R, ColorFormat>,
                 dsv: gfx::handle::DepthStencilView<R, DepthFormat>)
-                -> TerrainDrawSystem<R>
+                -> Result<TerrainDrawSystem<R>, GameError>
     where F: gfx::Factory<R> {
     use gfx::traits::FactoryExt;
 
-    let plane = Plane::subdivide(TILES_PCS_W, TILES_PCS_H);
-    let vertex_data: Vec<VertexData> =
-      plane.shared_vertex_iter()
-        .map(|vertex| {
-          let tile_x = TILES_PCS_W as f32;
-          let tile_y = TILES_PCS_H as f32;
-          let (raw_x, raw_y) = cartesian_to_isometric(vertex.pos.x, vertex.pos.y);
-          let vertex_x = (TILE_SIZE * (tile_x as f32) / 1.5) * raw_x;
-          let vertex_y = (TILE_SIZE * (tile_y as f32) / 1.666) * raw_y;
-
-          let (u_pos, v_pos) = ((raw_x / 4.0 - raw_y / 2.25) + 0.5, (raw_x / 4.0 + raw_y / 2.25) + 0.5);
-          let tile_map_x = u_pos * tile_x as f32;
-          let tile_map_y = v_pos * tile_y as f32;
-
-          VertexData::new([vertex_x, vertex_y], [tile_map_x, tile_map_y])
-        })
-        .collect();
-
-    let index_data =
-      plane.indexed_polygon_iter()
-        .triangulate()
-        .vertices()
-        .map(|i| i as u16)
-        .collect::<Vec<u16>>();
-
-    let tile_sheet_bytes = &include_bytes!("../../assets/maps/terrain.png")[..];
-    let tile_texture = load_texture(factory, tile_sheet_bytes);
-
-    let mesh = TexturedMesh::new(factory, &vertex_data.as_slice(), index_data.as_slice(), Texture::new(tile_texture, None));
+    let tile_sheet_size = MAX_TILESHEET_SIZE as u16;
+    let tile_sheet_kind = gfx::texture::Kind::D2Array(tile_sheet_size, tile_sheet_size, MAX_TILESETS as u16, gfx::texture::AaMode::Single);
+    let tile_sheet = factory.create_texture::<R8_G8_B8_A8>(tile_sheet_kind,
+                                                           1,
+                                                           gfx::memory::Bind::SHADER_RESOURCE | gfx::memory::Bind::TRANSFER_DST,
+                                                           gfx::memory::Usage::Dynamic,
+                                                           Some(ChannelType::Unorm))
+      .map_err(|e| GameError::Device { context: format!("Tile sheet texture creation error: {}", e) })?;
+    let tile_sheet_view = factory.view_texture_as_shader_resource::<Rgba8>(&tile_sheet, (0, 0), Swizzle::new())
+      .map_err(|e| GameError::Device { context: format!("Tile sheet texture view error: {:?}", e) })?;
+
+    let tilemap_size = MAX_MAP_SIZE as u16;
+    let tilemap_kind = gfx::texture::Kind::D2Array(tilemap_size, tilemap_size, MAX_TILE_LAYERS as u16, gfx::texture::AaMode::Single);
+    let tilemap = factory.create_texture::<R16>(tilemap_kind,
+                                                1,
+                                                gfx::memory::Bind::SHADER_RESOURCE | gfx::memory::Bind::TRANSFER_DST,
+                                                gfx::memory::Usage::Dynamic,
+                                                Some(ChannelType::Uint))
+      .map_err(|e| GameError::Device { context: format!("Tile map texture creation error: {}", e) })?;
+    let tilemap_view = factory.view_texture_as_shader_resource::<(R16, Uint)>(&tilemap, (0, 0), Swizzle::new())
+      .map_err(|e| GameError::Device { context: format!("Tile map texture view error: {:?}", e) })?;
+    let tilemap_sampler = factory.create_sampler(gfx::texture::SamplerInfo::new(gfx::texture::FilterMethod::Scale,
+                                                                                gfx::texture::WrapMode::Clamp));
+
+    let vbuf = factory.create_buffer(4,
+                                     gfx::buffer::Role::Vertex,
+                                     gfx::memory::Usage::Dynamic,
+                                     gfx::memory::Bind::empty())
+      .map_err(|e| GameError::Device { context: format!("Terrain vertex buffer creation error: {}", e) })?;
+    let slice = gfx::Slice {
+      start: 0,
+      end: 6,
+      base_vertex: 0,
+      instances: None,
+      buffer: factory.create_index_buffer(&[0u16, 1, 2, 2, 3, 0][..]),
+    };
 
     let pso = factory.create_pipeline_simple(SHADER_VERT, SHADER_FRAG, tilemap_pipeline::new())
       .expect("Terrain shader loading error");
 
-    let terrain = tile_map::Terrain::new();
-
     let pipeline_data = tilemap_pipeline::Data {
-      vbuf: mesh.vertex_buffer,
+      vbuf,
       position_cb: factory.create_constant_buffer(1),
+      view_cb: factory.create_constant_buffer(1),
       time_passed_cb: factory.create_constant_buffer(1),
       projection_cb: factory.create_constant_buffer(1),
-      tilemap: factory.create_buffer_immutable(&terrain.tiles.as_slice(),
-                                               gfx::buffer::Role::Constant,
-                                               gfx::memory::Bind::empty()).unwrap(),
+      tilemap: (tilemap_view, tilemap_sampler),
       tilemap_cb: factory.create_constant_buffer(1),
-      tilesheet: (mesh.texture.raw, factory.create_sampler_linear()),
+      tilesheet: (tile_sheet_view, factory.create_sampler_linear()),
       out_color: rtv,
       out_depth: dsv,
     };
 
-    TerrainDrawSystem {
-      bundle: gfx::Bundle::new(mesh.slice, pso, pipeline_data),
-      is_tile_map_dirty: true,
-    }
+    Ok(TerrainDrawSystem {
+      bundle: gfx::Bundle::new(slice, pso, pipeline_data),
+      tilemap,
+      tile_sheet,
+      tile_sheet_images: vec![],
+    })
+  }
+
+  pub fn set_pipeline(&mut self, pso: gfx::PipelineState<R, tilemap_pipeline::Meta>) {
+    self.bundle.pso = pso;
+  }
+
+  /// Uploads the tileset images again on the next frame, even if the map still uses the same files.
+  pub fn reload_tile_sheets(&mut self) {
+    self.tile_sheet_images.clear();
   }
 
   pub fn draw<C>(&mut self,
                  drawable: &TerrainDrawable,
+                 terrain: &mut tile_map::Terrain,
                  time_passed: u64,
+                 view: &ViewTranslation,
                  encoder: &mut gfx::Encoder<R, C>)
     where C: gfx::CommandBuffer<R> {
     encoder.update_constant_buffer(&self.bundle.data.projection_cb, &drawable.projection);
     encoder.update_constant_buffer(&self.bundle.data.position_cb, &drawable.position);
+    encoder.update_constant_buffer(&self.bundle.data.view_cb, view);
     encoder.update_constant_buffer(&self.bundle.data.time_passed_cb, &Time::new(time_passed));
 
-    if self.is_tile_map_dirty {
+    if terrain.is_dirty {
+      let mut layer_opacity = [0.0; MAX_TILE_LAYERS];
+      for (idx, layer) in terrain.layers.iter().enumerate() {
+        let image = gfx::texture::ImageInfoCommon {
+          xoffset: 0,
+          yoffset: 0,
+          zoffset: idx as u16,
+          width: terrain.width as u16,
+          height: terrain.height as u16,
+          depth: 1,
+          format: (),
+          mipmap: 0,
+        };
+        encoder.update_texture::<R16, (R16, Uint)>(&self.tilemap, None, image, &layer.tiles)
+          .expect("Tile map update error");
+        layer_opacity[idx] = layer.opacity;
+      }
+      let images = terrain.tilesets.iter().map(|tileset| tileset.image_path.clone()).collect::<Vec<_>>();
+      if images != self.tile_sheet_images {
+        for (idx, tileset) in terrain.tilesets.iter().enumerate() {
+          self.upload_tile_sheet(idx, tileset, encoder);
+        }
+        self.tile_sheet_images = images;
+      }
+      let (mut tilesets, mut tileset_spacing) = ([[0.0; 4]; MAX_TILESETS], [[0.0; 4]; MAX_TILESETS]);
+      for (idx, tileset) in terrain.tilesets.iter().enumerate() {
+        tilesets[idx] = [tileset.first_gid as f32, tileset.columns as f32, tileset.tile_width as f32, tileset.tile_height as f32];
+        tileset_spacing[idx] = [tileset.margin as f32, tileset.spacing as f32, 0.0, 0.0];
+      }
+      encoder.update_buffer(&self.bundle.data.vbuf, &terrain_vertices(terrain.width, terrain.height), 0)
+        .expect("Terrain vertex update error");
       encoder.update_constant_buffer(&self.bundle.data.tilemap_cb, &TilemapSettings {
-        world_size: [TILES_PCS_W as f32, TILES_PCS_H as f32],
-        tilesheet_size: [32.0, 32.0],
+        tilesets,
+        tileset_spacing,
+        layer_opacity,
+        world_size: [terrain.width as f32, terrain.height as f32],
       });
-      self.is_tile_map_dirty = false
+      terrain.is_dirty = false;
     }
 
     self.bundle.encode(encoder);
   }
+
+  fn upload_tile_sheet<C>(&self, idx: usize, tileset: &TerrainTileset, encoder: &mut gfx::Encoder<R, C>)
+    where C: gfx::CommandBuffer<R> {
+    let (width, height) = tileset.image_size;
+    let image = gfx::texture::ImageInfoCommon {
+      xoffset: 0,
+      yoffset: 0,
+      zoffset: idx as u16,
+      width: width as u16,
+      height: height as u16,
+      depth: 1,
+      format: (),
+      mipmap: 0,
+    };
+    encoder.update_texture::<R8_G8_B8_A8, Rgba8>(&self.tile_sheet, None, image, &tileset.pixels)
+      .expect("Tile sheet update error");
+  }
 }
 
 pub struct PreDrawSystem;
@@ -148,14 +239,15 @@ impl<'a> specs::prelude::System<'a> for PreDrawSystem {
   type SystemData = (WriteStorage<'a, TerrainDrawable>,
                      ReadStorage<'a, CameraInputState>,
                      WriteStorage<'a, CharacterInputState>,
-                     Read<'a, Dimensions>);
+                     Read<'a, Dimensions>,
+                     ReadExpect<'a, CollisionGrid>);
 
-  fn run(&mut self, (mut terrain, camera_input, mut character_input, dim): Self::SystemData) {
+  fn run(&mut self, (mut terrain, camera_input, mut character_input, dim, grid): Self::SystemData) {
     use specs::join::Join;
 
     for (t, camera, ci) in (&mut terrain, &camera_input, &mut character_input).join() {
       let world_to_clip = dim.world_to_projection(camera);
-      t.update(&world_to_clip, ci);
+      t.update(&world_to_clip, ci, &grid);
     }
   }
 }
@@ -1,58 +1,56 @@
+use std::collections::VecDeque;
+
 use cgmath::Point2;
 use pathfinding::{directed::astar::astar, utils::absdiff};
 
-use crate::game::constants::{TILES_PCS_H, TILES_PCS_W, TERRAIN_OBJECTS};
-use crate::game::get_rand_from_range;
+use crate::game::GameRng;
 use crate::graphics::coords_to_tile;
 use crate::shaders::Position;
+use crate::terrain::collision_grid::CollisionGrid;
+
+const NEIGHBOUR_OFFSETS: [(i32, i32); 8] = [(-1, 0), (-1, -1), (0, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (1, -1)];
 
-fn neighbours<'c>(curr_pos: Point2<i32>, impassable_tiles: &[[i32; 2]], neighbour_tiles: &'c mut Vec<Point2<i32>>) -> Vec<&'c Point2<i32>> {
-  neighbour_tiles.push(Point2::new(curr_pos.x - 1, curr_pos.y));
-  neighbour_tiles.push(Point2::new(curr_pos.x - 1, curr_pos.y - 1));
-  neighbour_tiles.push(Point2::new(curr_pos.x, curr_pos.y - 1));
-  neighbour_tiles.push(Point2::new(curr_pos.x + 1, curr_pos.y));
-  neighbour_tiles.push(Point2::new(curr_pos.x + 1, curr_pos.y + 1));
-  neighbour_tiles.push(Point2::new(curr_pos.x, curr_pos.y + 1));
-  neighbour_tiles.push(Point2::new(curr_pos.x - 1, curr_pos.y + 1));
-  neighbour_tiles.push(Point2::new(curr_pos.x + 1, curr_pos.y - 1));
+fn neighbours<'c>(curr_pos: Point2<i32>, grid: &CollisionGrid, neighbour_tiles: &'c mut Vec<Point2<i32>>) -> Vec<&'c Point2<i32>> {
+  for (x, y) in NEIGHBOUR_OFFSETS.iter() {
+    neighbour_tiles.push(Point2::new(curr_pos.x + x, curr_pos.y + y));
+  }
 
   neighbour_tiles
     .iter()
-    .filter(|ref e| e.x >= 0 && e.x < TILES_PCS_W as i32 && e.y >= 0 && e.y < TILES_PCS_H as i32)
-    .filter(|ref e| !impassable_tiles.contains(&[e.x, e.y]))
+    .filter(|e| !grid.is_blocked(**e))
     .collect()
 }
 
-fn tiles(p: Point2<i32>, impassable_tiles: &[[i32; 2]]) -> Vec<(Point2<i32>, i32)> {
-  neighbours(p, &impassable_tiles, &mut vec![])
+fn tiles(p: Point2<i32>, grid: &CollisionGrid) -> Vec<(Point2<i32>, i32)> {
+  neighbours(p, grid, &mut vec![])
     .iter()
     .map(|p| (**p, 1))
     .collect()
 }
 
-fn find_next_best_endpoint<'c>(end_point: &'c Point2<i32>, impassable_tiles: &[[i32; 2]], neighbour_tiles: &'c mut Vec<Point2<i32>>) -> &'c Point2<i32> {
-  if impassable_tiles.iter().any(|e| e[0] == end_point.x && e[1] == end_point.y) {
-    neighbours(*end_point, &impassable_tiles, neighbour_tiles)[0]
+fn find_next_best_endpoint<'c>(end_point: &'c Point2<i32>, grid: &CollisionGrid, neighbour_tiles: &'c mut Vec<Point2<i32>>) -> &'c Point2<i32> {
+  if grid.is_blocked(*end_point) {
+    neighbours(*end_point, grid, neighbour_tiles)[0]
   } else {
     &end_point
   }
 }
 
-pub fn calc_route(start_point: Position, end_point: Position, impassable_tiles: &[[i32; 2]]) -> Option<(Vec<Point2<i32>>, i32)> {
+pub fn calc_route(start_point: Position, end_point: Position, grid: &CollisionGrid) -> Option<(Vec<Point2<i32>>, i32)> {
   let mut neighbour_tiles = vec![];
   let end_tile = coords_to_tile(end_point);
   let start_tile = coords_to_tile(start_point);
 
-  let end = find_next_best_endpoint(&end_tile, &impassable_tiles, &mut neighbour_tiles);
+  let end = find_next_best_endpoint(&end_tile, grid, &mut neighbour_tiles);
 
   astar(&start_tile,
-        |p: &Point2<i32>| tiles(*p, &impassable_tiles),
+        |p: &Point2<i32>| tiles(*p, grid),
         |p: &Point2<i32>| absdiff(p.x, end.x) + absdiff(p.y, end.y),
         |p: &Point2<i32>| p.x == end.x && p.y == end.y)
 }
 
-pub fn calc_next_movement(start_point: Position, end_point: Position) -> i32 {
-  let next_step: Point2<i32> = calc_route(start_point, end_point, &TERRAIN_OBJECTS.to_vec())
+pub fn calc_next_movement(start_point: Position, end_point: Position, grid: &CollisionGrid, rng: &mut GameRng) -> i32 {
+  let next_step: Point2<i32> = calc_route(start_point, end_point, grid)
     .map_or_else(|| Point2::new(0, 0),
                  |(route, ..)| {
                    if route.len() > 1 {
@@ -63,8 +61,10 @@ pub fn calc_next_movement(start_point: Position, end_point: Position) -> i32 {
                  });
 
   let start = coords_to_tile(start_point);
-  let diff: (i32, i32) = (next_step.x - start.x, next_step.y - start.y);
+  step_to_direction((next_step.x - start.x, next_step.y - start.y), rng)
+}
 
+fn step_to_direction(diff: (i32, i32), rng: &mut GameRng) -> i32 {
   match diff {
     (1, 0) => 315,
     (1, 1) => 270,
@@ -74,6 +74,84 @@ pub fn calc_next_movement(start_point: Position, end_point: Position) -> i32 {
     (-1, -1) => 90,
     (0, -1) => 45,
     (1, -1) => 0,
-    _ => get_rand_from_range(0, 359),
+    _ => rng.get_rand_from_range(0, 359),
+  }
+}
+
+const UNREACHABLE: u32 = u32::MAX;
+
+/// Step distances from every tile to the player's tile, shared by all chasing zombies.
+/// Only recomputed when the player moves to another tile or the collision grid changes.
+#[derive(Default)]
+pub struct FlowField {
+  target: Option<Point2<i32>>,
+  revision: u64,
+  width: usize,
+  height: usize,
+  distances: Vec<u32>,
+}
+
+impl FlowField {
+  fn index(&self, tile: Point2<i32>) -> Option<usize> {
+    if tile.x >= 0 && (tile.x as usize) < self.width && tile.y >= 0 && (tile.y as usize) < self.height {
+      Some(tile.y as usize * self.width + tile.x as usize)
+    } else {
+      None
+    }
+  }
+
+  pub fn distance(&self, tile: Point2<i32>) -> Option<u32> {
+    self.index(tile)
+      .map(|idx| self.distances[idx])
+      .filter(|d| *d != UNREACHABLE)
+  }
+
+  /// Breadth-first fill from `target`, every step costs the same as in `calc_route`.
+  pub fn update(&mut self, target: Point2<i32>, grid: &CollisionGrid) {
+    if self.target == Some(target) && self.revision == grid.revision() && self.width == grid.width() {
+      return;
+    }
+    self.target = Some(target);
+    self.revision = grid.revision();
+    self.width = grid.width();
+    self.height = grid.height();
+    self.distances.clear();
+    self.distances.resize(self.width * self.height, UNREACHABLE);
+    let start = match self.index(target) {
+      Some(idx) => idx,
+      None => return,
+    };
+    self.distances[start] = 0;
+
+    let mut queue = VecDeque::new();
+    queue.push_back(target);
+    let mut neighbour_tiles = Vec::with_capacity(8);
+    while let Some(tile) = queue.pop_front() {
+      let next_distance = self.distances[self.index(tile).unwrap()] + 1;
+      neighbour_tiles.clear();
+      for neighbour in neighbours(tile, grid, &mut neighbour_tiles) {
+        let idx = self.index(*neighbour).unwrap();
+        if self.distances[idx] == UNREACHABLE {
+          self.distances[idx] = next_distance;
+          queue.push_back(*neighbour);
+        }
+      }
+    }
+  }
+
+  /// Direction towards the neighbour tile closest to the target, random when already there or cut off.
+  pub fn next_movement(&self, position: Position, rng: &mut GameRng) -> i32 {
+    let start = coords_to_tile(position);
+    let current = self.distance(start).unwrap_or(UNREACHABLE);
+    let best = NEIGHBOUR_OFFSETS.iter()
+      .map(|(x, y)| Point2::new(start.x + x, start.y + y))
+      .filter_map(|tile| self.distance(tile).map(|d| (d, tile)))
+      .filter(|(d, _)| *d < current)
+      .min_by_key(|(d, _)| *d);
+
+    match best {
+      Some((_, next_step)) => step_to_direction((next_step.x - start.x, next_step.y - start.y), rng),
+      None => step_to_direction((0, 0), rng),
+    }
   }
 }
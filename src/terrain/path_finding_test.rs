@@ -0,0 +1,53 @@
+#[test]
+fn flow_field_matches_astar_distance_test() {
+  use cgmath::Point2;
+  use crate::data::load_map_file;
+  use crate::game::constants::MAP_FILE_PATH;
+  use crate::graphics::coords_to_tile;
+  use crate::shaders::Position;
+  use crate::terrain::{collision_grid::CollisionGrid, map_objects::MapObjects, path_finding::{calc_route, FlowField}};
+
+  let map = load_map_file(MAP_FILE_PATH).unwrap();
+  let objects = MapObjects::from_map(&map, MAP_FILE_PATH).unwrap();
+  let grid = CollisionGrid::from_map(&map, &objects.blocked_tiles);
+  let player = Position::new(0.0, 0.0);
+  let mut flow_field = FlowField::default();
+  flow_field.update(coords_to_tile(player), &grid);
+  assert_eq!(flow_field.distance(coords_to_tile(player)), Some(0));
+
+  for zombie in [Position::new(300.0, 300.0), Position::new(-500.0, 120.0), Position::new(800.0, -650.0)].iter() {
+    let (_, cost) = calc_route(*zombie, player, &grid).unwrap();
+    assert_eq!(flow_field.distance(coords_to_tile(*zombie)), Some(cost as u32));
+  }
+  assert_eq!(flow_field.distance(Point2::new(objects.blocked_tiles[0][0], objects.blocked_tiles[0][1])), None);
+  assert_eq!(flow_field.distance(Point2::new(-1, 0)), None);
+}
+
+#[test]
+fn flow_field_moves_towards_target_test() {
+  use cgmath::Point2;
+  use crate::game::GameRng;
+  use crate::graphics::{coords_to_tile, direction_movement};
+  use crate::shaders::Position;
+  use crate::terrain::{collision_grid::CollisionGrid, path_finding::FlowField};
+
+  let mut rng = GameRng::new(1);
+  let mut grid = CollisionGrid::new(128, 128);
+  let mut flow_field = FlowField::default();
+  let target = Point2::new(20, 20);
+  flow_field.update(target, &grid);
+  assert_eq!(flow_field.distance(target), Some(0));
+  assert_eq!(flow_field.distance(Point2::new(22, 20)), Some(2));
+
+  grid.set_footprint(&[[21, 19], [21, 20], [21, 21]], true);
+  flow_field.update(target, &grid);
+  assert_eq!(flow_field.distance(Point2::new(22, 20)), Some(4), "Flow field should follow collision grid changes");
+
+  let mut position = Position::new(600.0, -400.0);
+  let start = flow_field.distance(coords_to_tile(position)).unwrap();
+  for _ in 0..200 {
+    let step = direction_movement(flow_field.next_movement(position, &mut rng) as f32);
+    position = position + Position::new(step.x * 5.0, step.y * 5.0);
+  }
+  assert!(flow_field.distance(coords_to_tile(position)).unwrap() < start);
+}
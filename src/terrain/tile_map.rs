@@ -1,60 +1,144 @@
-use tiled::Map;
+use std::path::{Path, PathBuf};
 
-use crate::data::{get_map_tile, load_map_file};
-use crate::game::constants::{MAP_FILE_PATH, TILES_PCS_H, TILES_PCS_W};
-use crate::shaders::TileMapData;
+use tiled::{Map, Tileset};
 
-const TILEMAP_BUF_LENGTH: usize = TILES_PCS_H * TILES_PCS_H;
-const QUARTER_BUF_LENGTH: usize = TILEMAP_BUF_LENGTH / 4;
+use crate::data::get_map_tile;
+use crate::game::error::GameError;
 
-fn calc_index(x_pos: usize, y_pos: usize) -> usize {
-  (y_pos * TILES_PCS_W) + x_pos
+/// Side of the tile index texture, the largest texture every OpenGL 3 driver has to support.
+pub const MAX_MAP_SIZE: usize = 1024;
+/// Visible tile layers the terrain shader blends together, bottom layer first.
+pub const MAX_TILE_LAYERS: usize = 4;
+/// Tilesets a map can use, each is uploaded to its own layer of the tile sheet texture.
+pub const MAX_TILESETS: usize = 4;
+/// Largest tileset image on either side, in pixels.
+pub const MAX_TILESHEET_SIZE: usize = 1024;
+
+/// Where the tiles of one tileset are found in its image.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TerrainTileset {
+  pub first_gid: u32,
+  pub image_path: PathBuf,
+  pub columns: u32,
+  pub tile_width: u32,
+  pub tile_height: u32,
+  pub margin: u32,
+  pub spacing: u32,
+  /// Width and height of the decoded image in pixels.
+  pub image_size: (u32, u32),
+  /// RGBA pixels of the image row by row, decoded when the map is loaded so drawing can't fail on them.
+  pub pixels: Vec<[u8; 4]>,
 }
 
-fn populate_tile_map<'a>(tiles: &'a mut Vec<TileMapData>, map: &Map) -> &'a mut Vec<TileMapData> {
-  for y_pos in 0..TILES_PCS_H {
-    for x_pos in 0..TILES_PCS_W {
-      let map_val = get_map_tile(map, 0, x_pos, y_pos) - 1;
-      let idx = calc_index(x_pos, y_pos);
-
-      if idx < QUARTER_BUF_LENGTH {
-        tiles[idx] =
-          TileMapData::new([map_val as f32, 0.0, 0.0, 0.0]);
-      } else if idx < QUARTER_BUF_LENGTH * 2 {
-        tiles[idx - QUARTER_BUF_LENGTH] =
-          TileMapData::new([tiles[idx - QUARTER_BUF_LENGTH].data[0], map_val as f32, 0.0, 0.0]);
-      } else if idx < QUARTER_BUF_LENGTH * 3 {
-        tiles[idx - QUARTER_BUF_LENGTH * 2] =
-          TileMapData::new([tiles[idx - QUARTER_BUF_LENGTH * 2].data[0], tiles[idx - QUARTER_BUF_LENGTH * 2].data[1], map_val as f32, 0.0]);
-      } else {
-        tiles[idx - QUARTER_BUF_LENGTH * 3] =
-          TileMapData::new([tiles[idx - QUARTER_BUF_LENGTH * 3].data[0], tiles[idx - QUARTER_BUF_LENGTH * 3].data[1], tiles[idx - QUARTER_BUF_LENGTH * 3].data[2], map_val as f32]);
-      }
+impl TerrainTileset {
+  /// The image source is resolved relative to the map file, like Tiled does.
+  fn from_tileset(tileset: &Tileset, map_path: &str) -> Result<TerrainTileset, GameError> {
+    let invalid = |reason: &str| GameError::parse(map_path, format!("tileset \"{}\" {}", tileset.name, reason));
+    let image = match tileset.images.as_slice() {
+      [image] => image,
+      _ => return Err(invalid("must use exactly one image")),
+    };
+    if image.width <= 0 || image.height <= 0 || image.width as usize > MAX_TILESHEET_SIZE || image.height as usize > MAX_TILESHEET_SIZE {
+      return Err(invalid(&format!("image is not between 1x1 and {}x{} pixels", MAX_TILESHEET_SIZE, MAX_TILESHEET_SIZE)));
+    }
+    let usable_width = (image.width as u32 + tileset.spacing).saturating_sub(tileset.margin * 2);
+    let columns = usable_width / (tileset.tile_width + tileset.spacing).max(1);
+    if tileset.tile_width == 0 || tileset.tile_height == 0 || columns == 0 {
+      return Err(invalid("has no tiles"));
+    }
+
+    let image_path = Path::new(map_path).parent().unwrap_or_else(|| Path::new("")).join(&image.source);
+    let image_name = image_path.to_string_lossy().to_string();
+    let decoded = image::open(&image_path)
+      .map_err(|e| match e {
+        image::ImageError::IoError(e) => GameError::io(&image_name, e),
+        e => GameError::parse(&image_name, e.to_string()),
+      })?
+      .to_rgba();
+    let image_size = decoded.dimensions();
+    if image_size.0 as usize > MAX_TILESHEET_SIZE || image_size.1 as usize > MAX_TILESHEET_SIZE {
+      return Err(GameError::parse(&image_name, format!("image is larger than {}x{} pixels", MAX_TILESHEET_SIZE, MAX_TILESHEET_SIZE)));
     }
+    let pixels = decoded.into_raw().chunks(4).map(|p| [p[0], p[1], p[2], p[3]]).collect();
+
+    Ok(TerrainTileset {
+      first_gid: tileset.first_gid,
+      image_path,
+      columns,
+      tile_width: tileset.tile_width,
+      tile_height: tileset.tile_height,
+      margin: tileset.margin,
+      spacing: tileset.spacing,
+      image_size,
+      pixels,
+    })
   }
-  tiles
 }
 
+/// Global tile ids of one layer row by row, 0 where the layer has no tile.
+pub struct TileLayer {
+  pub tiles: Vec<u16>,
+  pub opacity: f32,
+}
+
+/// Tile layers of the current map, the way the terrain shader reads them.
 pub struct Terrain {
-  pub tiles: Vec<TileMapData>,
-  pub tile_sets: [Map; 1],
-  pub curr_tile_set_idx: usize,
+  pub width: usize,
+  pub height: usize,
+  pub layers: Vec<TileLayer>,
+  /// Sorted by `first_gid`.
+  pub tilesets: Vec<TerrainTileset>,
+  /// Set when the tiles change, the draw system uploads them on the next frame.
+  pub is_dirty: bool,
 }
 
 impl Terrain {
-  pub fn new() -> Terrain {
-    let mut map_data = Vec::with_capacity(TILEMAP_BUF_LENGTH);
-
-    for _ in 0..TILEMAP_BUF_LENGTH {
-      map_data.push(TileMapData::new_empty());
+  /// Reads every visible tile layer. Hidden layers are left out, like in Tiled.
+  pub fn from_map(map: &Map, map_path: &str) -> Result<Terrain, GameError> {
+    let (width, height) = (map.width as usize, map.height as usize);
+    if width == 0 || height == 0 || width > MAX_MAP_SIZE || height > MAX_MAP_SIZE {
+      return Err(GameError::parse(map_path, format!("map size {}x{} is not between 1x1 and {}x{}", width, height, MAX_MAP_SIZE, MAX_MAP_SIZE)));
     }
 
-    let map_a = load_map_file(MAP_FILE_PATH);
+    let mut layers = vec![];
+    for (layer_idx, layer) in map.layers.iter().enumerate().filter(|(_, layer)| layer.visible) {
+      if layers.len() == MAX_TILE_LAYERS {
+        return Err(GameError::parse(map_path, format!("more than {} visible tile layers", MAX_TILE_LAYERS)));
+      }
+      let mut tiles = Vec::with_capacity(width * height);
+      for y_pos in 0..height {
+        for x_pos in 0..width {
+          let gid = get_map_tile(map, layer_idx, x_pos, y_pos)
+            .ok_or_else(|| GameError::missing_data(map_path, format!("tile ({}, {}) in layer \"{}\"", x_pos, y_pos, layer.name)))?;
+          if gid > u32::from(u16::MAX) {
+            return Err(GameError::parse(map_path, format!("tile id {} in layer \"{}\" is too large", gid, layer.name)));
+          }
+          tiles.push(gid as u16);
+        }
+      }
+      layers.push(TileLayer { tiles, opacity: layer.opacity });
+    }
+    if layers.is_empty() {
+      return Err(GameError::missing_data(map_path, "a visible tile layer".to_string()));
+    }
 
-    Terrain {
-      tiles: populate_tile_map(&mut map_data, &map_a).to_vec(),
-      tile_sets: [map_a],
-      curr_tile_set_idx: 0,
+    let mut tilesets = map.tilesets.iter()
+      .map(|tileset| TerrainTileset::from_tileset(tileset, map_path))
+      .collect::<Result<Vec<_>, _>>()?;
+    tilesets.sort_by_key(|tileset| tileset.first_gid);
+    if tilesets.is_empty() {
+      return Err(GameError::missing_data(map_path, "a tileset".to_string()));
+    }
+    if tilesets.len() > MAX_TILESETS {
+      return Err(GameError::parse(map_path, format!("more than {} tilesets", MAX_TILESETS)));
     }
+
+    Ok(Terrain {
+      width,
+      height,
+      layers,
+      tilesets,
+      is_dirty: true,
+    })
   }
 }
@@ -0,0 +1,95 @@
+#[test]
+fn terrain_follows_map_dimensions_test() {
+  use crate::terrain::tile_map::Terrain;
+
+  let tmx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.2" orientation="isometric" renderorder="left-down" width="3" height="2" tilewidth="32" tileheight="32">
+ <tileset firstgid="1" name="terrain" tilewidth="32" tileheight="32"><image source="assets/maps/terrain.png" width="1024" height="1024"/></tileset>
+ <layer id="1" name="Ground" width="3" height="2">
+  <data encoding="csv">
+1,2,3,
+4,5,6
+</data>
+ </layer>
+</map>"#;
+  let terrain = Terrain::from_map(&tiled::parse(tmx.as_bytes()).unwrap(), "test").unwrap();
+
+  assert_eq!((terrain.width, terrain.height), (3, 2));
+  assert_eq!(terrain.layers.len(), 1);
+  assert_eq!(terrain.layers[0].tiles, vec![4, 5, 6, 1, 2, 3]);
+  assert!(terrain.is_dirty);
+}
+
+#[test]
+fn visible_layers_keep_order_and_opacity_test() {
+  use crate::terrain::tile_map::Terrain;
+
+  let tmx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.2" orientation="isometric" renderorder="left-down" width="2" height="1" tilewidth="32" tileheight="32">
+ <tileset firstgid="1" name="terrain" tilewidth="32" tileheight="32"><image source="assets/maps/terrain.png" width="1024" height="1024"/></tileset>
+ <layer id="1" name="Ground" width="2" height="1"><data encoding="csv">1,1</data></layer>
+ <layer id="2" name="Hidden" width="2" height="1" visible="0"><data encoding="csv">7,7</data></layer>
+ <layer id="3" name="Road" width="2" height="1" opacity="0.5"><data encoding="csv">0,9</data></layer>
+</map>"#;
+  let terrain = Terrain::from_map(&tiled::parse(tmx.as_bytes()).unwrap(), "test").unwrap();
+
+  assert_eq!(terrain.layers.len(), 2);
+  assert_eq!((terrain.layers[0].tiles.clone(), terrain.layers[0].opacity), (vec![1, 1], 1.0));
+  assert_eq!((terrain.layers[1].tiles.clone(), terrain.layers[1].opacity), (vec![0, 9], 0.5));
+}
+
+#[test]
+fn unsupported_maps_are_rejected_test() {
+  use crate::terrain::tile_map::{MAX_MAP_SIZE, Terrain};
+
+  let tmx = format!(r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.2" orientation="isometric" renderorder="left-down" width="{}" height="300" tilewidth="32" tileheight="32">
+</map>"#, MAX_MAP_SIZE + 1);
+  let error = Terrain::from_map(&tiled::parse(tmx.as_bytes()).unwrap(), "test").err().unwrap().to_string();
+  assert!(error.contains("1025x300"), "Unexpected error: {}", error);
+
+  let layer = r#"<layer name="Decal" width="1" height="1"><data encoding="csv">1</data></layer>"#;
+  let tmx = format!(r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.2" orientation="isometric" renderorder="left-down" width="1" height="1" tilewidth="32" tileheight="32">
+{}</map>"#, layer.repeat(5));
+  let error = Terrain::from_map(&tiled::parse(tmx.as_bytes()).unwrap(), "test").err().unwrap().to_string();
+  assert!(error.contains("more than 4"), "Unexpected error: {}", error);
+}
+
+#[test]
+fn tilesets_are_read_from_the_map_test() {
+  use std::path::PathBuf;
+  use crate::terrain::tile_map::Terrain;
+
+  let map_with = |tilesets: &str| {
+    let tmx = format!(r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.2" orientation="isometric" renderorder="left-down" width="1" height="1" tilewidth="32" tileheight="32">
+{}
+ <layer id="1" name="Ground" width="1" height="1"><data encoding="csv">1</data></layer>
+</map>"#, tilesets);
+    tiled::parse(tmx.as_bytes()).unwrap()
+  };
+  let decals = r#"<tileset firstgid="1025" name="decals" tilewidth="16" tileheight="24" margin="2" spacing="4">
+  <image source="shape.png" width="150" height="100"/></tileset>"#;
+  let terrain = r#"<tileset firstgid="1" name="terrain" tilewidth="32" tileheight="32">
+  <image source="terrain.png" width="1024" height="1024"/></tileset>"#;
+
+  let loaded = Terrain::from_map(&map_with(&format!("{}{}", decals, terrain)), "assets/maps/test.tmx").unwrap();
+  let summary = loaded.tilesets.iter()
+    .map(|t| (t.first_gid, t.columns, t.tile_width, t.tile_height, t.margin, t.spacing))
+    .collect::<Vec<_>>();
+  assert_eq!(summary, vec![(1, 32, 32, 32, 0, 0), (1025, 7, 16, 24, 2, 4)]);
+  assert_eq!(loaded.tilesets[1].image_path, PathBuf::from("assets/maps/shape.png"));
+
+  let missing = r#"<tileset firstgid="1" name="lost" tilewidth="32" tileheight="32"><image source="lost.png" width="64" height="64"/></tileset>"#;
+  let error = Terrain::from_map(&map_with(missing), "assets/maps/test.tmx").err().unwrap().to_string();
+  assert!(error.contains("assets/maps/lost.png"), "Unexpected error: {}", error);
+
+  let broken = r#"<tileset firstgid="1" name="broken" tilewidth="32" tileheight="32"><image source="campaign.json" width="64" height="64"/></tileset>"#;
+  let error = Terrain::from_map(&map_with(broken), "assets/maps/test.tmx").err().unwrap().to_string();
+  assert!(error.contains("Could not parse assets/maps/campaign.json"), "Unexpected error: {}", error);
+
+  let collection = r#"<tileset firstgid="1" name="props" tilewidth="32" tileheight="32"><tile id="0"><image source="tree.png" width="32" height="32"/></tile></tileset>"#;
+  let error = Terrain::from_map(&map_with(collection), "assets/maps/test.tmx").err().unwrap().to_string();
+  assert!(error.contains("props"), "Unexpected error: {}", error);
+}
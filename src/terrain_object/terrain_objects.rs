@@ -1,32 +1,25 @@
+use specs::{Builder, shred::World, world::WorldExt};
 
-use crate::game::constants::{AMMO_POSITIONS, HOUSE_POSITIONS, TREE_POSITIONS};
-use crate::terrain_object::{TerrainObjectDrawable, TerrainTexture};
-use crate::graphics::set_position;
+use crate::game::components::WorldPosition;
+use crate::shaders::Position;
+use crate::terrain::map_objects::MapObjects;
+use crate::terrain_object::{TerrainObject, TerrainTexture};
 
-pub struct TerrainObjects {
-  pub objects: Vec<TerrainObjectDrawable>,
-}
-
-impl TerrainObjects {
-  pub fn new() -> TerrainObjects {
-    TerrainObjects {
-      objects: vec![
-        TerrainObjectDrawable::new(set_position(AMMO_POSITIONS[0][0], AMMO_POSITIONS[0][1]), TerrainTexture::Ammo),
-        TerrainObjectDrawable::new(set_position(AMMO_POSITIONS[1][0], AMMO_POSITIONS[1][1]), TerrainTexture::Ammo),
-        TerrainObjectDrawable::new(set_position(AMMO_POSITIONS[2][0], AMMO_POSITIONS[2][1]), TerrainTexture::Ammo),
-        TerrainObjectDrawable::new(set_position(AMMO_POSITIONS[3][0], AMMO_POSITIONS[3][1]), TerrainTexture::Ammo),
-        TerrainObjectDrawable::new(set_position(HOUSE_POSITIONS[0][0], HOUSE_POSITIONS[0][1]), TerrainTexture::House),
-        TerrainObjectDrawable::new(set_position(HOUSE_POSITIONS[1][0], HOUSE_POSITIONS[1][1]), TerrainTexture::House),
-        TerrainObjectDrawable::new(set_position(TREE_POSITIONS[0][0], TREE_POSITIONS[0][1]), TerrainTexture::Tree),
-        TerrainObjectDrawable::new(set_position(TREE_POSITIONS[1][0], TREE_POSITIONS[1][1]), TerrainTexture::Tree),
-        TerrainObjectDrawable::new(set_position(TREE_POSITIONS[2][0], TREE_POSITIONS[2][1]), TerrainTexture::Tree),
-        TerrainObjectDrawable::new(set_position(TREE_POSITIONS[3][0], TREE_POSITIONS[3][1]), TerrainTexture::Tree),
-        TerrainObjectDrawable::new(set_position(TREE_POSITIONS[4][0], TREE_POSITIONS[4][1]), TerrainTexture::Tree),
-      ]
-    }
+pub fn create_terrain_objects(world: &mut World, objects: &MapObjects) {
+  for pos in objects.ammo.iter() {
+    create_terrain_object(world, *pos, TerrainTexture::Ammo);
+  }
+  for pos in objects.houses.iter() {
+    create_terrain_object(world, *pos, TerrainTexture::House);
+  }
+  for pos in objects.trees.iter() {
+    create_terrain_object(world, *pos, TerrainTexture::Tree);
   }
 }
 
-impl specs::prelude::Component for TerrainObjects {
-  type Storage = specs::storage::VecStorage<TerrainObjects>;
+fn create_terrain_object(world: &mut World, position: Position, object_type: TerrainTexture) {
+  world.create_entity()
+    .with(TerrainObject::new(object_type))
+    .with(WorldPosition::new(position))
+    .build();
 }
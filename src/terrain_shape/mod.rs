@@ -1,54 +1,34 @@
 use cgmath::{Point2, Matrix2};
-use specs::{Read, ReadStorage, WriteStorage};
 
-use crate::character::controls::CharacterInputState;
-use crate::game::constants::{ASPECT_RATIO, VIEW_DISTANCE};
 use crate::gfx_app::{ColorFormat, DepthFormat};
-use crate::graphics::camera::CameraInputState;
-use crate::graphics::dimensions::{Dimensions, get_projection, get_view_matrix};
 use crate::graphics::mesh::{Geometry, RectangularTexturedMesh};
 use crate::graphics::orientation::Orientation;
 use crate::graphics::texture::{load_texture, Texture};
-use crate::shaders::{Position, Projection, static_element_pipeline, Time};
-use crate::terrain_shape::terrain_shape_objects::TerrainShapeObjects;
+use crate::shaders::{Position, Projection, static_element_pipeline, Time, ViewTranslation};
 
 pub mod terrain_shape_objects;
 
 const SHADER_VERT: &[u8] = include_bytes!("../shaders/static_element.v.glsl");
 const SHADER_FRAG: &[u8] = include_bytes!("../shaders/static_element.f.glsl");
 
-pub struct TerrainShapeDrawable {
-  projection: Projection,
-  pub position: Position,
-  previous_position: Position,
+pub struct TerrainShape {
   orientation: Orientation,
 }
 
-impl TerrainShapeDrawable {
-  pub fn new(position: Position, orientation: Orientation) -> TerrainShapeDrawable {
-    let view = get_view_matrix(VIEW_DISTANCE);
-    let projection = get_projection(view, ASPECT_RATIO);
-    TerrainShapeDrawable {
-      position,
-      previous_position: Position::origin(),
-      projection,
+impl TerrainShape {
+  pub fn new(orientation: Orientation) -> TerrainShape {
+    TerrainShape {
       orientation,
     }
   }
 
-  pub fn update(&mut self, world_to_clip: &Projection, ci: &CharacterInputState) {
-    self.projection = *world_to_clip;
-    self.position = self.position + ci.movement - self.previous_position;
-    self.previous_position = ci.movement;
-  }
-
   pub fn get_shape(&self) -> &Orientation {
     &self.orientation
   }
 }
 
-impl specs::prelude::Component for TerrainShapeDrawable {
-  type Storage = specs::storage::VecStorage<TerrainShapeDrawable>;
+impl specs::prelude::Component for TerrainShape {
+  type Storage = specs::storage::VecStorage<TerrainShape>;
 }
 
 pub struct TerrainShapeDrawSystem<R: gfx::Resources> {
@@ -109,6 +89,7 @@ impl<R: gfx::Resources> TerrainShapeDrawSystem<R> {
     let pipeline_data = static_element_pipeline::Data {
       vbuf: rect_mesh.mesh.vertex_buffer,
       position_cb: factory.create_constant_buffer(1),
+      view_cb: factory.create_constant_buffer(1),
       time_passed_cb: factory.create_constant_buffer(1),
       projection_cb: factory.create_constant_buffer(1),
       static_element_sheet: (rect_mesh.mesh.texture.raw, factory.create_sampler_linear()),
@@ -121,35 +102,21 @@ impl<R: gfx::Resources> TerrainShapeDrawSystem<R> {
     }
   }
 
+  pub fn set_pipeline(&mut self, pso: gfx::PipelineState<R, static_element_pipeline::Meta>) {
+    self.bundle.pso = pso;
+  }
+
   pub fn draw<C>(&self,
-                 drawable: &TerrainShapeDrawable,
+                 position: &Position,
+                 projection: &Projection,
                  time_passed: u64,
+                 view: &ViewTranslation,
                  encoder: &mut gfx::Encoder<R, C>)
     where C: gfx::CommandBuffer<R> {
-    encoder.update_constant_buffer(&self.bundle.data.projection_cb, &drawable.projection);
-    encoder.update_constant_buffer(&self.bundle.data.position_cb, &drawable.position);
+    encoder.update_constant_buffer(&self.bundle.data.projection_cb, projection);
+    encoder.update_constant_buffer(&self.bundle.data.position_cb, position);
+    encoder.update_constant_buffer(&self.bundle.data.view_cb, view);
     encoder.update_constant_buffer(&self.bundle.data.time_passed_cb, &Time::new(time_passed));
     self.bundle.encode(encoder);
   }
 }
-
-pub struct PreDrawSystem;
-
-impl<'a> specs::prelude::System<'a> for PreDrawSystem {
-  type SystemData = (ReadStorage<'a, CameraInputState>,
-                     ReadStorage<'a, CharacterInputState>,
-                     WriteStorage<'a, TerrainShapeObjects>,
-                     Read<'a, Dimensions>);
-
-  fn run(&mut self, (camera_input, character_input, mut terrain_shape_objects, dim): Self::SystemData) {
-    use specs::join::Join;
-
-    for (camera, ci, ts_obj) in (&camera_input, &character_input, &mut terrain_shape_objects).join() {
-      let world_to_clip = dim.world_to_projection(camera);
-
-      for o in &mut ts_obj.objects {
-        o.update(&world_to_clip, ci);
-      }
-    }
-  }
-}
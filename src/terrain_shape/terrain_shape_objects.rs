@@ -1,32 +1,27 @@
+use specs::{Builder, shred::World, world::WorldExt};
 
+use crate::game::components::WorldPosition;
 use crate::graphics::orientation::Orientation;
 use crate::graphics::set_position;
-use crate::terrain_shape::TerrainShapeDrawable;
+use crate::shaders::Position;
+use crate::terrain_shape::TerrainShape;
 
-pub struct TerrainShapeObjects {
-  pub objects: Vec<TerrainShapeDrawable>,
+/// `center` is the world position of the hill top, the slopes are laid out around it.
+pub fn create_small_hill(world: &mut World, center: Position) {
+  create_terrain_shape(world, center, 0, 0, Orientation::Normal);
+  create_terrain_shape(world, center, -1, -1, Orientation::DownLeft);
+  create_terrain_shape(world, center, 1, -1, Orientation::DownRight);
+  create_terrain_shape(world, center, -1, 1, Orientation::UpLeft);
+  create_terrain_shape(world, center, 1, 1, Orientation::UpRight);
+  create_terrain_shape(world, center, -2, 0, Orientation::Left);
+  create_terrain_shape(world, center, 2, 0, Orientation::Right);
+  create_terrain_shape(world, center, 0, -1, Orientation::Down);
+  create_terrain_shape(world, center, 0, 1, Orientation::Up);
 }
 
-impl TerrainShapeObjects {
-  pub fn new() -> TerrainShapeObjects {
-    TerrainShapeObjects {
-      objects: Vec::new(),
-    }
-  }
-
-  pub fn small_hill(&mut self, x: i32, y: i32) {
-    self.objects.push(TerrainShapeDrawable::new(set_position(x, y), Orientation::Normal));
-    self.objects.push(TerrainShapeDrawable::new(set_position(x - 1, y - 1), Orientation::DownLeft));
-    self.objects.push(TerrainShapeDrawable::new(set_position(x + 1, y - 1), Orientation::DownRight));
-    self.objects.push(TerrainShapeDrawable::new(set_position(x - 1, y + 1), Orientation::UpLeft));
-    self.objects.push(TerrainShapeDrawable::new(set_position(x + 1, y + 1), Orientation::UpRight));
-    self.objects.push(TerrainShapeDrawable::new(set_position(x - 2, y), Orientation::Left));
-    self.objects.push(TerrainShapeDrawable::new(set_position(x + 2, y), Orientation::Right));
-    self.objects.push(TerrainShapeDrawable::new(set_position(x, y - 1), Orientation::Down));
-    self.objects.push(TerrainShapeDrawable::new(set_position(x, y + 1), Orientation::Up));
-  }
-}
-
-impl specs::prelude::Component for TerrainShapeObjects {
-  type Storage = specs::storage::VecStorage<TerrainShapeObjects>;
+fn create_terrain_shape(world: &mut World, center: Position, x: i32, y: i32, orientation: Orientation) {
+  world.create_entity()
+    .with(TerrainShape::new(orientation))
+    .with(WorldPosition::new(center + set_position(x, y)))
+    .build();
 }
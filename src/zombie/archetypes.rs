@@ -0,0 +1,123 @@
+use json::JsonValue;
+
+use crate::game::constants::{ZOMBIE_DAMAGE, ZOMBIE_SHEET_PATH};
+use crate::game::error::GameError;
+use crate::game::GameRng;
+
+/// Stats and looks of one kind of zombie, loaded from the archetype data file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ZombieArchetype {
+  pub name: String,
+  pub max_health: f32,
+  pub idle_speed: f32,
+  pub chase_speed: f32,
+  /// How much of the speed is lost as health runs out, 0.0 keeps full speed until death.
+  pub wounded_slowdown: f32,
+  /// Falls back to the `aggro_radius` setting when not given.
+  pub aggro_radius: Option<f32>,
+  pub damage: usize,
+  pub sprite_sheet: String,
+  pub tint: [f32; 4],
+  pub spawn_weight: usize,
+  pub min_wave: usize,
+}
+
+impl Default for ZombieArchetype {
+  fn default() -> ZombieArchetype {
+    ZombieArchetype {
+      name: "walker".to_string(),
+      max_health: 1.0,
+      idle_speed: 1.0,
+      chase_speed: 2.0,
+      wounded_slowdown: 1.0,
+      aggro_radius: None,
+      damage: ZOMBIE_DAMAGE,
+      sprite_sheet: ZOMBIE_SHEET_PATH.to_string(),
+      tint: [1.0, 1.0, 1.0, 1.0],
+      spawn_weight: 1,
+      min_wave: 1,
+    }
+  }
+}
+
+impl ZombieArchetype {
+  pub fn speed(&self, is_chasing: bool, health_fraction: f32) -> f32 {
+    let base = if is_chasing { self.chase_speed } else { self.idle_speed };
+    base * (1.0 - self.wounded_slowdown * (1.0 - health_fraction))
+  }
+
+  fn from_json(name: &str, value: &JsonValue, path: &str) -> Result<ZombieArchetype, GameError> {
+    let defaults = ZombieArchetype::default();
+    let required = |field: &str| value[field].as_f32()
+      .ok_or_else(|| GameError::missing_data(path, format!("{}.{}", name, field)));
+    let invalid = |reason: String| GameError::parse(path, format!("{}: {}", name, reason));
+
+    let tint = if value["tint"].is_null() {
+      defaults.tint
+    } else {
+      let channels = value["tint"].members().map(JsonValue::as_f32).collect::<Option<Vec<f32>>>()
+        .filter(|c| c.len() == 3 || c.len() == 4)
+        .ok_or_else(|| invalid("tint must be a list of 3 or 4 numbers".to_string()))?;
+      [channels[0], channels[1], channels[2], channels.get(3).cloned().unwrap_or(1.0)]
+    };
+
+    let archetype = ZombieArchetype {
+      name: name.to_string(),
+      max_health: required("max_health")?,
+      idle_speed: required("idle_speed")?,
+      chase_speed: required("chase_speed")?,
+      wounded_slowdown: value["wounded_slowdown"].as_f32().unwrap_or(defaults.wounded_slowdown),
+      aggro_radius: value["aggro_radius"].as_f32(),
+      damage: value["damage"].as_usize()
+        .ok_or_else(|| GameError::missing_data(path, format!("{}.damage", name)))?,
+      sprite_sheet: value["sprite_sheet"].as_str().map(str::to_string).unwrap_or(defaults.sprite_sheet),
+      tint,
+      spawn_weight: value["spawn_weight"].as_usize().unwrap_or(defaults.spawn_weight),
+      min_wave: value["min_wave"].as_usize().unwrap_or(defaults.min_wave),
+    };
+
+    if archetype.max_health <= 0.0 || archetype.idle_speed < 0.0 || archetype.chase_speed <= 0.0 {
+      return Err(invalid("max_health and chase_speed must be positive and idle_speed not negative".to_string()));
+    }
+    if archetype.wounded_slowdown < 0.0 || archetype.wounded_slowdown > 1.0 {
+      return Err(invalid(format!("wounded_slowdown must be between 0 and 1, got {}", archetype.wounded_slowdown)));
+    }
+    if let Some(radius) = archetype.aggro_radius {
+      if radius <= 0.0 {
+        return Err(invalid(format!("aggro_radius must be positive, got {}", radius)));
+      }
+    }
+    Ok(archetype)
+  }
+}
+
+/// Parses a JSON object mapping archetype names to their stats, keeping the order of the file.
+pub fn parse_archetypes(value: &JsonValue, path: &str) -> Result<Vec<ZombieArchetype>, GameError> {
+  if !value.is_object() {
+    return Err(GameError::parse(path, "expected a JSON object of zombie archetypes".to_string()));
+  }
+  let archetypes = value.entries()
+    .map(|(name, archetype)| ZombieArchetype::from_json(name, archetype, path))
+    .collect::<Result<Vec<_>, _>>()?;
+  if !archetypes.iter().any(|a| a.min_wave <= 1 && a.spawn_weight > 0) {
+    return Err(GameError::parse(path, "at least one archetype must spawn from the first wave".to_string()));
+  }
+  Ok(archetypes)
+}
+
+/// Weighted random pick among the archetypes allowed in `wave`. Returns an index into `archetypes`.
+pub fn pick_archetype(archetypes: &[ZombieArchetype], wave: usize, rng: &mut GameRng) -> usize {
+  let allowed = |a: &ZombieArchetype| a.min_wave <= wave && a.spawn_weight > 0;
+  let total_weight: usize = archetypes.iter().filter(|a| allowed(a)).map(|a| a.spawn_weight).sum();
+  if total_weight == 0 {
+    return 0;
+  }
+  let mut roll = rng.get_rand_from_range(0, total_weight);
+  for (idx, archetype) in archetypes.iter().enumerate().filter(|(_, a)| allowed(a)) {
+    if roll < archetype.spawn_weight {
+      return idx;
+    }
+    roll -= archetype.spawn_weight;
+  }
+  0
+}
@@ -0,0 +1,48 @@
+#[test]
+fn archetype_file_test() {
+  use crate::data::load_zombie_archetypes;
+  use crate::game::constants::ZOMBIE_ARCHETYPES_PATH;
+  use crate::zombie::archetypes::ZombieArchetype;
+
+  let archetypes = load_zombie_archetypes(ZOMBIE_ARCHETYPES_PATH).unwrap();
+  let names = archetypes.iter().map(|a| a.name.as_str()).collect::<Vec<_>>();
+  assert_eq!(names, vec!["walker", "runner", "crawler", "brute"]);
+
+  let defaults = ZombieArchetype::default();
+  let walker = &archetypes[0];
+  assert_eq!((walker.max_health, walker.chase_speed, walker.damage), (defaults.max_health, defaults.chase_speed, defaults.damage));
+  assert!(archetypes[3].max_health > walker.max_health, "Brutes should be tougher than walkers");
+}
+
+#[test]
+fn invalid_archetypes_test() {
+  use crate::game::error::GameError;
+  use crate::zombie::archetypes::parse_archetypes;
+
+  let parse = |content: &str| parse_archetypes(&json::parse(content).unwrap(), "archetypes.json");
+
+  match parse(r#"{ "walker": { "max_health": 1.0, "idle_speed": 1.0, "damage": 1 } }"#) {
+    Err(GameError::MissingData { context, .. }) => assert_eq!(context, "walker.chase_speed"),
+    _ => panic!("Archetype without chase_speed should be a MissingData error"),
+  }
+  assert!(parse(r#"{ "walker": { "max_health": 1.0, "idle_speed": 1.0, "chase_speed": 2.0, "damage": 1, "tint": [1.0] } }"#).is_err());
+  assert!(parse(r#"{ "brute": { "max_health": 3.0, "idle_speed": 1.0, "chase_speed": 2.0, "damage": 2, "min_wave": 3 } }"#).is_err(),
+          "First wave needs something to spawn");
+}
+
+#[test]
+fn wounded_speed_and_wave_picks_test() {
+  use crate::game::GameRng;
+  use crate::zombie::archetypes::{pick_archetype, ZombieArchetype};
+
+  let walker = ZombieArchetype::default();
+  assert_eq!(walker.speed(true, 1.0), 2.0);
+  assert_eq!(walker.speed(true, 0.5), 1.0);
+  let brute = ZombieArchetype { name: "brute".to_string(), wounded_slowdown: 0.0, min_wave: 3, ..ZombieArchetype::default() };
+  assert_eq!(brute.speed(false, 0.1), brute.idle_speed);
+
+  let archetypes = vec![walker, brute];
+  let mut rng = GameRng::new(7);
+  assert!((0..50).all(|_| pick_archetype(&archetypes, 1, &mut rng) == 0), "Brutes should not spawn before wave 3");
+  assert!((0..50).any(|_| pick_archetype(&archetypes, 3, &mut rng) == 1));
+}
@@ -1,170 +1,164 @@
+use std::{collections::HashMap, fs};
+
 use cgmath::Point2;
-use specs::prelude::{Read, ReadStorage, WriteStorage};
+use gfx::handle::ShaderResourceView;
+use specs::prelude::{Read, ReadExpect, ReadStorage, Write, WriteExpect, WriteStorage};
 
-use crate::bullet::{BulletDrawable, bullets::Bullets};
 use crate::character::controls::CharacterInputState;
 use crate::critter::CritterData;
 use crate::data;
-use crate::game::constants::{ASPECT_RATIO, NORMAL_DEATH_SPRITE_OFFSET, SMALL_HILLS, SPRITE_OFFSET, VIEW_DISTANCE, ZOMBIE_SHEET_TOTAL_WIDTH, ZOMBIE_STILL_SPRITE_OFFSET};
-use crate::game::get_random_bool;
+use crate::game::{components::{Animation, Health, Velocity, WorldPosition}, config::GameConfig, error::GameError, GameRng};
+use crate::game::constants::{NORMAL_DEATH_SPRITE_OFFSET, SPRITE_OFFSET, ZOMBIE_SHEET_PATH, ZOMBIE_SHEET_TOTAL_WIDTH, ZOMBIE_STILL_SPRITE_OFFSET};
 use crate::gfx_app::{ColorFormat, DepthFormat};
-use crate::graphics::{camera::CameraInputState, can_move_to_tile, check_terrain_elevation, direction, direction_movement, direction_movement_180, distance, GameTime, get_nearest_random_tile_position, orientation::{Orientation, Stance}, orientation_to_direction, overlaps};
-use crate::graphics::dimensions::{Dimensions, get_projection, get_view_matrix};
+use crate::graphics::{can_move_to_tile, check_terrain_elevation, coords_to_tile, direction, direction_movement, direction_movement_180, distance, GameTime, get_nearest_random_tile_position, orientation::{Orientation, Stance}, orientation_to_direction};
 use crate::graphics::mesh::{Geometry, RectangularTexturedMesh};
 use crate::graphics::texture::{load_texture, Texture};
-use crate::shaders::{CharacterSheet, critter_pipeline, Position, Projection};
-use crate::terrain::path_finding::calc_next_movement;
-use crate::zombie::zombies::Zombies;
-
+use crate::shaders::{CharacterSheet, critter_pipeline, Position, Projection, Tint, ViewTranslation};
+use crate::terrain::{collision_grid::CollisionGrid, map_objects::MapObjects, path_finding::{calc_next_movement, FlowField}};
+use crate::zombie::archetypes::ZombieArchetype;
+
+pub mod archetypes;
+mod archetypes_test;
+pub mod waves;
+mod waves_test;
 pub mod zombies;
 
 const SHADER_VERT: &[u8] = include_bytes!("../shaders/character.v.glsl");
 const SHADER_FRAG: &[u8] = include_bytes!("../shaders/character.f.glsl");
 
-pub struct ZombieDrawable {
-  projection: Projection,
-  pub position: Position,
-  previous_position: Position,
-  previous_elevation: f32,
-  orientation: Orientation,
-  pub stance: Stance,
-  direction: Orientation,
+pub struct ZombieAi {
   last_decision: i64,
+  previous_elevation: f32,
   pub movement_direction: Point2<f32>,
-  zombie_idx: usize,
-  zombie_death_idx: usize,
-  movement_speed: f32,
-  health: f32,
+  /// Index into the configured zombie archetypes.
+  pub archetype: usize,
+  pub speed: f32,
+  pub max_health: f32,
+}
+
+/// What a zombie steers by on every tick, shared by all zombies.
+pub struct Surroundings<'a> {
+  pub player_position: Position,
+  pub hills: &'a [Position],
+  pub flow_field: &'a FlowField,
+  pub grid: &'a CollisionGrid,
 }
 
-impl ZombieDrawable {
-  pub fn new(position: Position) -> ZombieDrawable {
-    let view = get_view_matrix(VIEW_DISTANCE);
-    let projection = get_projection(view, ASPECT_RATIO);
-    ZombieDrawable {
-      projection,
-      position,
-      previous_position: Position::origin(),
-      orientation: Orientation::Left,
-      stance: Stance::Still,
-      direction: Orientation::Left,
+impl ZombieAi {
+  pub fn new(archetype: usize, speed: f32, max_health: f32) -> ZombieAi {
+    ZombieAi {
       last_decision: -2,
-      movement_direction: Point2::new(0.0, 0.0),
       previous_elevation: 0.0,
-      zombie_idx: 0,
-      zombie_death_idx: 0,
-      movement_speed: 0.0,
-      health: 1.0,
+      movement_direction: Point2::new(0.0, 0.0),
+      archetype,
+      speed,
+      max_health,
     }
   }
 
-  pub fn update(&mut self, world_to_clip: &Projection, ci: &CharacterInputState, game_time: u64) {
-    self.projection = *world_to_clip;
-
-    let elevated_pos_y = check_terrain_elevation(ci.movement - self.position, &SMALL_HILLS);
-
-    let offset_delta = ci.movement - self.previous_position;
-    self.previous_position = ci.movement;
+  pub fn update(&mut self, position: Position, health: &Health, animation: &mut Animation, surroundings: &Surroundings,
+                config: &GameConfig, game_time: u64, rng: &mut GameRng) -> Position {
+    let archetype = config.zombie_archetype(self.archetype);
+    let aggro_radius = archetype.aggro_radius.unwrap_or(config.aggro_radius);
+    let elevated_pos_y = check_terrain_elevation(position, surroundings.hills);
 
-    let x_y_distance_to_player = self.position - offset_delta;
+    let x_y_distance_to_player = position - surroundings.player_position;
 
     let distance_to_player = distance(x_y_distance_to_player.x().abs(), x_y_distance_to_player.y().abs());
 
-    let is_alive = self.health > 0.0 && self.stance != Stance::NormalDeath && self.stance != Stance::CriticalDeath;
+    let health_fraction = health.0 / self.max_health;
+    let mut movement_speed = 0.0;
 
-    if is_alive {
-      let zombie_pos = ci.movement - self.position;
-
-      if distance_to_player < 400.0 {
-        let dir = calc_next_movement(zombie_pos, self.previous_position) as f32;
-        self.direction = orientation_to_direction(dir);
+    if health.is_alive() && !animation.is_dead() {
+      if distance_to_player < aggro_radius {
+        let dir = surroundings.flow_field.next_movement(position, rng) as f32;
+        animation.direction = orientation_to_direction(dir);
         self.movement_direction = direction_movement(dir);
-        self.stance = Stance::Running;
-        self.movement_speed = 2.0 * self.health;
+        animation.stance = Stance::Running;
+        movement_speed = self.speed * archetype.speed(true, health_fraction);
       } else {
-        self.idle_direction_movement(zombie_pos, game_time as i64);
-        self.movement_speed = self.health;
+        self.idle_direction_movement(position, animation, surroundings.grid, game_time as i64, rng);
+        movement_speed = self.speed * archetype.speed(false, health_fraction);
       }
     } else {
       self.movement_direction = Point2::new(0.0, 0.0);
     }
 
-    self.position = Position::new(self.position.position[0] + self.movement_direction.x * self.movement_speed,
-                                  self.position.position[1] + (elevated_pos_y - self.previous_elevation) + self.movement_direction.y * self.movement_speed) + offset_delta;
+    let velocity = Position::new(self.movement_direction.x * movement_speed,
+                                 (elevated_pos_y - self.previous_elevation) + self.movement_direction.y * movement_speed);
     self.previous_elevation = elevated_pos_y;
-
+    velocity
   }
 
-  fn idle_direction_movement(&mut self, zombie_pos: Position, game_time: i64) {
-    if !can_move_to_tile(zombie_pos) {
+  fn idle_direction_movement(&mut self, zombie_pos: Position, animation: &mut Animation, grid: &CollisionGrid, game_time: i64, rng: &mut GameRng) {
+    if !can_move_to_tile(zombie_pos, grid) {
       let dir = direction(self.movement_direction, Point2::new(0.0, 0.0));
       self.movement_direction = direction_movement_180(self.movement_direction);
-      self.orientation = orientation_to_direction(dir);
-      self.direction = orientation_to_direction(dir);
+      animation.orientation = orientation_to_direction(dir);
+      animation.direction = orientation_to_direction(dir);
     }
 
     if self.last_decision + 2 < game_time {
-      self.stance = Stance::Walking;
+      animation.stance = Stance::Walking;
       self.last_decision = game_time;
-      let end_point = get_nearest_random_tile_position(zombie_pos);
-      let dir = calc_next_movement(zombie_pos, end_point) as f32;
+      let end_point = get_nearest_random_tile_position(zombie_pos, grid, rng);
+      let dir = calc_next_movement(zombie_pos, end_point, grid, rng) as f32;
       self.movement_direction = direction_movement(dir);
-      self.direction = orientation_to_direction(dir);
-    }
-  }
-
-  fn handle_bullet_hit(&mut self) {
-    self.health -= 0.5;
-    if self.health <= 0.0 {
-      self.stance =
-        if get_random_bool() {
-          Stance::NormalDeath
-        } else {
-          Stance::CriticalDeath
-        };
+      animation.direction = orientation_to_direction(dir);
     }
   }
+}
 
-  fn check_bullet_hits(&mut self, bullets: &[BulletDrawable]) {
-    bullets.iter().for_each(|bullet| {
-      if overlaps(self.position, bullet.position, 15.0, 15.0) && self.stance != Stance::NormalDeath && self.stance != Stance::CriticalDeath {
-        self.handle_bullet_hit()
-      }
-    });
-  }
-
-  pub fn update_alive_idx(&mut self, max_idx: usize) {
-    if self.zombie_idx < max_idx {
-      self.zombie_idx += 1;
-    } else {
-      self.zombie_idx = 0;
-    }
+impl Default for ZombieAi {
+  fn default() -> ZombieAi {
+    ZombieAi::new(0, 1.0, 1.0)
   }
+}
 
-  pub fn update_death_idx(&mut self, max_idx: usize) {
-    if self.zombie_death_idx < max_idx {
-      self.zombie_death_idx += 1;
-    }
-  }
+impl specs::prelude::Component for ZombieAi {
+  type Storage = specs::storage::VecStorage<ZombieAi>;
 }
 
 pub struct ZombieDrawSystem<R: gfx::Resources> {
   bundle: gfx::pso::bundle::Bundle<R, critter_pipeline::Data<R>>,
   data: Vec<CritterData>,
+  sprite_sheets: Vec<ShaderResourceView<R, [f32; 4]>>,
+  tints: Vec<Tint>,
+}
+
+/// Every archetype sheet must share the frame layout of the default zombie sheet.
+pub fn load_sprite_sheets<R, F>(factory: &mut F, archetypes: &[ZombieArchetype]) -> Result<Vec<ShaderResourceView<R, [f32; 4]>>, GameError>
+  where R: gfx::Resources, F: gfx::Factory<R> {
+  let mut loaded: HashMap<&str, ShaderResourceView<R, [f32; 4]>> = HashMap::new();
+  let mut sheets = Vec::with_capacity(archetypes.len());
+  for archetype in archetypes {
+    let path = archetype.sprite_sheet.as_str();
+    if !loaded.contains_key(path) {
+      let texture = if path == ZOMBIE_SHEET_PATH {
+        load_texture(factory, include_bytes!("../../assets/zombie.png"))
+      } else {
+        load_texture(factory, &fs::read(path).map_err(|e| GameError::io(path, e))?)
+      };
+      loaded.insert(path, texture);
+    }
+    sheets.push(loaded[path].clone());
+  }
+  Ok(sheets)
 }
 
 impl<R: gfx::Resources> ZombieDrawSystem<R> {
   pub fn new<F>(factory: &mut F,
                 rtv: gfx::handle::RenderTargetView<R, ColorFormat>,
-                dsv: gfx::handle::DepthStencilView<R, DepthFormat>) -> ZombieDrawSystem<R>
+                dsv: gfx::handle::DepthStencilView<R, DepthFormat>,
+                archetypes: &[ZombieArchetype]) -> Result<ZombieDrawSystem<R>, GameError>
     where F: gfx::Factory<R> {
     use gfx::traits::FactoryExt;
 
-    let zombie_bytes = include_bytes!("../../assets/zombie.png");
-    let char_texture = load_texture(factory, zombie_bytes);
+    let sprite_sheets = load_sprite_sheets(factory, archetypes)?;
+    let tints = archetypes.iter().map(|a| Tint { color: a.tint }).collect();
 
     let rect_mesh =
-      RectangularTexturedMesh::new(factory, Texture::new(char_texture, None), Geometry::Rectangle, Point2::new(25.0, 35.0), None, None, None);
+      RectangularTexturedMesh::new(factory, Texture::new(sprite_sheets[0].clone(), None), Geometry::Rectangle, Point2::new(25.0, 35.0), None, None, None);
 
     let pso =
       factory.create_pipeline_simple(SHADER_VERT, SHADER_FRAG, critter_pipeline::new())
@@ -174,40 +168,58 @@ impl<R: gfx::Resources> ZombieDrawSystem<R> {
       vbuf: rect_mesh.mesh.vertex_buffer,
       projection_cb: factory.create_constant_buffer(1),
       position_cb: factory.create_constant_buffer(1),
+      view_cb: factory.create_constant_buffer(1),
       character_sprite_cb: factory.create_constant_buffer(1),
+      tint_cb: factory.create_constant_buffer(1),
       charactersheet: (rect_mesh.mesh.texture.raw, factory.create_sampler_linear()),
       out_color: rtv,
       out_depth: dsv,
     };
 
-    let data = data::load_zombie();
+    let data = data::load_zombie()?;
 
-    ZombieDrawSystem {
+    Ok(ZombieDrawSystem {
       bundle: gfx::Bundle::new(rect_mesh.mesh.slice, pso, pipeline_data),
       data,
-    }
+      sprite_sheets,
+      tints,
+    })
+  }
+
+  pub fn set_pipeline(&mut self, pso: gfx::PipelineState<R, critter_pipeline::Meta>) {
+    self.bundle.pso = pso;
+  }
+
+  pub fn set_sprite_data(&mut self, data: Vec<CritterData>) {
+    self.data = data;
   }
 
-  fn get_next_sprite(&self, drawable: &mut ZombieDrawable) -> CharacterSheet {
+  /// `sprite_sheets` has the sheet of each archetype, as `load_sprite_sheets` returns them.
+  pub fn set_archetypes(&mut self, archetypes: &[ZombieArchetype], sprite_sheets: Vec<ShaderResourceView<R, [f32; 4]>>) {
+    self.tints = archetypes.iter().map(|a| Tint { color: a.tint }).collect();
+    self.sprite_sheets = sprite_sheets;
+  }
+
+  fn get_next_sprite(&self, drawable: &mut Animation) -> CharacterSheet {
     let sprite_idx = match drawable.stance {
       Stance::Still => {
-        drawable.direction as usize * 4 + drawable.zombie_idx
+        drawable.direction as usize * 4 + drawable.frame_idx
       }
       Stance::Walking if drawable.orientation != Orientation::Normal => {
-        drawable.direction as usize * 8 + drawable.zombie_idx + ZOMBIE_STILL_SPRITE_OFFSET
+        drawable.direction as usize * 8 + drawable.frame_idx + ZOMBIE_STILL_SPRITE_OFFSET
       }
       Stance::Running if drawable.orientation != Orientation::Normal => {
-        drawable.direction as usize * 8 + drawable.zombie_idx + ZOMBIE_STILL_SPRITE_OFFSET
+        drawable.direction as usize * 8 + drawable.frame_idx + ZOMBIE_STILL_SPRITE_OFFSET
       }
       Stance::NormalDeath if drawable.orientation != Orientation::Normal => {
-        drawable.direction as usize * 6 + drawable.zombie_death_idx + NORMAL_DEATH_SPRITE_OFFSET
+        drawable.direction as usize * 6 + drawable.death_frame_idx + NORMAL_DEATH_SPRITE_OFFSET
       }
       Stance::CriticalDeath if drawable.orientation != Orientation::Normal => {
-        drawable.direction as usize * 8 + drawable.zombie_death_idx
+        drawable.direction as usize * 8 + drawable.death_frame_idx
       }
       _ => {
         drawable.direction = drawable.orientation;
-        drawable.orientation as usize * 8 + drawable.zombie_idx + ZOMBIE_STILL_SPRITE_OFFSET
+        drawable.orientation as usize * 8 + drawable.frame_idx + ZOMBIE_STILL_SPRITE_OFFSET
       }
     } as usize;
 
@@ -228,36 +240,54 @@ impl<R: gfx::Resources> ZombieDrawSystem<R> {
   }
 
   pub fn draw<C>(&mut self,
-                 mut drawable: &mut ZombieDrawable,
+                 position: &Position,
+                 animation: &mut Animation,
+                 archetype: usize,
+                 projection: &Projection,
+                 view: &ViewTranslation,
                  encoder: &mut gfx::Encoder<R, C>)
     where C: gfx::CommandBuffer<R> {
-    encoder.update_constant_buffer(&self.bundle.data.projection_cb, &drawable.projection);
-    encoder.update_constant_buffer(&self.bundle.data.position_cb, &drawable.position);
+    encoder.update_constant_buffer(&self.bundle.data.projection_cb, projection);
+    encoder.update_constant_buffer(&self.bundle.data.position_cb, position);
+    encoder.update_constant_buffer(&self.bundle.data.view_cb, view);
+    let archetype = if archetype < self.sprite_sheets.len() { archetype } else { 0 };
+    self.bundle.data.charactersheet.0 = self.sprite_sheets[archetype].clone();
+    encoder.update_constant_buffer(&self.bundle.data.tint_cb, &self.tints[archetype]);
     encoder.update_constant_buffer(&self.bundle.data.character_sprite_cb,
-                                   &self.get_next_sprite(&mut drawable));
+                                   &self.get_next_sprite(animation));
     self.bundle.encode(encoder);
   }
 }
 
-pub struct PreDrawSystem;
+pub struct AiSystem;
 
-impl<'a> specs::prelude::System<'a> for PreDrawSystem {
-  type SystemData = (WriteStorage<'a, Zombies>,
-                     ReadStorage<'a, CameraInputState>,
+impl<'a> specs::prelude::System<'a> for AiSystem {
+  type SystemData = (WriteStorage<'a, ZombieAi>,
+                     ReadStorage<'a, WorldPosition>,
+                     WriteStorage<'a, Velocity>,
+                     ReadStorage<'a, Health>,
+                     WriteStorage<'a, Animation>,
                      ReadStorage<'a, CharacterInputState>,
-                     ReadStorage<'a, Bullets>,
-                     Read<'a, Dimensions>,
-                     Read<'a, GameTime>);
-
-  fn run(&mut self, (mut zombies, camera_input, character_input, bullets, dim, gt): Self::SystemData) {
+                     Read<'a, GameTime>,
+                     Read<'a, GameConfig>,
+                     Write<'a, FlowField>,
+                     ReadExpect<'a, CollisionGrid>,
+                     ReadExpect<'a, MapObjects>,
+                     WriteExpect<'a, GameRng>);
+
+  fn run(&mut self, (mut zombies, positions, mut velocities, healths, mut animations, character_input, gt, config, mut flow_field, grid, map_objects, mut rng): Self::SystemData) {
     use specs::join::Join;
 
-    for (zs, camera, ci, bs) in (&mut zombies, &camera_input, &character_input, &bullets).join() {
-      let world_to_clip = dim.world_to_projection(camera);
-
-      for z in &mut zs.zombies {
-        z.update(&world_to_clip, ci, gt.0);
-        z.check_bullet_hits(&bs.bullets);
+    for ci in (&character_input).join() {
+      flow_field.update(coords_to_tile(ci.position), &grid);
+      let surroundings = Surroundings {
+        player_position: ci.position,
+        hills: &map_objects.hills,
+        flow_field: &flow_field,
+        grid: &grid,
+      };
+      for (z, p, v, h, a) in (&mut zombies, &positions, &mut velocities, &healths, &mut animations).join() {
+        v.0 = z.update(p.current, h, a, &surroundings, &config, gt.0, &mut rng);
       }
     }
   }
@@ -0,0 +1,136 @@
+use specs::prelude::{Entities, LazyUpdate, Read, ReadStorage, Write, WriteExpect};
+
+use crate::character::controls::CharacterInputState;
+use crate::game::components::Animation;
+use crate::game::config::GameConfig;
+use crate::game::constants::{WAVE_BASE_COUNT, WAVE_BREATHER, WAVE_COUNT_INCREASE, WAVE_HEALTH_INCREASE, WAVE_MAX_SPEED,
+                             WAVE_MIN_SPAWN_DISTANCE, WAVE_SPAWN_INTERVAL, WAVE_SPEED_INCREASE};
+use crate::game::GameRng;
+use crate::graphics::{DeltaTime, distance};
+use crate::shaders::Position;
+use crate::zombie::{archetypes::pick_archetype, zombies::spawn_zombie, ZombieAi};
+
+/// Zombie count of a wave and the multipliers it applies to archetype health and speed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WaveStats {
+  pub count: usize,
+  pub health: f32,
+  pub speed: f32,
+}
+
+impl WaveStats {
+  pub fn for_wave(wave: usize) -> WaveStats {
+    let increase = wave.saturating_sub(1);
+    WaveStats {
+      count: WAVE_BASE_COUNT + WAVE_COUNT_INCREASE * increase,
+      health: 1.0 + WAVE_HEALTH_INCREASE * increase as f32,
+      speed: (1.0 + WAVE_SPEED_INCREASE * increase as f32).min(WAVE_MAX_SPEED),
+    }
+  }
+}
+
+/// Progress of the current wave. Zombies are released one at a time from the spawn points,
+/// and the next wave starts after a breather once every zombie of the current one is dead, removing the dead ones.
+pub struct ZombieWaves {
+  pub wave: usize,
+  pub remaining: usize,
+  pub spawn_points: Vec<Position>,
+  next_spawn: f64,
+  /// Seconds left until the next wave, if currently between waves.
+  pub breather: Option<f64>,
+}
+
+impl ZombieWaves {
+  pub fn with_spawn_points(spawn_points: Vec<Position>) -> ZombieWaves {
+    ZombieWaves {
+      wave: 1,
+      remaining: WaveStats::for_wave(1).count,
+      spawn_points,
+      next_spawn: 0.0,
+      breather: None,
+    }
+  }
+
+  /// Picks a random spawn point out of the player's sight, or any spawn point if all of them are close.
+  fn spawn_point(&self, player_position: Position, rng: &mut GameRng) -> Option<Position> {
+    let distant = self.spawn_points.iter()
+      .filter(|p| {
+        let d = **p - player_position;
+        distance(d.x().abs(), d.y().abs()) > WAVE_MIN_SPAWN_DISTANCE
+      })
+      .copied()
+      .collect::<Vec<_>>();
+    let candidates = if distant.is_empty() { &self.spawn_points } else { &distant };
+    if candidates.is_empty() {
+      None
+    } else {
+      Some(candidates[rng.get_rand_from_range(0, candidates.len())])
+    }
+  }
+
+  fn start_next_wave(&mut self) {
+    self.wave += 1;
+    self.remaining = WaveStats::for_wave(self.wave).count;
+    self.next_spawn = 0.0;
+    self.breather = None;
+  }
+}
+
+impl Default for ZombieWaves {
+  fn default() -> ZombieWaves {
+    ZombieWaves::with_spawn_points(vec![])
+  }
+}
+
+pub struct SpawnSystem;
+
+impl<'a> specs::prelude::System<'a> for SpawnSystem {
+  type SystemData = (Entities<'a>,
+                     Read<'a, LazyUpdate>,
+                     Write<'a, ZombieWaves>,
+                     ReadStorage<'a, ZombieAi>,
+                     ReadStorage<'a, Animation>,
+                     ReadStorage<'a, CharacterInputState>,
+                     Read<'a, DeltaTime>,
+                     Read<'a, GameConfig>,
+                     WriteExpect<'a, GameRng>);
+
+  fn run(&mut self, (entities, lazy_update, mut waves, zombies, animations, character_input, dt, config, mut rng): Self::SystemData) {
+    use specs::join::Join;
+
+    let player_position = match (&character_input).join().next() {
+      Some(ci) => ci.position,
+      None => return,
+    };
+
+    if waves.remaining > 0 {
+      waves.next_spawn -= dt.0;
+      if waves.next_spawn <= 0.0 {
+        if let Some(position) = waves.spawn_point(player_position, &mut rng) {
+          let stats = WaveStats::for_wave(waves.wave);
+          let archetype = pick_archetype(&config.zombie_archetypes, waves.wave, &mut rng);
+          let health = config.zombie_archetype(archetype).max_health * stats.health;
+          spawn_zombie(&entities, &lazy_update, position, archetype, health, stats.speed);
+        }
+        waves.remaining -= 1;
+        waves.next_spawn = WAVE_SPAWN_INTERVAL;
+      }
+      return;
+    }
+
+    if (&zombies, &animations).join().any(|(_, a)| !a.is_dead()) {
+      return;
+    }
+
+    let breather = waves.breather.unwrap_or(WAVE_BREATHER) - dt.0;
+    if breather <= 0.0 {
+      // The bodies of the finished wave go away so entities and draw calls don't pile up wave after wave.
+      for (e, _) in (&entities, &zombies).join() {
+        entities.delete(e).expect("Zombie delete error");
+      }
+      waves.start_next_wave();
+    } else {
+      waves.breather = Some(breather);
+    }
+  }
+}
@@ -0,0 +1,84 @@
+#[test]
+fn wave_stats_scale_test() {
+  use crate::game::constants::{WAVE_BASE_COUNT, WAVE_MAX_SPEED};
+  use crate::zombie::waves::WaveStats;
+
+  let first = WaveStats::for_wave(1);
+  let fifth = WaveStats::for_wave(5);
+  assert_eq!(first, WaveStats { count: WAVE_BASE_COUNT, health: 1.0, speed: 1.0 });
+  assert!(fifth.count > first.count && fifth.health > first.health && fifth.speed > first.speed);
+  assert_eq!(WaveStats::for_wave(100).speed, WAVE_MAX_SPEED);
+}
+
+#[cfg(test)]
+fn run_spawner(w: &mut specs::World, seconds: f64) {
+  use specs::{prelude::RunNow, world::WorldExt};
+  use crate::game::constants::FIXED_TIMESTEP;
+  use crate::graphics::DeltaTime;
+  use crate::zombie::waves::SpawnSystem;
+
+  *w.write_resource::<DeltaTime>() = DeltaTime(FIXED_TIMESTEP);
+  for _ in 0..(seconds / FIXED_TIMESTEP) as u64 {
+    SpawnSystem.run_now(w);
+    w.maintain();
+  }
+}
+
+#[test]
+fn next_wave_starts_after_breather_test() {
+  use specs::{join::Join, world::WorldExt};
+  use crate::game::components::Animation;
+  use crate::game::config::GameConfig;
+  use crate::game::constants::{RESOLUTION_X, RESOLUTION_Y, WAVE_BREATHER, WAVE_SPAWN_INTERVAL};
+  use crate::game::campaign::Campaign;
+  use crate::game::simulation::setup_world;
+  use crate::graphics::{dimensions::Dimensions, orientation::Stance};
+  use crate::zombie::{waves::{WaveStats, ZombieWaves}, ZombieAi};
+
+  let mut w = specs::World::new();
+  setup_world(&mut w, Dimensions::new(RESOLUTION_X as f32, RESOLUTION_Y as f32, 1.0, true), 5, &GameConfig::default(), &Campaign::default()).unwrap();
+  assert_eq!(w.read_storage::<ZombieAi>().count(), 0);
+
+  let first_wave = WaveStats::for_wave(1).count;
+  run_spawner(&mut w, WAVE_SPAWN_INTERVAL * first_wave as f64);
+  assert_eq!(w.read_storage::<ZombieAi>().count(), first_wave);
+  assert_eq!(w.read_resource::<ZombieWaves>().remaining, 0);
+
+  run_spawner(&mut w, WAVE_BREATHER + 1.0);
+  assert_eq!(w.read_resource::<ZombieWaves>().wave, 1, "Wave should not end while zombies are alive");
+
+  for a in (&mut w.write_storage::<Animation>()).join() {
+    a.stance = Stance::NormalDeath;
+  }
+  run_spawner(&mut w, 1.0);
+  assert!(w.read_resource::<ZombieWaves>().breather.is_some());
+  run_spawner(&mut w, WAVE_BREATHER);
+  let waves = w.read_resource::<ZombieWaves>();
+  assert_eq!(waves.wave, 2);
+  assert!(waves.breather.is_none());
+  assert!(w.read_storage::<ZombieAi>().count() < first_wave, "Dead zombies of the first wave should be removed");
+}
+
+#[test]
+fn zombies_spawn_away_from_player_test() {
+  use specs::{join::Join, world::WorldExt};
+  use crate::game::components::WorldPosition;
+  use crate::game::config::GameConfig;
+  use crate::game::constants::{RESOLUTION_X, RESOLUTION_Y, WAVE_SPAWN_INTERVAL};
+  use crate::game::campaign::Campaign;
+  use crate::game::simulation::setup_world;
+  use crate::graphics::dimensions::Dimensions;
+  use crate::shaders::Position;
+  use crate::zombie::{waves::ZombieWaves, ZombieAi};
+
+  let mut w = specs::World::new();
+  setup_world(&mut w, Dimensions::new(RESOLUTION_X as f32, RESOLUTION_Y as f32, 1.0, true), 5, &GameConfig::default(), &Campaign::default()).unwrap();
+  w.insert(ZombieWaves::with_spawn_points(vec![Position::new(10.0, 10.0), Position::new(900.0, 0.0)]));
+
+  run_spawner(&mut w, WAVE_SPAWN_INTERVAL * 4.0);
+  let zombies = w.read_storage::<ZombieAi>();
+  let positions = w.read_storage::<WorldPosition>();
+  let spawned = (&zombies, &positions).join().map(|(_, p)| p.current).collect::<Vec<_>>();
+  assert!(!spawned.is_empty());
+  assert!(spawned.iter().all(|p| *p == Position::new(900.0, 0.0)), "Spawn point next to the player should be skipped");
+}
@@ -1,76 +1,24 @@
+use specs::{Builder, Entity, prelude::{Entities, LazyUpdate}, shred::World, world::WorldExt};
 
+use crate::game::components::{Animation, Collider, Health, Velocity, WorldPosition};
+use crate::graphics::orientation::{Orientation, Stance};
 use crate::shaders::Position;
-use crate::zombie::ZombieDrawable;
+use crate::zombie::ZombieAi;
 
-pub struct Zombies {
-  pub zombies: Vec<ZombieDrawable>,
+fn with_zombie_components<B: Builder>(builder: B, position: Position, archetype: usize, health: f32, speed: f32) -> B {
+  builder
+    .with(ZombieAi::new(archetype, speed, health))
+    .with(WorldPosition::new(position))
+    .with(Velocity(Position::origin()))
+    .with(Health(health))
+    .with(Animation::new(Orientation::Left, Stance::Still))
+    .with(Collider::new(15.0, 15.0))
 }
 
-impl Zombies {
-  pub fn new() -> Zombies {
-    Zombies {
-      zombies: vec![
-        // 1
-        ZombieDrawable::new(Position::new(500.0, 40.0)),
-        ZombieDrawable::new(Position::new(-500.0, 40.0)),
-        ZombieDrawable::new(Position::new(40.0, 500.0)),
-        ZombieDrawable::new(Position::new(40.0, -500.0)),
-        ZombieDrawable::new(Position::new(300.0, -300.0)),
-        ZombieDrawable::new(Position::new(-300.0, -300.0)),
-        ZombieDrawable::new(Position::new(300.0, 300.0)),
-        ZombieDrawable::new(Position::new(-300.0, 300.0)),
-        ZombieDrawable::new(Position::new(500.0, -500.0)),
-        ZombieDrawable::new(Position::new(-500.0, -500.0)),
-        ZombieDrawable::new(Position::new(-500.0, 500.0)),
-        ZombieDrawable::new(Position::new(500.0, 500.0)),
-        ZombieDrawable::new(Position::new(600.0, -600.0)),
-        ZombieDrawable::new(Position::new(-600.0, -600.0)),
-        ZombieDrawable::new(Position::new(-600.0, 600.0)),
-        ZombieDrawable::new(Position::new(600.0, 600.0)),
-        ZombieDrawable::new(Position::new(650.0, -650.0)),
-        ZombieDrawable::new(Position::new(-650.0, -650.0)),
-        ZombieDrawable::new(Position::new(-650.0, 650.0)),
-        ZombieDrawable::new(Position::new(650.0, 650.0)),
-        // 2
-        ZombieDrawable::new(Position::new(700.0, 60.0)),
-        ZombieDrawable::new(Position::new(-900.0, 60.0)),
-        ZombieDrawable::new(Position::new(60.0, 700.0)),
-        ZombieDrawable::new(Position::new(60.0, -700.0)),
-        // 3
-        ZombieDrawable::new(Position::new(750.0, 60.0)),
-        ZombieDrawable::new(Position::new(-750.0, 60.0)),
-        ZombieDrawable::new(Position::new(60.0, 750.0)),
-        ZombieDrawable::new(Position::new(60.0, -750.0)),
-        // 4
-        ZombieDrawable::new(Position::new(800.0, 160.0)),
-        ZombieDrawable::new(Position::new(-1000.0, 160.0)),
-        ZombieDrawable::new(Position::new(160.0, 800.0)),
-        ZombieDrawable::new(Position::new(160.0, -800.0)),
-        // 5
-        ZombieDrawable::new(Position::new(900.0, 10.0)),
-        ZombieDrawable::new(Position::new(-900.0, 10.0)),
-        ZombieDrawable::new(Position::new(10.0, 900.0)),
-        ZombieDrawable::new(Position::new(10.0, -900.0)),
-        // 6
-        ZombieDrawable::new(Position::new(1000.0, 10.0)),
-        ZombieDrawable::new(Position::new(-1000.0, 10.0)),
-        ZombieDrawable::new(Position::new(10.0, 1000.0)),
-        ZombieDrawable::new(Position::new(10.0, -1000.0)),
-        // 7
-        ZombieDrawable::new(Position::new(1100.0, 10.0)),
-        ZombieDrawable::new(Position::new(-1100.0, 10.0)),
-        ZombieDrawable::new(Position::new(10.0, 1100.0)),
-        ZombieDrawable::new(Position::new(10.0, -1100.0)),
-        // 8
-        ZombieDrawable::new(Position::new(1200.0, 10.0)),
-        ZombieDrawable::new(Position::new(-1200.0, 10.0)),
-        ZombieDrawable::new(Position::new(10.0, 1200.0)),
-        ZombieDrawable::new(Position::new(10.0, -1200.0)),
-      ]
-    }
-  }
+pub fn create_zombie(world: &mut World, position: Position) -> Entity {
+  with_zombie_components(world.create_entity(), position, 0, 1.0, 1.0).build()
 }
 
-impl specs::prelude::Component for Zombies {
-  type Storage = specs::storage::VecStorage<Zombies>;
+pub fn spawn_zombie(entities: &Entities, lazy_update: &LazyUpdate, position: Position, archetype: usize, health: f32, speed: f32) {
+  with_zombie_components(lazy_update.create_entity(entities), position, archetype, health, speed).build();
 }